@@ -1,6 +1,6 @@
 //! Define the [Ast] representation of [serde] serialization.
 
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 use serde::{
     ser::{
@@ -10,13 +10,73 @@ use serde::{
     Serialize,
 };
 
+use crate::{intern::intern_name, pretty::Printer};
+
 /// Represents an empty type. This can never be constructed.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum Final {}
 
+/// Name of a type, variant or field.
+///
+/// Names are usually borrowed from the serialized type, but can be owned when an [Ast] is built
+/// or decoded at runtime. Owned names are [interned](crate::intern) when serialized, since
+/// [serde::Serializer] requires `&'static str`.
+pub type Name = Cow<'static, str>;
+
 /// Define a closed [Ast] without any injected type extension.
 pub type Ast = XAst<Final>;
 
+/// Define an [XAst] borrowing strings and bytes from the input it was deserialized from.
+///
+/// Deserializing an [AstRef] keeps the strings and bytes the [serde::Deserializer] borrows from
+/// its input as [Borrowed] extension nodes, and copies the transient ones, e.g. strings with
+/// escape sequences in JSON, into [XAst::Str] and [XAst::Bytes].
+///
+/// Serializing always hands transient slices to [serde::Serializer], so [to_ast](crate::to_ast)
+/// can only build an owned [Ast].
+///
+/// ```
+/// # use serde::Deserialize;
+/// # use serde_ast::ast::{AstRef, Borrowed, Seq, XAst};
+/// let json = r#"["borrowed","copied\n"]"#;
+/// let ast = AstRef::deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap();
+/// let XAst::Seq { ops, .. } = &ast else {
+///     panic!("expected a sequence");
+/// };
+/// let [Seq::Element { value: a }, Seq::Element { value: b }] = ops.as_slice() else {
+///     panic!("expected two elements");
+/// };
+/// assert_eq!(**a, XAst::X(Borrowed::Str("borrowed")));
+/// assert_eq!(**b, XAst::Str("copied\n".to_string()));
+/// assert_eq!(serde_json::to_string(&ast).unwrap(), json);
+/// ```
+pub type AstRef<'a> = XAst<Borrowed<'a>>;
+
+/// Extension of [AstRef] for data borrowed from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Borrowed<'a> {
+    /// Borrowed [XAst::Str].
+    Str(&'a str),
+    /// Borrowed [XAst::Bytes].
+    Bytes(&'a [u8]),
+}
+
+impl<'a> From<Borrowed<'a>> for Ast {
+    fn from(borrowed: Borrowed<'a>) -> Self {
+        match borrowed {
+            Borrowed::Str(v) => XAst::Str(v.to_owned()),
+            Borrowed::Bytes(v) => XAst::Bytes(v.to_owned()),
+        }
+    }
+}
+
+impl AstRef<'_> {
+    /// Copy the borrowed data to build an owned [Ast].
+    pub fn into_owned(self) -> Ast {
+        self.map_x(Ast::from)
+    }
+}
+
 /// Represent calls made to [serde::Serializer] during serialization.
 ///
 /// This is an extensible
@@ -33,6 +93,8 @@ pub enum XAst<X> {
     I32(i32),
     /// [serde::Serializer::serialize_i64]
     I64(i64),
+    /// [serde::Serializer::serialize_i128]
+    I128(i128),
 
     /// [serde::Serializer::serialize_u8]
     U8(u8),
@@ -42,6 +104,8 @@ pub enum XAst<X> {
     U32(u32),
     /// [serde::Serializer::serialize_u64]
     U64(u64),
+    /// [serde::Serializer::serialize_u128]
+    U128(u128),
 
     /// [serde::Serializer::serialize_f32]
     F32(f32),
@@ -63,32 +127,32 @@ pub enum XAst<X> {
     /// [serde::Serializer::serialize_unit]
     Unit,
     /// [serde::Serializer::serialize_unit_struct]
-    UnitStruct(&'static str),
+    UnitStruct(Name),
     /// [serde::Serializer::serialize_unit_variant]
     UnitVariant {
         /// name
-        name: &'static str,
+        name: Name,
         /// variant_index
         variant_index: u32,
         /// variant
-        variant: &'static str,
+        variant: Name,
     },
 
     /// [serde::Serializer::serialize_newtype_struct]
     NewtypeStruct {
         /// name
-        name: &'static str,
+        name: Name,
         /// value
         value: Box<XAst<X>>,
     },
     /// [serde::Serializer::serialize_newtype_variant]
     NewtypeVariant {
         /// name
-        name: &'static str,
+        name: Name,
         /// variant_index
         variant_index: u32,
         /// variant
-        variant: &'static str,
+        variant: Name,
         /// value
         value: Box<XAst<X>>,
     },
@@ -111,7 +175,7 @@ pub enum XAst<X> {
     /// [serde::Serializer::serialize_tuple_struct]
     TupleStruct {
         /// name
-        name: &'static str,
+        name: Name,
         /// len
         len: usize,
         /// [serde::ser::SerializeTupleStruct] operations
@@ -120,11 +184,11 @@ pub enum XAst<X> {
     /// [serde::Serializer::serialize_tuple_variant]
     TupleVariant {
         /// name
-        name: &'static str,
+        name: Name,
         /// variant_index
         variant_index: u32,
         /// variant
-        variant: &'static str,
+        variant: Name,
         /// len
         len: usize,
         /// [serde::ser::SerializeTupleVariant] operations
@@ -141,7 +205,7 @@ pub enum XAst<X> {
     /// [serde::Serializer::serialize_struct]
     Struct {
         /// name
-        name: &'static str,
+        name: Name,
         /// len
         len: usize,
         /// [serde::ser::SerializeStruct] operations
@@ -150,17 +214,25 @@ pub enum XAst<X> {
     /// [serde::Serializer::serialize_struct_variant]
     StructVariant {
         /// name
-        name: &'static str,
+        name: Name,
         /// variant_index
         variant_index: u32,
         /// variant
-        variant: &'static str,
+        variant: Name,
         /// len
         len: usize,
         /// [serde::ser::SerializeStructVariant] operations
         ops: Vec<StructVariant<X>>,
     },
 
+    /// A value serializing differently depending on [serde::Serializer::is_human_readable].
+    Readability {
+        /// value serialized by a human-readable serializer
+        human_readable: Box<XAst<X>>,
+        /// value serialized by a compact serializer
+        compact: Box<XAst<X>>,
+    },
+
     /// Allow arbitrary extensions of this enum by injecting an extension type.
     X(X),
 }
@@ -170,11 +242,207 @@ where
     X: std::fmt::Debug,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:#?}", self)
+        Printer::new().display(self).fmt(f)
+    }
+}
+
+impl<X> XAst<X> {
+    /// Replace every extension node [XAst::X] with the node returned by `f`.
+    pub fn map_x<Y>(self, mut f: impl FnMut(X) -> XAst<Y>) -> XAst<Y> {
+        self.map_x_with(&mut f)
+    }
+
+    fn map_x_with<Y, F>(self, f: &mut F) -> XAst<Y>
+    where
+        F: FnMut(X) -> XAst<Y>,
+    {
+        let mut map = |value: Box<XAst<X>>| Box::new(value.map_x_with(f));
+        match self {
+            Self::Bool(v) => XAst::Bool(v),
+            Self::I8(v) => XAst::I8(v),
+            Self::I16(v) => XAst::I16(v),
+            Self::I32(v) => XAst::I32(v),
+            Self::I64(v) => XAst::I64(v),
+            Self::I128(v) => XAst::I128(v),
+            Self::U8(v) => XAst::U8(v),
+            Self::U16(v) => XAst::U16(v),
+            Self::U32(v) => XAst::U32(v),
+            Self::U64(v) => XAst::U64(v),
+            Self::U128(v) => XAst::U128(v),
+            Self::F32(v) => XAst::F32(v),
+            Self::F64(v) => XAst::F64(v),
+            Self::Char(v) => XAst::Char(v),
+            Self::Str(v) => XAst::Str(v),
+            Self::Bytes(v) => XAst::Bytes(v),
+            Self::None => XAst::None,
+            Self::Some(v) => XAst::Some(map(v)),
+            Self::Unit => XAst::Unit,
+            Self::UnitStruct(name) => XAst::UnitStruct(name),
+            Self::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            } => XAst::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            },
+            Self::NewtypeStruct { name, value } => XAst::NewtypeStruct {
+                name,
+                value: map(value),
+            },
+            Self::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value,
+            } => XAst::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value: map(value),
+            },
+            Self::Seq { len, ops } => XAst::Seq {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        Seq::Element { value } => Seq::Element { value: map(value) },
+                    })
+                    .collect(),
+            },
+            Self::Tuple { len, ops } => XAst::Tuple {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        Tuple::Element { value } => Tuple::Element { value: map(value) },
+                    })
+                    .collect(),
+            },
+            Self::TupleStruct { name, len, ops } => XAst::TupleStruct {
+                name,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        TupleStruct::Field { value } => TupleStruct::Field { value: map(value) },
+                    })
+                    .collect(),
+            },
+            Self::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => XAst::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        TupleVariant::Field { value } => TupleVariant::Field { value: map(value) },
+                    })
+                    .collect(),
+            },
+            Self::Map { len, ops } => XAst::Map {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        Map::Key { key } => Map::Key { key: map(key) },
+                        Map::Value { value } => Map::Value { value: map(value) },
+                    })
+                    .collect(),
+            },
+            Self::Struct { name, len, ops } => XAst::Struct {
+                name,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        Struct::Field { key, value } => Struct::Field {
+                            key,
+                            value: map(value),
+                        },
+                        Struct::Skip { key } => Struct::Skip { key },
+                    })
+                    .collect(),
+            },
+            Self::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        StructVariant::Field { key, value } => StructVariant::Field {
+                            key,
+                            value: map(value),
+                        },
+                        StructVariant::Skip { key } => StructVariant::Skip { key },
+                    })
+                    .collect(),
+            },
+            Self::Readability {
+                human_readable,
+                compact,
+            } => XAst::Readability {
+                human_readable: map(human_readable),
+                compact: map(compact),
+            },
+            Self::X(x) => f(x),
+        }
     }
 }
 
-impl Serialize for XAst<Final> {
+/// Serialize an extension node [XAst::X] of [XAst].
+///
+/// Implementing this for an extension type enables [Serialize] for the [XAst] using it.
+pub trait SerializeExtension {
+    /// Serialize this extension node into `serializer`.
+    ///
+    /// The node replaces a single value, so exactly one value should be serialized.
+    fn serialize_extension<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer;
+}
+
+impl SerializeExtension for Borrowed<'_> {
+    fn serialize_extension<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Str(v) => serializer.serialize_str(v),
+            Self::Bytes(v) => serializer.serialize_bytes(v),
+        }
+    }
+}
+
+impl SerializeExtension for Final {
+    fn serialize_extension<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match *self {}
+    }
+}
+
+impl<X> Serialize for XAst<X>
+where
+    X: SerializeExtension,
+{
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -185,10 +453,12 @@ impl Serialize for XAst<Final> {
             Self::I16(v) => serializer.serialize_i16(*v),
             Self::I32(v) => serializer.serialize_i32(*v),
             Self::I64(v) => serializer.serialize_i64(*v),
+            Self::I128(v) => serializer.serialize_i128(*v),
             Self::U8(v) => serializer.serialize_u8(*v),
             Self::U16(v) => serializer.serialize_u16(*v),
             Self::U32(v) => serializer.serialize_u32(*v),
             Self::U64(v) => serializer.serialize_u64(*v),
+            Self::U128(v) => serializer.serialize_u128(*v),
             Self::F32(v) => serializer.serialize_f32(*v),
             Self::F64(v) => serializer.serialize_f64(*v),
             Self::Char(v) => serializer.serialize_char(*v),
@@ -197,19 +467,30 @@ impl Serialize for XAst<Final> {
             Self::None => serializer.serialize_none(),
             Self::Some(v) => serializer.serialize_some(v),
             Self::Unit => serializer.serialize_unit(),
-            Self::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            Self::UnitStruct(name) => serializer.serialize_unit_struct(intern_name(name)),
             Self::UnitVariant {
                 name,
                 variant_index,
                 variant,
-            } => serializer.serialize_unit_variant(name, *variant_index, variant),
-            Self::NewtypeStruct { name, value } => serializer.serialize_newtype_struct(name, value),
+            } => serializer.serialize_unit_variant(
+                intern_name(name),
+                *variant_index,
+                intern_name(variant),
+            ),
+            Self::NewtypeStruct { name, value } => {
+                serializer.serialize_newtype_struct(intern_name(name), value)
+            }
             Self::NewtypeVariant {
                 name,
                 variant_index,
                 variant,
                 value,
-            } => serializer.serialize_newtype_variant(name, *variant_index, variant, value),
+            } => serializer.serialize_newtype_variant(
+                intern_name(name),
+                *variant_index,
+                intern_name(variant),
+                value,
+            ),
             Self::Seq { len, ops } => {
                 let mut s = serializer.serialize_seq(*len)?;
                 for op in ops {
@@ -229,7 +510,7 @@ impl Serialize for XAst<Final> {
                 s.end()
             }
             Self::TupleStruct { name, len, ops } => {
-                let mut s = serializer.serialize_tuple_struct(name, *len)?;
+                let mut s = serializer.serialize_tuple_struct(intern_name(name), *len)?;
                 for op in ops {
                     match op {
                         TupleStruct::Field { value } => s.serialize_field(value)?,
@@ -244,8 +525,12 @@ impl Serialize for XAst<Final> {
                 len,
                 ops,
             } => {
-                let mut s =
-                    serializer.serialize_tuple_variant(name, *variant_index, variant, *len)?;
+                let mut s = serializer.serialize_tuple_variant(
+                    intern_name(name),
+                    *variant_index,
+                    intern_name(variant),
+                    *len,
+                )?;
                 for op in ops {
                     match op {
                         TupleVariant::Field { value } => s.serialize_field(value)?,
@@ -264,11 +549,13 @@ impl Serialize for XAst<Final> {
                 s.end()
             }
             Self::Struct { name, len, ops } => {
-                let mut s = serializer.serialize_struct(name, *len)?;
+                let mut s = serializer.serialize_struct(intern_name(name), *len)?;
                 for op in ops {
                     match op {
-                        Struct::Field { key, value } => s.serialize_field(key, value)?,
-                        Struct::Skip { key } => s.skip_field(key)?,
+                        Struct::Field { key, value } => {
+                            s.serialize_field(intern_name(key), value)?
+                        }
+                        Struct::Skip { key } => s.skip_field(intern_name(key))?,
                     }
                 }
                 s.end()
@@ -280,17 +567,30 @@ impl Serialize for XAst<Final> {
                 len,
                 ops,
             } => {
-                let mut s =
-                    serializer.serialize_struct_variant(name, *variant_index, variant, *len)?;
+                let mut s = serializer.serialize_struct_variant(
+                    intern_name(name),
+                    *variant_index,
+                    intern_name(variant),
+                    *len,
+                )?;
                 for op in ops {
                     match op {
-                        StructVariant::Field { key, value } => s.serialize_field(key, value)?,
-                        StructVariant::Skip { key } => s.skip_field(key)?,
+                        StructVariant::Field { key, value } => {
+                            s.serialize_field(intern_name(key), value)?
+                        }
+                        StructVariant::Skip { key } => s.skip_field(intern_name(key))?,
                     }
                 }
                 s.end()
             }
-            Self::X(never) => match *never {},
+            Self::Readability {
+                human_readable,
+                compact,
+            } => match serializer.is_human_readable() {
+                true => human_readable.serialize(serializer),
+                false => compact.serialize(serializer),
+            },
+            Self::X(x) => x.serialize_extension(serializer),
         }
     }
 }
@@ -356,14 +656,14 @@ pub enum Struct<X> {
     /// [serde::ser::SerializeStruct::serialize_field]
     Field {
         /// key
-        key: &'static str,
+        key: Name,
         /// value
         value: Box<XAst<X>>,
     },
     /// [serde::ser::SerializeStruct::skip_field]
     Skip {
         /// key
-        key: &'static str,
+        key: Name,
     },
 }
 
@@ -373,13 +673,13 @@ pub enum StructVariant<X> {
     /// [serde::ser::SerializeStructVariant::serialize_field]
     Field {
         /// key
-        key: &'static str,
+        key: Name,
         /// value
         value: Box<XAst<X>>,
     },
     /// [serde::ser::SerializeStructVariant::skip_field]
     Skip {
         /// key
-        key: &'static str,
+        key: Name,
     },
 }
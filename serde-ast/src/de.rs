@@ -0,0 +1,853 @@
+//! Implement [Deserializer] for [Ast], and [serde::Deserialize] for [Ast].
+//!
+//! Deserializing an [Ast] from a self-describing format records the calls made to the [Visitor]:
+//! - leaves map to the corresponding [Ast] leaf, e.g. [Visitor::visit_u64] to [Ast::U64]
+//! - [Visitor::visit_seq] maps to [Ast::Seq] with one [ast::Seq::Element] per element
+//! - [Visitor::visit_map] maps to [Ast::Map] with alternating [ast::Map::Key] and [ast::Map::Value]
+//! - [Visitor::visit_newtype_struct] maps to [Ast::NewtypeStruct]
+//! - [Visitor::visit_enum] maps to one of the variant forms, see below
+//!
+//! The data model of [serde::Deserializer] carries less information than [serde::Serializer], so some
+//! of it is lost:
+//! - struct and enum names are unknown, they are recorded as `""`
+//! - variant indices are unknown, they are recorded as `0`
+//! - structs are indistinguishable from maps, they are recorded as [Ast::Map]
+//! - the variant form is guessed from the payload: [Ast::Unit] becomes [Ast::UnitVariant],
+//!   a sequence becomes [Ast::TupleVariant], a map with string keys becomes [Ast::StructVariant],
+//!   anything else becomes [Ast::NewtypeVariant]
+//!
+//! Strings and bytes are copied, unless the extension implements [DeserializeExtension] to borrow
+//! them from the input, see [AstRef](crate::ast::AstRef).
+
+use std::{borrow::Cow, fmt::Display, marker::PhantomData};
+
+use serde::{
+    de::{
+        value::{StrDeserializer, UnitDeserializer},
+        DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+        Visitor,
+    },
+    Deserialize, Deserializer as _,
+};
+
+use crate::{
+    ast::{self, Borrowed, Final, Name, XAst},
+    Ast,
+};
+
+/// [serde::Deserializer::Error] for [Deserializer]
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum Error {
+    #[error("{0}")]
+    Custom(String),
+    #[error("map key without a value")]
+    MissingValue,
+    #[error("map value without a key")]
+    MissingKey,
+}
+impl serde::de::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// [Deserializer] is a [serde::Deserializer] for [Ast].
+///
+/// The [Ast] is only borrowed, all data is handed to the [Visitor] as transient values.
+/// It is human-readable, reading the `human_readable` value of [Ast::Readability] nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct Deserializer<'a> {
+    ast: &'a Ast,
+}
+impl<'a> Deserializer<'a> {
+    /// Create a new [Deserializer] reading from `ast`.
+    pub fn new(mut ast: &'a Ast) -> Self {
+        while let Ast::Readability { human_readable, .. } = ast {
+            ast = human_readable;
+        }
+        Self { ast }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Ast {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+impl<'de, 'a> IntoDeserializer<'de, Error> for &'a Ast {
+    type Deserializer = Deserializer<'a>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        Deserializer::new(self)
+    }
+}
+
+macro_rules! forward_to_ast {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                Deserializer::new(&self).$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for Ast {
+    type Error = Error;
+
+    forward_to_ast! {
+        deserialize_any deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_seq deserialize_map deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_unit_struct(name, visitor)
+    }
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_newtype_struct(name, visitor)
+    }
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_tuple(len, visitor)
+    }
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_tuple_struct(name, len, visitor)
+    }
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_struct(name, fields, visitor)
+    }
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::new(&self).deserialize_enum(name, variants, visitor)
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Deserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.ast {
+            Ast::Bool(v) => visitor.visit_bool(*v),
+            Ast::I8(v) => visitor.visit_i8(*v),
+            Ast::I16(v) => visitor.visit_i16(*v),
+            Ast::I32(v) => visitor.visit_i32(*v),
+            Ast::I64(v) => visitor.visit_i64(*v),
+            Ast::I128(v) => visitor.visit_i128(*v),
+            Ast::U8(v) => visitor.visit_u8(*v),
+            Ast::U16(v) => visitor.visit_u16(*v),
+            Ast::U32(v) => visitor.visit_u32(*v),
+            Ast::U64(v) => visitor.visit_u64(*v),
+            Ast::U128(v) => visitor.visit_u128(*v),
+            Ast::F32(v) => visitor.visit_f32(*v),
+            Ast::F64(v) => visitor.visit_f64(*v),
+            Ast::Char(v) => visitor.visit_char(*v),
+            Ast::Str(v) => visitor.visit_str(v),
+            Ast::Bytes(v) => visitor.visit_bytes(v),
+            Ast::None => visitor.visit_none(),
+            Ast::Some(v) => visitor.visit_some(Deserializer::new(v)),
+            Ast::Unit | Ast::UnitStruct(_) => visitor.visit_unit(),
+            Ast::NewtypeStruct { value, .. } => {
+                visitor.visit_newtype_struct(Deserializer::new(value))
+            }
+            Ast::UnitVariant { .. }
+            | Ast::NewtypeVariant { .. }
+            | Ast::TupleVariant { .. }
+            | Ast::StructVariant { .. } => visitor.visit_enum(Enum::Node(self.ast)),
+            Ast::Seq { ops, .. } => visit_seq(
+                ops.iter().map(|op| match op {
+                    ast::Seq::Element { value } => value.as_ref(),
+                }),
+                visitor,
+            ),
+            Ast::Tuple { ops, .. } => visit_seq(
+                ops.iter().map(|op| match op {
+                    ast::Tuple::Element { value } => value.as_ref(),
+                }),
+                visitor,
+            ),
+            Ast::TupleStruct { ops, .. } => visit_seq(
+                ops.iter().map(|op| match op {
+                    ast::TupleStruct::Field { value } => value.as_ref(),
+                }),
+                visitor,
+            ),
+            Ast::Map { ops, .. } => {
+                let mut map = Map::new(ops.iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Ast::Struct { ops, .. } => visit_struct(
+                ops.iter().filter_map(|op| match op {
+                    ast::Struct::Field { key, value } => Some((key.as_ref(), value.as_ref())),
+                    ast::Struct::Skip { .. } => None,
+                }),
+                visitor,
+            ),
+            Ast::Readability { human_readable, .. } => {
+                Deserializer::new(human_readable).deserialize_any(visitor)
+            }
+            Ast::X(never) => match *never {},
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.ast {
+            Ast::None | Ast::Unit => visitor.visit_none(),
+            Ast::Some(v) => visitor.visit_some(Deserializer::new(v)),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.ast {
+            Ast::NewtypeStruct { value, .. } => {
+                visitor.visit_newtype_struct(Deserializer::new(value))
+            }
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.ast {
+            Ast::UnitVariant { .. }
+            | Ast::NewtypeVariant { .. }
+            | Ast::TupleVariant { .. }
+            | Ast::StructVariant { .. } => visitor.visit_enum(Enum::Node(self.ast)),
+            // externally tagged representation, e.g. from a self-describing format
+            Ast::Str(variant) => visitor.visit_enum(Enum::Tagged(variant, None)),
+            Ast::Map { ops, .. } => match ops.as_slice() {
+                [ast::Map::Key { key }, ast::Map::Value { value }] => match key.as_ref() {
+                    Ast::Str(variant) => visitor.visit_enum(Enum::Tagged(variant, Some(value))),
+                    _ => self.deserialize_any(visitor),
+                },
+                _ => self.deserialize_any(visitor),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn visit_seq<'a, 'de, I, V>(values: I, visitor: V) -> Result<V::Value, Error>
+where
+    I: Iterator<Item = &'a Ast>,
+    V: Visitor<'de>,
+{
+    let mut seq = Seq { values };
+    let value = visitor.visit_seq(&mut seq)?;
+    match seq.values.next() {
+        None => Ok(value),
+        Some(_) => Err(serde::de::Error::custom("trailing elements in sequence")),
+    }
+}
+
+fn visit_struct<'a, 'de, I, V>(fields: I, visitor: V) -> Result<V::Value, Error>
+where
+    I: Iterator<Item = (&'a str, &'a Ast)>,
+    V: Visitor<'de>,
+{
+    visitor.visit_map(Struct {
+        fields,
+        value: None,
+    })
+}
+
+/// [SeqAccess] over the element values.
+struct Seq<I> {
+    values: I,
+}
+impl<'a, 'de, I> SeqAccess<'de> for Seq<I>
+where
+    I: Iterator<Item = &'a Ast>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.values
+            .next()
+            .map(|value| seed.deserialize(Deserializer::new(value)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.values.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// [MapAccess] over [ast::Map] Key/Value pairs.
+struct Map<I> {
+    ops: I,
+}
+impl<'a, I> Map<I>
+where
+    I: Iterator<Item = &'a ast::Map<ast::Final>>,
+{
+    fn new(ops: I) -> Self {
+        Self { ops }
+    }
+
+    fn end(mut self) -> Result<(), Error> {
+        match self.ops.next() {
+            None => Ok(()),
+            Some(_) => Err(serde::de::Error::custom("trailing entries in map")),
+        }
+    }
+}
+impl<'a, 'de, I> MapAccess<'de> for &mut Map<I>
+where
+    I: Iterator<Item = &'a ast::Map<ast::Final>>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.ops.next() {
+            None => Ok(None),
+            Some(ast::Map::Key { key }) => seed.deserialize(Deserializer::new(key)).map(Some),
+            Some(ast::Map::Value { .. }) => Err(Error::MissingKey),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.ops.next() {
+            Some(ast::Map::Value { value }) => seed.deserialize(Deserializer::new(value)),
+            Some(ast::Map::Key { .. }) | None => Err(Error::MissingValue),
+        }
+    }
+}
+
+/// [MapAccess] over [ast::Struct] or [ast::StructVariant] fields.
+struct Struct<'a, I> {
+    fields: I,
+    value: Option<&'a Ast>,
+}
+impl<'a, 'de, I> MapAccess<'de> for Struct<'a, I>
+where
+    I: Iterator<Item = (&'a str, &'a Ast)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.next() {
+            None => Ok(None),
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key: StrDeserializer<'_, Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(Deserializer::new(value)),
+            None => Err(Error::MissingValue),
+        }
+    }
+}
+
+/// [EnumAccess] for the variant forms of [Ast].
+enum Enum<'a> {
+    /// One of the `*Variant` nodes.
+    Node(&'a Ast),
+    /// Variant name with an optional payload.
+    Tagged(&'a str, Option<&'a Ast>),
+}
+impl<'a, 'de> EnumAccess<'de> for Enum<'a> {
+    type Error = Error;
+    type Variant = Variant<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (variant, access) = match self {
+            Enum::Node(ast) => match ast {
+                Ast::UnitVariant { variant, .. } => (variant.as_ref(), Variant::Unit),
+                Ast::NewtypeVariant { variant, value, .. } => {
+                    (variant.as_ref(), Variant::Payload(value.as_ref()))
+                }
+                Ast::TupleVariant { variant, .. } | Ast::StructVariant { variant, .. } => {
+                    (variant.as_ref(), Variant::Ops(ast))
+                }
+                _ => return Err(serde::de::Error::custom("expected an enum variant")),
+            },
+            Enum::Tagged(variant, None) => (variant, Variant::Unit),
+            Enum::Tagged(variant, Some(value)) => (variant, Variant::Payload(value)),
+        };
+        let variant: StrDeserializer<'_, Error> = variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, access))
+    }
+}
+
+/// [VariantAccess] for [Enum].
+enum Variant<'a> {
+    /// No payload.
+    Unit,
+    /// Payload is a single value, deserialized according to the expected variant kind.
+    Payload(&'a Ast),
+    /// Payload are the ops of [Ast::TupleVariant] or [Ast::StructVariant].
+    Ops(&'a Ast),
+}
+impl<'de> VariantAccess<'de> for Variant<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self {
+            Variant::Unit | Variant::Payload(Ast::Unit) => Ok(()),
+            _ => Err(serde::de::Error::custom("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self {
+            Variant::Unit => seed.deserialize(UnitDeserializer::new()),
+            Variant::Payload(value) => seed.deserialize(Deserializer::new(value)),
+            Variant::Ops(ast) => seed.deserialize(Ops(ast)),
+        }
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Variant::Payload(value) => Deserializer::new(value).deserialize_tuple(len, visitor),
+            Variant::Ops(Ast::TupleVariant { ops, .. }) => visit_seq(
+                ops.iter().map(|op| match op {
+                    ast::TupleVariant::Field { value } => value.as_ref(),
+                }),
+                visitor,
+            ),
+            _ => Err(serde::de::Error::custom("expected a tuple variant")),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Variant::Payload(value) => {
+                Deserializer::new(value).deserialize_struct("", fields, visitor)
+            }
+            Variant::Ops(Ast::StructVariant { ops, .. }) => visit_struct(
+                ops.iter().filter_map(|op| match op {
+                    ast::StructVariant::Field { key, value } => {
+                        Some((key.as_ref(), value.as_ref()))
+                    }
+                    ast::StructVariant::Skip { .. } => None,
+                }),
+                visitor,
+            ),
+            _ => Err(serde::de::Error::custom("expected a struct variant")),
+        }
+    }
+}
+
+/// [serde::Deserializer] presenting the ops of [Ast::TupleVariant] as a sequence,
+/// and the ops of [Ast::StructVariant] as a map.
+struct Ops<'a>(&'a Ast);
+impl<'de> serde::Deserializer<'de> for Ops<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let Self(ast) = self;
+        match ast {
+            Ast::StructVariant { .. } => Variant::Ops(ast).struct_variant(&[], visitor),
+            _ => Variant::Ops(ast).tuple_variant(0, visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Keep data borrowed from the input of a [serde::Deserializer] as an extension node [XAst::X].
+///
+/// Deserializing an [XAst] copies strings and bytes into [XAst::Str] and [XAst::Bytes], unless
+/// the extension takes the borrowed data, see [AstRef](crate::ast::AstRef).
+pub trait DeserializeExtension<'de>: Sized {
+    /// Take a string borrowed from the input, or return [None] to copy it into [XAst::Str].
+    fn borrowed_str(v: &'de str) -> Option<Self> {
+        let _ = v;
+        None
+    }
+
+    /// Take bytes borrowed from the input, or return [None] to copy them into [XAst::Bytes].
+    fn borrowed_bytes(v: &'de [u8]) -> Option<Self> {
+        let _ = v;
+        None
+    }
+
+    /// View this extension node as a string, used for the field keys of a guessed
+    /// [XAst::StructVariant].
+    fn as_str(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl DeserializeExtension<'_> for Final {}
+
+impl<'de> DeserializeExtension<'de> for Borrowed<'de> {
+    fn borrowed_str(v: &'de str) -> Option<Self> {
+        Some(Self::Str(v))
+    }
+
+    fn borrowed_bytes(v: &'de [u8]) -> Option<Self> {
+        Some(Self::Bytes(v))
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(v) => Some(v),
+            Self::Bytes(_) => None,
+        }
+    }
+}
+
+impl<'de, X> Deserialize<'de> for XAst<X>
+where
+    X: DeserializeExtension<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(AstVisitor(PhantomData))
+    }
+}
+
+/// [Visitor] building an [XAst].
+struct AstVisitor<X>(PhantomData<X>);
+impl<'de, X> Visitor<'de> for AstVisitor<X>
+where
+    X: DeserializeExtension<'de>,
+{
+    type Value = XAst<X>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(XAst::Bool(v))
+    }
+    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E> {
+        Ok(XAst::I8(v))
+    }
+    fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E> {
+        Ok(XAst::I16(v))
+    }
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E> {
+        Ok(XAst::I32(v))
+    }
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(XAst::I64(v))
+    }
+    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(XAst::I128(v))
+    }
+    fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E> {
+        Ok(XAst::U8(v))
+    }
+    fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E> {
+        Ok(XAst::U16(v))
+    }
+    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E> {
+        Ok(XAst::U32(v))
+    }
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(XAst::U64(v))
+    }
+    fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(XAst::U128(v))
+    }
+    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E> {
+        Ok(XAst::F32(v))
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(XAst::F64(v))
+    }
+    fn visit_char<E>(self, v: char) -> Result<Self::Value, E> {
+        Ok(XAst::Char(v))
+    }
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(match X::borrowed_str(v) {
+            Some(x) => XAst::X(x),
+            None => XAst::Str(v.to_owned()),
+        })
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(XAst::Str(v.to_owned()))
+    }
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(XAst::Str(v))
+    }
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+        Ok(match X::borrowed_bytes(v) {
+            Some(x) => XAst::X(x),
+            None => XAst::Bytes(v.to_owned()),
+        })
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(XAst::Bytes(v.to_owned()))
+    }
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(XAst::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(XAst::None)
+    }
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(XAst::Some(Box::new(XAst::deserialize(deserializer)?)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(XAst::Unit)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(XAst::NewtypeStruct {
+            name: Cow::Borrowed(""),
+            value: Box::new(XAst::deserialize(deserializer)?),
+        })
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut ops = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(value) = seq.next_element()? {
+            ops.push(ast::Seq::Element {
+                value: Box::new(value),
+            });
+        }
+
+        Ok(XAst::Seq {
+            len: Some(ops.len()),
+            ops,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut ops = Vec::with_capacity(2 * map.size_hint().unwrap_or_default());
+        while let Some((key, value)) = map.next_entry()? {
+            ops.push(ast::Map::Key { key: Box::new(key) });
+            ops.push(ast::Map::Value {
+                value: Box::new(value),
+            });
+        }
+
+        Ok(XAst::Map {
+            len: Some(ops.len() / 2),
+            ops,
+        })
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (variant, access) = data.variant::<String>()?;
+        let value = access.newtype_variant::<XAst<X>>()?;
+
+        Ok(into_variant(Name::Owned(variant), value))
+    }
+}
+
+/// Guess the variant form from the payload `value`.
+fn into_variant<'de, X>(variant: Name, value: XAst<X>) -> XAst<X>
+where
+    X: DeserializeExtension<'de>,
+{
+    let (name, variant_index) = (Cow::Borrowed(""), 0);
+    match value {
+        XAst::Unit => XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        },
+        XAst::Seq { ops, .. } => XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len: ops.len(),
+            ops: ops
+                .into_iter()
+                .map(|op| match op {
+                    ast::Seq::Element { value } => ast::TupleVariant::Field { value },
+                })
+                .collect(),
+        },
+        XAst::Map { len, ops }
+            if ops.iter().all(|op| match op {
+                ast::Map::Key { key } => key_str(key).is_some(),
+                ast::Map::Value { .. } => true,
+            }) =>
+        {
+            let mut fields = Vec::with_capacity(ops.len() / 2);
+            let mut key = None;
+            for op in ops {
+                match op {
+                    ast::Map::Key { key: k } => {
+                        key = Some(key_str(&k).expect("keys checked above").to_owned())
+                    }
+                    ast::Map::Value { value } => fields.push(ast::StructVariant::Field {
+                        key: Name::Owned(key.take().unwrap_or_default()),
+                        value,
+                    }),
+                }
+            }
+            XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len: len.unwrap_or(fields.len()),
+                ops: fields,
+            }
+        }
+        value => XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value: Box::new(value),
+        },
+    }
+}
+
+/// View a map key as a string, borrowed or owned.
+fn key_str<'de, X>(key: &XAst<X>) -> Option<&str>
+where
+    X: DeserializeExtension<'de>,
+{
+    match key {
+        XAst::Str(key) => Some(key),
+        XAst::X(x) => x.as_str(),
+        _ => None,
+    }
+}
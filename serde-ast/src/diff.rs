@@ -0,0 +1,568 @@
+//! Compute a structural [Diff] between two [XAst].
+//!
+//! Nodes are compared by the serialization calls they represent:
+//! - [XAst::Struct] and [XAst::StructVariant] fields are matched by key, skipped fields are absent
+//! - [XAst::Map] entries are matched by their key [XAst]
+//! - [XAst::Seq], [XAst::Tuple], [XAst::TupleStruct] and [XAst::TupleVariant] elements are matched
+//!   by index, or aligned by their longest common subsequence with [Options::lcs]
+//! - [XAst::Some], [XAst::NewtypeStruct] and [XAst::NewtypeVariant] are compared by their value
+//!
+//! Nodes with a different variant, name or variant index are [Change::Changed] as a whole, and so
+//! are containers whose `len` differs beyond their added and removed children, e.g. a sequence of
+//! unknown length and one of known length. Nodes are compared with [XAst::total_cmp].
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{diff::Change, path::AstPath, to_ast};
+//! #[derive(Serialize)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//! }
+//! let a = to_ast(&Server { host: "localhost".to_string(), port: 80 }).unwrap();
+//! let b = to_ast(&Server { host: "localhost".to_string(), port: 443 }).unwrap();
+//!
+//! let diff = serde_ast::diff(&a, &b);
+//! assert!(matches!(
+//!     diff.changes(),
+//!     [Change::Changed { path, .. }] if path.to_string() == "/port",
+//! ));
+//! print!("{diff}");
+//! ```
+//! ```text
+//! @@ /port @@
+//! - 80u16
+//! + 443u16
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fmt::{Debug, Display},
+};
+
+use crate::{
+    ast::{Map, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    path::{key_segment, AstPath},
+    pretty::Printer,
+};
+
+/// Maximum size of the table of [Options::lcs], `len(a) * len(b)`.
+pub const LCS_MAX_CELLS: usize = 1 << 22;
+
+/// Options for [diff_with].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Align sequence elements by their longest common subsequence instead of by index.
+    ///
+    /// Removed elements are addressed by their index in the old sequence,
+    /// added and changed elements by their index in the new sequence.
+    ///
+    /// Sequences whose lengths multiply to more than [LCS_MAX_CELLS] are matched by index.
+    pub lcs: bool,
+}
+
+/// A single difference between two [XAst].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<'a, X> {
+    /// A node present only in the new [XAst].
+    Added {
+        /// path of the node in the new [XAst]
+        path: AstPath,
+        /// added node
+        value: &'a XAst<X>,
+    },
+    /// A node present only in the old [XAst].
+    Removed {
+        /// path of the node in the old [XAst]
+        path: AstPath,
+        /// removed node
+        value: &'a XAst<X>,
+    },
+    /// A node replaced by a different node.
+    Changed {
+        /// path of the node
+        path: AstPath,
+        /// node in the old [XAst]
+        from: &'a XAst<X>,
+        /// node in the new [XAst]
+        to: &'a XAst<X>,
+    },
+}
+impl<X> Change<'_, X> {
+    /// Path of the changed node.
+    pub fn path(&self) -> &AstPath {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => {
+                path
+            }
+        }
+    }
+}
+
+/// Structural difference between two [XAst], see the [module documentation](self).
+///
+/// [Display] renders a unified-style report, printing nodes on a single line with [Printer].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff<'a, X> {
+    changes: Vec<Change<'a, X>>,
+}
+impl<'a, X> Diff<'a, X> {
+    /// List of changes, in traversal order.
+    pub fn changes(&self) -> &[Change<'a, X>] {
+        &self.changes
+    }
+
+    /// Check if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+impl<'a, X> IntoIterator for Diff<'a, X> {
+    type Item = Change<'a, X>;
+    type IntoIter = std::vec::IntoIter<Change<'a, X>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+impl<X> Display for Diff<'_, X>
+where
+    X: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let printer = Printer::new().indent(None);
+        for change in &self.changes {
+            match change.path() {
+                path if path.is_root() => writeln!(f, "@@ (root) @@")?,
+                path => writeln!(f, "@@ {path} @@")?,
+            }
+            match change {
+                Change::Added { value, .. } => writeln!(f, "+ {}", printer.display(value))?,
+                Change::Removed { value, .. } => writeln!(f, "- {}", printer.display(value))?,
+                Change::Changed { from, to, .. } => {
+                    writeln!(f, "- {}", printer.display(from))?;
+                    writeln!(f, "+ {}", printer.display(to))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compute the [Diff] from `a` to `b`, matching sequence elements by index.
+pub fn diff<'a, X>(a: &'a XAst<X>, b: &'a XAst<X>) -> Diff<'a, X>
+where
+    X: Ord + Debug,
+{
+    diff_with(a, b, &Options::default())
+}
+
+/// Compute the [Diff] from `a` to `b` with [Options].
+pub fn diff_with<'a, X>(a: &'a XAst<X>, b: &'a XAst<X>, options: &Options) -> Diff<'a, X>
+where
+    X: Ord + Debug,
+{
+    let mut differ = Differ {
+        options,
+        changes: Vec::new(),
+    };
+    differ.node(&mut AstPath::root(), a, b);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+struct Differ<'a, 'o, X> {
+    options: &'o Options,
+    changes: Vec<Change<'a, X>>,
+}
+impl<'a, X> Differ<'a, '_, X>
+where
+    X: Ord + Debug,
+{
+    fn node(&mut self, path: &mut AstPath, a: &'a XAst<X>, b: &'a XAst<X>) {
+        if a.total_cmp(b).is_eq() {
+            return;
+        }
+        match (a, b) {
+            (XAst::Some(a), XAst::Some(b)) => self.node(path, a, b),
+            (
+                XAst::NewtypeStruct {
+                    name: a_name,
+                    value: a,
+                },
+                XAst::NewtypeStruct {
+                    name: b_name,
+                    value: b,
+                },
+            ) if a_name == b_name => self.node(path, a, b),
+            (
+                XAst::NewtypeVariant {
+                    name: a_name,
+                    variant_index: a_index,
+                    variant: a_variant,
+                    value: a,
+                },
+                XAst::NewtypeVariant {
+                    name: b_name,
+                    variant_index: b_index,
+                    variant: b_variant,
+                    value: b,
+                },
+            ) if (a_name, a_index, a_variant) == (b_name, b_index, b_variant) => {
+                self.node(path, a, b)
+            }
+            (XAst::Seq { len: a_len, ops: a }, XAst::Seq { len: b_len, ops: b })
+                if same_len(*a_len, a.len(), *b_len, b.len()) =>
+            {
+                self.elements(
+                    path,
+                    a.iter().map(|op| match op {
+                        Seq::Element { value } => value.as_ref(),
+                    }),
+                    b.iter().map(|op| match op {
+                        Seq::Element { value } => value.as_ref(),
+                    }),
+                )
+            }
+            (XAst::Tuple { len: a_len, ops: a }, XAst::Tuple { len: b_len, ops: b })
+                if same_len(Some(*a_len), a.len(), Some(*b_len), b.len()) =>
+            {
+                self.elements(
+                    path,
+                    a.iter().map(|op| match op {
+                        Tuple::Element { value } => value.as_ref(),
+                    }),
+                    b.iter().map(|op| match op {
+                        Tuple::Element { value } => value.as_ref(),
+                    }),
+                )
+            }
+            (
+                XAst::TupleStruct {
+                    name: a_name,
+                    len: a_len,
+                    ops: a,
+                },
+                XAst::TupleStruct {
+                    name: b_name,
+                    len: b_len,
+                    ops: b,
+                },
+            ) if a_name == b_name && same_len(Some(*a_len), a.len(), Some(*b_len), b.len()) => self
+                .elements(
+                    path,
+                    a.iter().map(|op| match op {
+                        TupleStruct::Field { value } => value.as_ref(),
+                    }),
+                    b.iter().map(|op| match op {
+                        TupleStruct::Field { value } => value.as_ref(),
+                    }),
+                ),
+            (
+                XAst::TupleVariant {
+                    name: a_name,
+                    variant_index: a_index,
+                    variant: a_variant,
+                    len: a_len,
+                    ops: a,
+                },
+                XAst::TupleVariant {
+                    name: b_name,
+                    variant_index: b_index,
+                    variant: b_variant,
+                    len: b_len,
+                    ops: b,
+                },
+            ) if (a_name, a_index, a_variant) == (b_name, b_index, b_variant)
+                && same_len(Some(*a_len), a.len(), Some(*b_len), b.len()) =>
+            {
+                self.elements(
+                    path,
+                    a.iter().map(|op| match op {
+                        TupleVariant::Field { value } => value.as_ref(),
+                    }),
+                    b.iter().map(|op| match op {
+                        TupleVariant::Field { value } => value.as_ref(),
+                    }),
+                )
+            }
+            (
+                XAst::Struct {
+                    name: a_name,
+                    len: a_len,
+                    ops: a,
+                },
+                XAst::Struct {
+                    name: b_name,
+                    len: b_len,
+                    ops: b,
+                },
+            ) if a_name == b_name
+                && same_len(
+                    Some(*a_len),
+                    a.iter()
+                        .filter(|op| matches!(op, Struct::Field { .. }))
+                        .count(),
+                    Some(*b_len),
+                    b.iter()
+                        .filter(|op| matches!(op, Struct::Field { .. }))
+                        .count(),
+                ) =>
+            {
+                self.fields(
+                    path,
+                    a.iter().filter_map(|op| match op {
+                        Struct::Field { key, value } => Some((key.as_ref(), value.as_ref())),
+                        Struct::Skip { .. } => None,
+                    }),
+                    b.iter().filter_map(|op| match op {
+                        Struct::Field { key, value } => Some((key.as_ref(), value.as_ref())),
+                        Struct::Skip { .. } => None,
+                    }),
+                )
+            }
+            (
+                XAst::StructVariant {
+                    name: a_name,
+                    variant_index: a_index,
+                    variant: a_variant,
+                    len: a_len,
+                    ops: a,
+                },
+                XAst::StructVariant {
+                    name: b_name,
+                    variant_index: b_index,
+                    variant: b_variant,
+                    len: b_len,
+                    ops: b,
+                },
+            ) if (a_name, a_index, a_variant) == (b_name, b_index, b_variant)
+                && same_len(
+                    Some(*a_len),
+                    a.iter()
+                        .filter(|op| matches!(op, StructVariant::Field { .. }))
+                        .count(),
+                    Some(*b_len),
+                    b.iter()
+                        .filter(|op| matches!(op, StructVariant::Field { .. }))
+                        .count(),
+                ) =>
+            {
+                self.fields(
+                    path,
+                    a.iter().filter_map(|op| match op {
+                        StructVariant::Field { key, value } => Some((key.as_ref(), value.as_ref())),
+                        StructVariant::Skip { .. } => None,
+                    }),
+                    b.iter().filter_map(|op| match op {
+                        StructVariant::Field { key, value } => Some((key.as_ref(), value.as_ref())),
+                        StructVariant::Skip { .. } => None,
+                    }),
+                )
+            }
+            (XAst::Map { len: a_len, ops: a }, XAst::Map { len: b_len, ops: b })
+                if same_len(*a_len, entries(a).count(), *b_len, entries(b).count()) =>
+            {
+                self.entries(path, a, b)
+            }
+            (a, b) => self.changes.push(Change::Changed {
+                path: path.clone(),
+                from: a,
+                to: b,
+            }),
+        }
+    }
+
+    fn added(&mut self, path: &AstPath, value: &'a XAst<X>) {
+        self.changes.push(Change::Added {
+            path: path.clone(),
+            value,
+        });
+    }
+    fn removed(&mut self, path: &AstPath, value: &'a XAst<X>) {
+        self.changes.push(Change::Removed {
+            path: path.clone(),
+            value,
+        });
+    }
+
+    fn child(&mut self, path: &mut AstPath, segment: String, a: &'a XAst<X>, b: &'a XAst<X>) {
+        path.push(segment);
+        self.node(path, a, b);
+        path.pop();
+    }
+
+    fn elements(
+        &mut self,
+        path: &mut AstPath,
+        a: impl Iterator<Item = &'a XAst<X>>,
+        b: impl Iterator<Item = &'a XAst<X>>,
+    ) {
+        let a: Vec<_> = a.collect();
+        let b: Vec<_> = b.collect();
+
+        if !self.options.lcs || a.len().saturating_mul(b.len()) > LCS_MAX_CELLS {
+            for i in 0..a.len().max(b.len()) {
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => self.child(path, i.to_string(), a, b),
+                    (Some(a), None) => self.removed(&path.join(i.to_string()), a),
+                    (None, Some(b)) => self.added(&path.join(i.to_string()), b),
+                    (None, None) => unreachable!("index below max len"),
+                }
+            }
+            return;
+        }
+
+        // lengths of the longest common subsequence of the suffixes a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0usize; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = match a[i].total_cmp(b[j]).is_eq() {
+                    true => lcs[(i + 1) * width + j + 1] + 1,
+                    false => lcs[(i + 1) * width + j].max(lcs[i * width + j + 1]),
+                };
+            }
+        }
+
+        // pair up unmatched runs between common elements
+        let (mut i, mut j) = (0, 0);
+        let (mut removed, mut added): (Vec<usize>, Vec<usize>) = (Vec::new(), Vec::new());
+        loop {
+            let common = i < a.len() && j < b.len() && a[i].total_cmp(b[j]).is_eq();
+            if common || (i == a.len() && j == b.len()) {
+                let paired = removed.len().min(added.len());
+                for (&i, &j) in removed.iter().zip(&added) {
+                    self.child(path, j.to_string(), a[i], b[j]);
+                }
+                for &i in &removed[paired..] {
+                    self.removed(&path.join(i.to_string()), a[i]);
+                }
+                for &j in &added[paired..] {
+                    self.added(&path.join(j.to_string()), b[j]);
+                }
+                removed.clear();
+                added.clear();
+
+                if !common {
+                    break;
+                }
+                i += 1;
+                j += 1;
+            } else if j == b.len()
+                || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                removed.push(i);
+                i += 1;
+            } else {
+                added.push(j);
+                j += 1;
+            }
+        }
+    }
+
+    fn fields(
+        &mut self,
+        path: &mut AstPath,
+        a: impl Iterator<Item = (&'a str, &'a XAst<X>)>,
+        b: impl Iterator<Item = (&'a str, &'a XAst<X>)>,
+    ) {
+        let b: Vec<_> = b.collect();
+        let mut index = HashMap::with_capacity(b.len());
+        for (i, (key, _)) in b.iter().enumerate() {
+            index.entry(*key).or_insert(i);
+        }
+        let mut matched = vec![false; b.len()];
+        for (key, a) in a {
+            match index.get(key).copied() {
+                Some(index) => {
+                    matched[index] = true;
+                    self.child(path, key.to_owned(), a, b[index].1);
+                }
+                None => self.removed(&path.join(key), a),
+            }
+        }
+        for ((key, b), matched) in b.into_iter().zip(matched) {
+            if !matched {
+                self.added(&path.join(key), b);
+            }
+        }
+    }
+
+    fn entries(&mut self, path: &mut AstPath, a: &'a [Map<X>], b: &'a [Map<X>]) {
+        let b: Vec<_> = entries(b).collect();
+        let mut index = BTreeMap::new();
+        for (i, (key, _)) in b.iter().enumerate() {
+            index.entry(Key(*key)).or_insert(i);
+        }
+        let mut matched = vec![false; b.len()];
+        for (key, a) in entries(a) {
+            match index.get(&Key(key)).copied() {
+                Some(index) => {
+                    matched[index] = true;
+                    self.child(path, entry_segment(key), a, b[index].1);
+                }
+                None => self.removed(&path.join(entry_segment(key)), a),
+            }
+        }
+        for ((key, b), matched) in b.into_iter().zip(matched) {
+            if !matched {
+                self.added(&path.join(entry_segment(key)), b);
+            }
+        }
+    }
+}
+
+/// Check if the declared `len`s of two containers only differ by their added and removed children.
+fn same_len(a: Option<usize>, a_count: usize, b: Option<usize>, b_count: usize) -> bool {
+    a == b || (a == Some(a_count) && b == Some(b_count))
+}
+
+/// Pair up [Map::Key] and [Map::Value] operations, ignoring any unpaired operation.
+fn entries<X>(ops: &[Map<X>]) -> impl Iterator<Item = (&XAst<X>, &XAst<X>)> {
+    ops.windows(2).filter_map(|pair| match pair {
+        [Map::Key { key }, Map::Value { value }] => Some((key.as_ref(), value.as_ref())),
+        _ => None,
+    })
+}
+
+/// Map key ordered by [XAst::total_cmp].
+struct Key<'a, X>(&'a XAst<X>);
+impl<X> PartialEq for Key<'_, X>
+where
+    X: Ord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl<X> Eq for Key<'_, X> where X: Ord {}
+impl<X> PartialOrd for Key<'_, X>
+where
+    X: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<X> Ord for Key<'_, X>
+where
+    X: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(other.0)
+    }
+}
+
+/// Path segment of a map entry, non string-like keys are rendered with [Printer].
+fn entry_segment<X>(key: &XAst<X>) -> String
+where
+    X: Debug,
+{
+    match key_segment(key) {
+        Some(segment) => segment.into_owned(),
+        None => Printer::new().indent(None).display(key).to_string(),
+    }
+}
@@ -0,0 +1,1194 @@
+//! Flat [Event] stream representation of serialization.
+//!
+//! An [Event] stream records the same calls as an [Ast], without building a tree:
+//! - leaves are a single [Event::Leaf]
+//! - [Event::Some] and the newtype events are followed by their value
+//! - the `*Begin` events are followed by their contents and a closing [Event::End]
+//! - sequence and tuple elements follow each other directly
+//! - map keys and values are preceded by [Event::Key] and [Event::Value]
+//! - struct fields are preceded by [Event::Field], skipped fields are [Event::Skip]
+//! - [XAst::Readability] nodes of an [Ast] are [Event::Readability], followed by both values
+//!
+//! [EventSerializer] emits the events of any value to a [Sink], and [replay] serializes an
+//! event stream into any [serde::Serializer]. Converting between events and an [Ast] is lossless.
+//!
+//! [XAst::Readability]: crate::ast::XAst::Readability
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::events::{self, Event, Leaf};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     id: u8,
+//! }
+//! let events = events::to_events(&Example { id: 7 }).unwrap();
+//! assert_eq!(
+//!     events,
+//!     [
+//!         Event::StructBegin { name: "Example".into(), len: 1 },
+//!         Event::Field("id".into()),
+//!         Event::Leaf(Leaf::U8(7)),
+//!         Event::End,
+//!     ],
+//! );
+//!
+//! let json = events::replay(events, &mut serde_json::Serializer::new(Vec::new()));
+//! # json.unwrap();
+//! ```
+
+use std::{cell::RefCell, iter::Peekable};
+
+use serde::{
+    ser::{
+        Error as _, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+        SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    intern::intern_name,
+    path::key_segment,
+    ser::{Error, Segment},
+    Ast,
+};
+
+/// Event of a flat serialization stream, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A value without contents.
+    Leaf(Leaf),
+
+    /// [serde::Serializer::serialize_some], followed by the value.
+    Some,
+    /// A value serializing differently depending on [serde::Serializer::is_human_readable],
+    /// followed by the human-readable and the compact value.
+    Readability,
+    /// [serde::Serializer::serialize_newtype_struct], followed by the value.
+    NewtypeStruct {
+        /// name
+        name: Name,
+    },
+    /// [serde::Serializer::serialize_newtype_variant], followed by the value.
+    NewtypeVariant {
+        /// name
+        name: Name,
+        /// variant_index
+        variant_index: u32,
+        /// variant
+        variant: Name,
+    },
+
+    /// [serde::Serializer::serialize_seq], followed by the elements and [Event::End].
+    SeqBegin {
+        /// len
+        len: Option<usize>,
+    },
+    /// [serde::Serializer::serialize_tuple], followed by the elements and [Event::End].
+    TupleBegin {
+        /// len
+        len: usize,
+    },
+    /// [serde::Serializer::serialize_tuple_struct], followed by the fields and [Event::End].
+    TupleStructBegin {
+        /// name
+        name: Name,
+        /// len
+        len: usize,
+    },
+    /// [serde::Serializer::serialize_tuple_variant], followed by the fields and [Event::End].
+    TupleVariantBegin {
+        /// name
+        name: Name,
+        /// variant_index
+        variant_index: u32,
+        /// variant
+        variant: Name,
+        /// len
+        len: usize,
+    },
+    /// [serde::Serializer::serialize_map], followed by [Event::Key] and [Event::Value] entries
+    /// and [Event::End].
+    MapBegin {
+        /// len
+        len: Option<usize>,
+    },
+    /// [serde::Serializer::serialize_struct], followed by [Event::Field] and [Event::Skip]
+    /// entries and [Event::End].
+    StructBegin {
+        /// name
+        name: Name,
+        /// len
+        len: usize,
+    },
+    /// [serde::Serializer::serialize_struct_variant], followed by [Event::Field] and
+    /// [Event::Skip] entries and [Event::End].
+    StructVariantBegin {
+        /// name
+        name: Name,
+        /// variant_index
+        variant_index: u32,
+        /// variant
+        variant: Name,
+        /// len
+        len: usize,
+    },
+
+    /// [serde::ser::SerializeMap::serialize_key], followed by the key.
+    Key,
+    /// [serde::ser::SerializeMap::serialize_value], followed by the value.
+    Value,
+    /// `serialize_field` of a struct, followed by the value.
+    Field(Name),
+    /// `skip_field` of a struct.
+    Skip(Name),
+    /// End of the contents of a `*Begin` event.
+    End,
+}
+
+/// Value without contents, see [Event::Leaf].
+#[derive(Debug, Clone, PartialEq)]
+#[allow(missing_docs)] // mirrors the leaves of XAst
+pub enum Leaf {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    None,
+    Unit,
+    UnitStruct(Name),
+    UnitVariant {
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+    },
+}
+
+/// Receive the [Event]s emitted by [EventSerializer].
+pub trait Sink {
+    /// Receive the next `event`.
+    fn event(&mut self, event: Event);
+}
+
+impl Sink for Vec<Event> {
+    fn event(&mut self, event: Event) {
+        self.push(event);
+    }
+}
+
+impl<F> Sink for F
+where
+    F: FnMut(Event),
+{
+    fn event(&mut self, event: Event) {
+        self(event)
+    }
+}
+
+/// Serialize a value into a `Vec` of [Event]s.
+pub fn to_events<T>(value: &T) -> Result<Vec<Event>, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut serializer = EventSerializer::new(Vec::new());
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
+
+/// Convert an [Ast] into [Event]s.
+pub fn from_ast(ast: &Ast) -> Vec<Event> {
+    let mut events = Vec::new();
+    ast_events(ast, &mut events);
+    events
+}
+
+fn ast_events(ast: &Ast, events: &mut Vec<Event>) {
+    let mut leaf = |leaf| events.push(Event::Leaf(leaf));
+    match ast {
+        XAst::Bool(v) => leaf(Leaf::Bool(*v)),
+        XAst::I8(v) => leaf(Leaf::I8(*v)),
+        XAst::I16(v) => leaf(Leaf::I16(*v)),
+        XAst::I32(v) => leaf(Leaf::I32(*v)),
+        XAst::I64(v) => leaf(Leaf::I64(*v)),
+        XAst::I128(v) => leaf(Leaf::I128(*v)),
+        XAst::U8(v) => leaf(Leaf::U8(*v)),
+        XAst::U16(v) => leaf(Leaf::U16(*v)),
+        XAst::U32(v) => leaf(Leaf::U32(*v)),
+        XAst::U64(v) => leaf(Leaf::U64(*v)),
+        XAst::U128(v) => leaf(Leaf::U128(*v)),
+        XAst::F32(v) => leaf(Leaf::F32(*v)),
+        XAst::F64(v) => leaf(Leaf::F64(*v)),
+        XAst::Char(v) => leaf(Leaf::Char(*v)),
+        XAst::Str(v) => leaf(Leaf::Str(v.clone())),
+        XAst::Bytes(v) => leaf(Leaf::Bytes(v.clone())),
+        XAst::None => leaf(Leaf::None),
+        XAst::Unit => leaf(Leaf::Unit),
+        XAst::UnitStruct(name) => leaf(Leaf::UnitStruct(name.clone())),
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => leaf(Leaf::UnitVariant {
+            name: name.clone(),
+            variant_index: *variant_index,
+            variant: variant.clone(),
+        }),
+        XAst::Some(value) => {
+            events.push(Event::Some);
+            ast_events(value, events);
+        }
+        XAst::NewtypeStruct { name, value } => {
+            events.push(Event::NewtypeStruct { name: name.clone() });
+            ast_events(value, events);
+        }
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => {
+            events.push(Event::NewtypeVariant {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+            });
+            ast_events(value, events);
+        }
+        XAst::Seq { len, ops } => {
+            events.push(Event::SeqBegin { len: *len });
+            for Seq::Element { value } in ops {
+                ast_events(value, events);
+            }
+            events.push(Event::End);
+        }
+        XAst::Tuple { len, ops } => {
+            events.push(Event::TupleBegin { len: *len });
+            for Tuple::Element { value } in ops {
+                ast_events(value, events);
+            }
+            events.push(Event::End);
+        }
+        XAst::TupleStruct { name, len, ops } => {
+            events.push(Event::TupleStructBegin {
+                name: name.clone(),
+                len: *len,
+            });
+            for TupleStruct::Field { value } in ops {
+                ast_events(value, events);
+            }
+            events.push(Event::End);
+        }
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            events.push(Event::TupleVariantBegin {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+                len: *len,
+            });
+            for TupleVariant::Field { value } in ops {
+                ast_events(value, events);
+            }
+            events.push(Event::End);
+        }
+        XAst::Map { len, ops } => {
+            events.push(Event::MapBegin { len: *len });
+            for op in ops {
+                match op {
+                    Map::Key { key } => {
+                        events.push(Event::Key);
+                        ast_events(key, events);
+                    }
+                    Map::Value { value } => {
+                        events.push(Event::Value);
+                        ast_events(value, events);
+                    }
+                }
+            }
+            events.push(Event::End);
+        }
+        XAst::Struct { name, len, ops } => {
+            events.push(Event::StructBegin {
+                name: name.clone(),
+                len: *len,
+            });
+            for op in ops {
+                match op {
+                    Struct::Field { key, value } => {
+                        events.push(Event::Field(key.clone()));
+                        ast_events(value, events);
+                    }
+                    Struct::Skip { key } => events.push(Event::Skip(key.clone())),
+                }
+            }
+            events.push(Event::End);
+        }
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            events.push(Event::StructVariantBegin {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+                len: *len,
+            });
+            for op in ops {
+                match op {
+                    StructVariant::Field { key, value } => {
+                        events.push(Event::Field(key.clone()));
+                        ast_events(value, events);
+                    }
+                    StructVariant::Skip { key } => events.push(Event::Skip(key.clone())),
+                }
+            }
+            events.push(Event::End);
+        }
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => {
+            events.push(Event::Readability);
+            ast_events(human_readable, events);
+            ast_events(compact, events);
+        }
+        XAst::X(x) => match *x {},
+    }
+}
+
+/// Convert [Event]s into an [Ast], failing if the events are not a single well-formed value.
+pub fn to_ast<I>(events: I) -> Result<Ast, Error>
+where
+    I: IntoIterator<Item = Event>,
+{
+    let replay = Replay {
+        events: RefCell::new(events.into_iter().peekable()),
+    };
+    let ast = replay.ast()?;
+    match replay.next() {
+        None => Ok(ast),
+        Some(event) => Err(Error::custom(format_args!(
+            "unexpected event {event:?} after the value"
+        ))),
+    }
+}
+
+/// [EventSerializer] is a [serde::Serializer] emitting [Event]s to a [Sink].
+///
+/// The [Sink] is either a `Vec<Event>` or a callback `FnMut(Event)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventSerializer<S> {
+    sink: S,
+}
+impl<S> EventSerializer<S>
+where
+    S: Sink,
+{
+    /// Create a new [EventSerializer] emitting events to `sink`.
+    pub fn new(sink: S) -> Self {
+        Self { sink }
+    }
+
+    /// Return the [Sink].
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+
+    fn leaf(&mut self, leaf: Leaf) -> Result<(), Error> {
+        self.event(Event::Leaf(leaf))
+    }
+
+    fn event(&mut self, event: Event) -> Result<(), Error> {
+        self.sink.event(event);
+        Ok(())
+    }
+}
+
+impl<S> serde::Serializer for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::I8(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::I16(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::I32(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::I64(v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::I128(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::U8(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::U16(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::U32(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::U64(v))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::U128(v))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::F32(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::F64(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::Char(v))
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::Str(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::Bytes(v.to_owned()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::None)
+    }
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::Some)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::Unit)
+    }
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::UnitStruct(name.into()))
+    }
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.leaf(Leaf::UnitVariant {
+            name: name.into(),
+            variant_index,
+            variant: variant.into(),
+        })
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::NewtypeStruct { name: name.into() })?;
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::NewtypeVariant {
+            name: name.into(),
+            variant_index,
+            variant: variant.into(),
+        })?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.event(Event::SeqBegin { len })?;
+        Ok(self)
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.event(Event::TupleBegin { len })?;
+        Ok(self)
+    }
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.event(Event::TupleStructBegin {
+            name: name.into(),
+            len,
+        })?;
+        Ok(self)
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.event(Event::TupleVariantBegin {
+            name: name.into(),
+            variant_index,
+            variant: variant.into(),
+            len,
+        })?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.event(Event::MapBegin { len })?;
+        Ok(self)
+    }
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.event(Event::StructBegin {
+            name: name.into(),
+            len,
+        })?;
+        Ok(self)
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.event(Event::StructVariantBegin {
+            name: name.into(),
+            variant_index,
+            variant: variant.into(),
+            len,
+        })?;
+        Ok(self)
+    }
+}
+
+impl<S> SerializeSeq for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeTuple for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeTupleStruct for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeTupleVariant for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeMap for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::Key)?;
+        key.serialize(&mut **self)
+    }
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::Value)?;
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeStruct for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::Field(key.into()))?;
+        value.serialize(&mut **self)
+    }
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.event(Event::Skip(key.into()))
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+impl<S> SerializeStructVariant for &mut EventSerializer<S>
+where
+    S: Sink,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.event(Event::Field(key.into()))?;
+        value.serialize(&mut **self)
+    }
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.event(Event::Skip(key.into()))
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.event(Event::End)
+    }
+}
+
+/// Serialize the value described by `events` into `serializer`.
+///
+/// Of the values following an [Event::Readability], the one matching
+/// [serde::Serializer::is_human_readable] is serialized.
+///
+/// Fails with a custom error if the events are not a single well-formed value.
+pub fn replay<I, S>(events: I, serializer: S) -> Result<S::Ok, S::Error>
+where
+    I: IntoIterator<Item = Event>,
+    S: serde::Serializer,
+{
+    let replay = Replay {
+        events: RefCell::new(events.into_iter().peekable()),
+    };
+    let ok = replay.value(serializer)?;
+    match replay.next() {
+        None => Ok(ok),
+        Some(event) => Err(S::Error::custom(format_args!(
+            "unexpected event {event:?} after the value"
+        ))),
+    }
+}
+
+/// Shared state of [replay], reading events one value at a time.
+struct Replay<I>
+where
+    I: Iterator<Item = Event>,
+{
+    events: RefCell<Peekable<I>>,
+}
+impl<I> Replay<I>
+where
+    I: Iterator<Item = Event>,
+{
+    fn next(&self) -> Option<Event> {
+        self.events.borrow_mut().next()
+    }
+
+    /// Consume the next event if it is [Event::End].
+    fn end(&self) -> bool {
+        self.events
+            .borrow_mut()
+            .next_if(|event| *event == Event::End)
+            .is_some()
+    }
+
+    /// Serialize the next value.
+    fn value<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let event = self.next().ok_or_else(|| unexpected(None, "a value"))?;
+        let value = Value(self);
+        match event {
+            Event::Leaf(leaf) => match leaf {
+                Leaf::Bool(v) => serializer.serialize_bool(v),
+                Leaf::I8(v) => serializer.serialize_i8(v),
+                Leaf::I16(v) => serializer.serialize_i16(v),
+                Leaf::I32(v) => serializer.serialize_i32(v),
+                Leaf::I64(v) => serializer.serialize_i64(v),
+                Leaf::I128(v) => serializer.serialize_i128(v),
+                Leaf::U8(v) => serializer.serialize_u8(v),
+                Leaf::U16(v) => serializer.serialize_u16(v),
+                Leaf::U32(v) => serializer.serialize_u32(v),
+                Leaf::U64(v) => serializer.serialize_u64(v),
+                Leaf::U128(v) => serializer.serialize_u128(v),
+                Leaf::F32(v) => serializer.serialize_f32(v),
+                Leaf::F64(v) => serializer.serialize_f64(v),
+                Leaf::Char(v) => serializer.serialize_char(v),
+                Leaf::Str(v) => serializer.serialize_str(&v),
+                Leaf::Bytes(v) => serializer.serialize_bytes(&v),
+                Leaf::None => serializer.serialize_none(),
+                Leaf::Unit => serializer.serialize_unit(),
+                Leaf::UnitStruct(name) => serializer.serialize_unit_struct(intern_name(&name)),
+                Leaf::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                } => serializer.serialize_unit_variant(
+                    intern_name(&name),
+                    variant_index,
+                    intern_name(&variant),
+                ),
+            },
+            Event::Readability => match serializer.is_human_readable() {
+                true => {
+                    let ok = self.value(serializer)?;
+                    self.skip().map_err(S::Error::custom)?;
+                    Ok(ok)
+                }
+                false => {
+                    self.skip().map_err(S::Error::custom)?;
+                    self.value(serializer)
+                }
+            },
+            Event::Some => serializer.serialize_some(&value),
+            Event::NewtypeStruct { name } => {
+                serializer.serialize_newtype_struct(intern_name(&name), &value)
+            }
+            Event::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+            } => serializer.serialize_newtype_variant(
+                intern_name(&name),
+                variant_index,
+                intern_name(&variant),
+                &value,
+            ),
+            Event::SeqBegin { len } => {
+                let mut s = serializer.serialize_seq(len)?;
+                while !self.end() {
+                    s.serialize_element(&value)?;
+                }
+                s.end()
+            }
+            Event::TupleBegin { len } => {
+                let mut s = serializer.serialize_tuple(len)?;
+                while !self.end() {
+                    s.serialize_element(&value)?;
+                }
+                s.end()
+            }
+            Event::TupleStructBegin { name, len } => {
+                let mut s = serializer.serialize_tuple_struct(intern_name(&name), len)?;
+                while !self.end() {
+                    s.serialize_field(&value)?;
+                }
+                s.end()
+            }
+            Event::TupleVariantBegin {
+                name,
+                variant_index,
+                variant,
+                len,
+            } => {
+                let mut s = serializer.serialize_tuple_variant(
+                    intern_name(&name),
+                    variant_index,
+                    intern_name(&variant),
+                    len,
+                )?;
+                while !self.end() {
+                    s.serialize_field(&value)?;
+                }
+                s.end()
+            }
+            Event::MapBegin { len } => {
+                let mut s = serializer.serialize_map(len)?;
+                loop {
+                    match self.next() {
+                        Some(Event::Key) => s.serialize_key(&value)?,
+                        Some(Event::Value) => s.serialize_value(&value)?,
+                        Some(Event::End) => return s.end(),
+                        event => return Err(unexpected(event, "a map key, value or end")),
+                    }
+                }
+            }
+            Event::StructBegin { name, len } => {
+                let mut s = serializer.serialize_struct(intern_name(&name), len)?;
+                loop {
+                    match self.next() {
+                        Some(Event::Field(key)) => s.serialize_field(intern_name(&key), &value)?,
+                        Some(Event::Skip(key)) => s.skip_field(intern_name(&key))?,
+                        Some(Event::End) => return s.end(),
+                        event => return Err(unexpected(event, "a struct field or end")),
+                    }
+                }
+            }
+            Event::StructVariantBegin {
+                name,
+                variant_index,
+                variant,
+                len,
+            } => {
+                let mut s = serializer.serialize_struct_variant(
+                    intern_name(&name),
+                    variant_index,
+                    intern_name(&variant),
+                    len,
+                )?;
+                loop {
+                    match self.next() {
+                        Some(Event::Field(key)) => s.serialize_field(intern_name(&key), &value)?,
+                        Some(Event::Skip(key)) => s.skip_field(intern_name(&key))?,
+                        Some(Event::End) => return s.end(),
+                        event => return Err(unexpected(event, "a struct field or end")),
+                    }
+                }
+            }
+            event @ (Event::Key | Event::Value | Event::Field(_) | Event::Skip(_) | Event::End) => {
+                Err(unexpected(Some(event), "a value"))
+            }
+        }
+    }
+}
+
+impl<I> Replay<I>
+where
+    I: Iterator<Item = Event>,
+{
+    /// Build the next value as an [Ast].
+    fn ast(&self) -> Result<Ast, Error> {
+        let event = self.next().ok_or_else(|| unexpected(None, "a value"))?;
+        let value = || self.ast().map(Box::new);
+        Ok(match event {
+            Event::Leaf(leaf) => match leaf {
+                Leaf::Bool(v) => XAst::Bool(v),
+                Leaf::I8(v) => XAst::I8(v),
+                Leaf::I16(v) => XAst::I16(v),
+                Leaf::I32(v) => XAst::I32(v),
+                Leaf::I64(v) => XAst::I64(v),
+                Leaf::I128(v) => XAst::I128(v),
+                Leaf::U8(v) => XAst::U8(v),
+                Leaf::U16(v) => XAst::U16(v),
+                Leaf::U32(v) => XAst::U32(v),
+                Leaf::U64(v) => XAst::U64(v),
+                Leaf::U128(v) => XAst::U128(v),
+                Leaf::F32(v) => XAst::F32(v),
+                Leaf::F64(v) => XAst::F64(v),
+                Leaf::Char(v) => XAst::Char(v),
+                Leaf::Str(v) => XAst::Str(v),
+                Leaf::Bytes(v) => XAst::Bytes(v),
+                Leaf::None => XAst::None,
+                Leaf::Unit => XAst::Unit,
+                Leaf::UnitStruct(name) => XAst::UnitStruct(name),
+                Leaf::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                } => XAst::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                },
+            },
+            Event::Readability => XAst::Readability {
+                human_readable: value()?,
+                compact: value()?,
+            },
+            Event::Some => XAst::Some(value()?),
+            Event::NewtypeStruct { name } => XAst::NewtypeStruct {
+                name,
+                value: value()?,
+            },
+            Event::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+            } => XAst::NewtypeVariant {
+                value: value().map_err(|e| e.at(Segment::Variant(intern_name(&variant))))?,
+                name,
+                variant_index,
+                variant,
+            },
+            Event::SeqBegin { len } => XAst::Seq {
+                len,
+                ops: self.elements(|value| Seq::Element { value })?,
+            },
+            Event::TupleBegin { len } => XAst::Tuple {
+                len,
+                ops: self.elements(|value| Tuple::Element { value })?,
+            },
+            Event::TupleStructBegin { name, len } => XAst::TupleStruct {
+                name,
+                len,
+                ops: self.elements(|value| TupleStruct::Field { value })?,
+            },
+            Event::TupleVariantBegin {
+                name,
+                variant_index,
+                variant,
+                len,
+            } => XAst::TupleVariant {
+                ops: self
+                    .elements(|value| TupleVariant::Field { value })
+                    .map_err(|e| e.at(Segment::Variant(intern_name(&variant))))?,
+                name,
+                variant_index,
+                variant,
+                len,
+            },
+            Event::MapBegin { len } => {
+                let mut ops = Vec::new();
+                loop {
+                    match self.next() {
+                        Some(Event::Key) => ops.push(Map::Key {
+                            key: value().map_err(|e| e.at(Segment::Index(ops.len() / 2)))?,
+                        }),
+                        Some(Event::Value) => {
+                            let segment = match ops.last() {
+                                Some(Map::Key { key }) => key_segment(key)
+                                    .map(|key| Segment::Key(key.into_owned()))
+                                    .unwrap_or(Segment::Index(ops.len() / 2)),
+                                _ => Segment::Index(ops.len() / 2),
+                            };
+                            ops.push(Map::Value {
+                                value: value().map_err(|e| e.at(segment))?,
+                            })
+                        }
+                        Some(Event::End) => break XAst::Map { len, ops },
+                        event => return Err(unexpected(event, "a map key, value or end")),
+                    }
+                }
+            }
+            Event::StructBegin { name, len } => XAst::Struct {
+                name,
+                len,
+                ops: self.fields(|key, value| match value {
+                    Some(value) => Struct::Field { key, value },
+                    None => Struct::Skip { key },
+                })?,
+            },
+            Event::StructVariantBegin {
+                name,
+                variant_index,
+                variant,
+                len,
+            } => XAst::StructVariant {
+                ops: self
+                    .fields(|key, value| match value {
+                        Some(value) => StructVariant::Field { key, value },
+                        None => StructVariant::Skip { key },
+                    })
+                    .map_err(|e| e.at(Segment::Variant(intern_name(&variant))))?,
+                name,
+                variant_index,
+                variant,
+                len,
+            },
+            event @ (Event::Key | Event::Value | Event::Field(_) | Event::Skip(_) | Event::End) => {
+                return Err(unexpected(Some(event), "a value"))
+            }
+        })
+    }
+
+    /// Consume the events of the next value without building it.
+    fn skip(&self) -> Result<(), Error> {
+        match self.next().ok_or_else(|| unexpected(None, "a value"))? {
+            Event::Leaf(_) => {}
+            Event::Some | Event::NewtypeStruct { .. } | Event::NewtypeVariant { .. } => {
+                self.skip()?
+            }
+            Event::Readability => {
+                self.skip()?;
+                self.skip()?;
+            }
+            Event::SeqBegin { .. }
+            | Event::TupleBegin { .. }
+            | Event::TupleStructBegin { .. }
+            | Event::TupleVariantBegin { .. } => {
+                while !self.end() {
+                    self.skip()?;
+                }
+            }
+            Event::MapBegin { .. } => loop {
+                match self.next() {
+                    Some(Event::Key | Event::Value) => self.skip()?,
+                    Some(Event::End) => break,
+                    event => return Err(unexpected(event, "a map key, value or end")),
+                }
+            },
+            Event::StructBegin { .. } | Event::StructVariantBegin { .. } => loop {
+                match self.next() {
+                    Some(Event::Field(_)) => self.skip()?,
+                    Some(Event::Skip(_)) => {}
+                    Some(Event::End) => break,
+                    event => return Err(unexpected(event, "a struct field or end")),
+                }
+            },
+            event @ (Event::Key | Event::Value | Event::Field(_) | Event::Skip(_) | Event::End) => {
+                return Err(unexpected(Some(event), "a value"))
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the values up to [Event::End].
+    fn elements<T>(&self, op: impl Fn(Box<Ast>) -> T) -> Result<Vec<T>, Error> {
+        let mut ops = Vec::new();
+        while !self.end() {
+            let value = self.ast().map_err(|e| e.at(Segment::Index(ops.len())))?;
+            ops.push(op(Box::new(value)));
+        }
+        Ok(ops)
+    }
+
+    /// Build the [Event::Field] and [Event::Skip] entries up to [Event::End].
+    fn fields<T>(&self, op: impl Fn(Name, Option<Box<Ast>>) -> T) -> Result<Vec<T>, Error> {
+        let mut ops = Vec::new();
+        loop {
+            match self.next() {
+                Some(Event::Field(key)) => {
+                    let value = self
+                        .ast()
+                        .map_err(|e| e.at(Segment::Field(intern_name(&key))))?;
+                    ops.push(op(key, Some(Box::new(value))));
+                }
+                Some(Event::Skip(key)) => ops.push(op(key, None)),
+                Some(Event::End) => return Ok(ops),
+                event => return Err(unexpected(event, "a struct field or end")),
+            }
+        }
+    }
+}
+
+/// Serialize the next value of a [Replay].
+struct Value<'r, I>(&'r Replay<I>)
+where
+    I: Iterator<Item = Event>;
+impl<I> Serialize for Value<'_, I>
+where
+    I: Iterator<Item = Event>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.value(serializer)
+    }
+}
+
+fn unexpected<E>(event: Option<Event>, expected: &str) -> E
+where
+    E: serde::ser::Error,
+{
+    match event {
+        Some(event) => E::custom(format_args!(
+            "unexpected event {event:?}, expected {expected}"
+        )),
+        None => E::custom(format_args!(
+            "unexpected end of events, expected {expected}"
+        )),
+    }
+}
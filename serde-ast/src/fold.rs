@@ -0,0 +1,601 @@
+//! Rewrite an [XAst] by value.
+//!
+//! Each method of [Fold] defaults to the free function of the same name, which folds the children
+//! of the node and rebuilds it. Override a method to transform a node, and call the free function
+//! to keep folding its children.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{ast::XAst, fold::Fold, to_ast};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     hello: String,
+//!     count: u8,
+//! }
+//! let example = Example { hello: "World".to_string(), count: 3 };
+//! let ast = to_ast(&example).expect("serialize to_ast");
+//!
+//! struct Redact;
+//! impl<X> Fold<X> for Redact {
+//!     fn fold_str(&mut self, _v: String) -> XAst<X> {
+//!         XAst::Str("***".to_string())
+//!     }
+//! }
+//! let ast = Redact.fold_ast(ast);
+//! assert_eq!(serde_json::to_string(&ast).unwrap(), r#"{"hello":"***","count":3}"#);
+//! ```
+
+use crate::ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst};
+
+/// Rewrite an [XAst] by value.
+///
+/// See the [module documentation](self).
+pub trait Fold<X> {
+    /// Fold any node, dispatching to the method for its variant.
+    fn fold_ast(&mut self, node: XAst<X>) -> XAst<X> {
+        fold_ast(self, node)
+    }
+
+    /// Fold [XAst::Bool].
+    fn fold_bool(&mut self, v: bool) -> XAst<X> {
+        XAst::Bool(v)
+    }
+    /// Fold [XAst::I8].
+    fn fold_i8(&mut self, v: i8) -> XAst<X> {
+        XAst::I8(v)
+    }
+    /// Fold [XAst::I16].
+    fn fold_i16(&mut self, v: i16) -> XAst<X> {
+        XAst::I16(v)
+    }
+    /// Fold [XAst::I32].
+    fn fold_i32(&mut self, v: i32) -> XAst<X> {
+        XAst::I32(v)
+    }
+    /// Fold [XAst::I64].
+    fn fold_i64(&mut self, v: i64) -> XAst<X> {
+        XAst::I64(v)
+    }
+    /// Fold [XAst::I128].
+    fn fold_i128(&mut self, v: i128) -> XAst<X> {
+        XAst::I128(v)
+    }
+    /// Fold [XAst::U8].
+    fn fold_u8(&mut self, v: u8) -> XAst<X> {
+        XAst::U8(v)
+    }
+    /// Fold [XAst::U16].
+    fn fold_u16(&mut self, v: u16) -> XAst<X> {
+        XAst::U16(v)
+    }
+    /// Fold [XAst::U32].
+    fn fold_u32(&mut self, v: u32) -> XAst<X> {
+        XAst::U32(v)
+    }
+    /// Fold [XAst::U64].
+    fn fold_u64(&mut self, v: u64) -> XAst<X> {
+        XAst::U64(v)
+    }
+    /// Fold [XAst::U128].
+    fn fold_u128(&mut self, v: u128) -> XAst<X> {
+        XAst::U128(v)
+    }
+    /// Fold [XAst::F32].
+    fn fold_f32(&mut self, v: f32) -> XAst<X> {
+        XAst::F32(v)
+    }
+    /// Fold [XAst::F64].
+    fn fold_f64(&mut self, v: f64) -> XAst<X> {
+        XAst::F64(v)
+    }
+    /// Fold [XAst::Char].
+    fn fold_char(&mut self, v: char) -> XAst<X> {
+        XAst::Char(v)
+    }
+    /// Fold [XAst::Str].
+    fn fold_str(&mut self, v: String) -> XAst<X> {
+        XAst::Str(v)
+    }
+    /// Fold [XAst::Bytes].
+    fn fold_bytes(&mut self, v: Vec<u8>) -> XAst<X> {
+        XAst::Bytes(v)
+    }
+
+    /// Fold [XAst::None].
+    fn fold_none(&mut self) -> XAst<X> {
+        XAst::None
+    }
+    /// Fold [XAst::Some].
+    fn fold_some(&mut self, value: XAst<X>) -> XAst<X> {
+        fold_some(self, value)
+    }
+
+    /// Fold [XAst::Unit].
+    fn fold_unit(&mut self) -> XAst<X> {
+        XAst::Unit
+    }
+    /// Fold [XAst::UnitStruct].
+    fn fold_unit_struct(&mut self, name: Name) -> XAst<X> {
+        XAst::UnitStruct(name)
+    }
+    /// Fold [XAst::UnitVariant].
+    fn fold_unit_variant(&mut self, name: Name, variant_index: u32, variant: Name) -> XAst<X> {
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        }
+    }
+
+    /// Fold [XAst::NewtypeStruct].
+    fn fold_newtype_struct(&mut self, name: Name, value: XAst<X>) -> XAst<X> {
+        fold_newtype_struct(self, name, value)
+    }
+    /// Fold [XAst::NewtypeVariant].
+    fn fold_newtype_variant(
+        &mut self,
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        value: XAst<X>,
+    ) -> XAst<X> {
+        fold_newtype_variant(self, name, variant_index, variant, value)
+    }
+
+    /// Fold [XAst::Seq].
+    fn fold_seq(&mut self, len: Option<usize>, ops: Vec<Seq<X>>) -> XAst<X> {
+        fold_seq(self, len, ops)
+    }
+    /// Fold [XAst::Tuple].
+    fn fold_tuple(&mut self, len: usize, ops: Vec<Tuple<X>>) -> XAst<X> {
+        fold_tuple(self, len, ops)
+    }
+    /// Fold [XAst::TupleStruct].
+    fn fold_tuple_struct(&mut self, name: Name, len: usize, ops: Vec<TupleStruct<X>>) -> XAst<X> {
+        fold_tuple_struct(self, name, len, ops)
+    }
+    /// Fold [XAst::TupleVariant].
+    fn fold_tuple_variant(
+        &mut self,
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        len: usize,
+        ops: Vec<TupleVariant<X>>,
+    ) -> XAst<X> {
+        fold_tuple_variant(self, name, variant_index, variant, len, ops)
+    }
+    /// Fold [XAst::Map].
+    fn fold_map(&mut self, len: Option<usize>, ops: Vec<Map<X>>) -> XAst<X> {
+        fold_map(self, len, ops)
+    }
+    /// Fold [XAst::Struct].
+    fn fold_struct(&mut self, name: Name, len: usize, ops: Vec<Struct<X>>) -> XAst<X> {
+        fold_struct(self, name, len, ops)
+    }
+    /// Fold [XAst::StructVariant].
+    fn fold_struct_variant(
+        &mut self,
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        len: usize,
+        ops: Vec<StructVariant<X>>,
+    ) -> XAst<X> {
+        fold_struct_variant(self, name, variant_index, variant, len, ops)
+    }
+
+    /// Fold [XAst::Readability].
+    fn fold_readability(&mut self, human_readable: XAst<X>, compact: XAst<X>) -> XAst<X> {
+        fold_readability(self, human_readable, compact)
+    }
+
+    /// Fold [XAst::X].
+    fn fold_x(&mut self, x: X) -> XAst<X> {
+        XAst::X(x)
+    }
+
+    /// Fold a [Seq] operation.
+    fn fold_seq_op(&mut self, op: Seq<X>) -> Seq<X> {
+        fold_seq_op(self, op)
+    }
+    /// Fold a [Tuple] operation.
+    fn fold_tuple_op(&mut self, op: Tuple<X>) -> Tuple<X> {
+        fold_tuple_op(self, op)
+    }
+    /// Fold a [TupleStruct] operation.
+    fn fold_tuple_struct_op(&mut self, op: TupleStruct<X>) -> TupleStruct<X> {
+        fold_tuple_struct_op(self, op)
+    }
+    /// Fold a [TupleVariant] operation.
+    fn fold_tuple_variant_op(&mut self, op: TupleVariant<X>) -> TupleVariant<X> {
+        fold_tuple_variant_op(self, op)
+    }
+    /// Fold a [Map] operation.
+    fn fold_map_op(&mut self, op: Map<X>) -> Map<X> {
+        fold_map_op(self, op)
+    }
+    /// Fold a [Struct] operation.
+    fn fold_struct_op(&mut self, op: Struct<X>) -> Struct<X> {
+        fold_struct_op(self, op)
+    }
+    /// Fold a [StructVariant] operation.
+    fn fold_struct_variant_op(&mut self, op: StructVariant<X>) -> StructVariant<X> {
+        fold_struct_variant_op(self, op)
+    }
+
+    /// Fold the `key` of [Map::Key].
+    fn fold_map_key(&mut self, key: XAst<X>) -> XAst<X> {
+        fold_map_key(self, key)
+    }
+    /// Fold the `value` of [Map::Value].
+    fn fold_map_value(&mut self, value: XAst<X>) -> XAst<X> {
+        fold_map_value(self, value)
+    }
+    /// Fold [Struct::Field].
+    fn fold_struct_field(&mut self, key: Name, value: XAst<X>) -> Struct<X> {
+        fold_struct_field(self, key, value)
+    }
+    /// Fold [Struct::Skip].
+    fn fold_struct_skip(&mut self, key: Name) -> Struct<X> {
+        Struct::Skip { key }
+    }
+    /// Fold [StructVariant::Field].
+    fn fold_struct_variant_field(&mut self, key: Name, value: XAst<X>) -> StructVariant<X> {
+        fold_struct_variant_field(self, key, value)
+    }
+    /// Fold [StructVariant::Skip].
+    fn fold_struct_variant_skip(&mut self, key: Name) -> StructVariant<X> {
+        StructVariant::Skip { key }
+    }
+}
+
+/// Dispatch `node` to the [Fold] method for its variant.
+pub fn fold_ast<F, X>(f: &mut F, node: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match node {
+        XAst::Bool(x) => f.fold_bool(x),
+        XAst::I8(x) => f.fold_i8(x),
+        XAst::I16(x) => f.fold_i16(x),
+        XAst::I32(x) => f.fold_i32(x),
+        XAst::I64(x) => f.fold_i64(x),
+        XAst::I128(x) => f.fold_i128(x),
+        XAst::U8(x) => f.fold_u8(x),
+        XAst::U16(x) => f.fold_u16(x),
+        XAst::U32(x) => f.fold_u32(x),
+        XAst::U64(x) => f.fold_u64(x),
+        XAst::U128(x) => f.fold_u128(x),
+        XAst::F32(x) => f.fold_f32(x),
+        XAst::F64(x) => f.fold_f64(x),
+        XAst::Char(x) => f.fold_char(x),
+        XAst::Str(x) => f.fold_str(x),
+        XAst::Bytes(x) => f.fold_bytes(x),
+        XAst::None => f.fold_none(),
+        XAst::Some(value) => f.fold_some(*value),
+        XAst::Unit => f.fold_unit(),
+        XAst::UnitStruct(name) => f.fold_unit_struct(name),
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => f.fold_unit_variant(name, variant_index, variant),
+        XAst::NewtypeStruct { name, value } => f.fold_newtype_struct(name, *value),
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => f.fold_newtype_variant(name, variant_index, variant, *value),
+        XAst::Seq { len, ops } => f.fold_seq(len, ops),
+        XAst::Tuple { len, ops } => f.fold_tuple(len, ops),
+        XAst::TupleStruct { name, len, ops } => f.fold_tuple_struct(name, len, ops),
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => f.fold_tuple_variant(name, variant_index, variant, len, ops),
+        XAst::Map { len, ops } => f.fold_map(len, ops),
+        XAst::Struct { name, len, ops } => f.fold_struct(name, len, ops),
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => f.fold_struct_variant(name, variant_index, variant, len, ops),
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => f.fold_readability(*human_readable, *compact),
+        XAst::X(x) => f.fold_x(x),
+    }
+}
+
+/// Fold the `value` of [XAst::Some].
+pub fn fold_some<F, X>(f: &mut F, value: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Some(Box::new(f.fold_ast(value)))
+}
+
+/// Fold the `value` of [XAst::NewtypeStruct].
+pub fn fold_newtype_struct<F, X>(f: &mut F, name: Name, value: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::NewtypeStruct {
+        name,
+        value: Box::new(f.fold_ast(value)),
+    }
+}
+
+/// Fold the `value` of [XAst::NewtypeVariant].
+pub fn fold_newtype_variant<F, X>(
+    f: &mut F,
+    name: Name,
+    variant_index: u32,
+    variant: Name,
+    value: XAst<X>,
+) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::NewtypeVariant {
+        name,
+        variant_index,
+        variant,
+        value: Box::new(f.fold_ast(value)),
+    }
+}
+
+/// Fold the `ops` of [XAst::Seq].
+pub fn fold_seq<F, X>(f: &mut F, len: Option<usize>, ops: Vec<Seq<X>>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Seq {
+        len,
+        ops: ops.into_iter().map(|op| f.fold_seq_op(op)).collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::Tuple].
+pub fn fold_tuple<F, X>(f: &mut F, len: usize, ops: Vec<Tuple<X>>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Tuple {
+        len,
+        ops: ops.into_iter().map(|op| f.fold_tuple_op(op)).collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::TupleStruct].
+pub fn fold_tuple_struct<F, X>(
+    f: &mut F,
+    name: Name,
+    len: usize,
+    ops: Vec<TupleStruct<X>>,
+) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::TupleStruct {
+        name,
+        len,
+        ops: ops
+            .into_iter()
+            .map(|op| f.fold_tuple_struct_op(op))
+            .collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::TupleVariant].
+pub fn fold_tuple_variant<F, X>(
+    f: &mut F,
+    name: Name,
+    variant_index: u32,
+    variant: Name,
+    len: usize,
+    ops: Vec<TupleVariant<X>>,
+) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::TupleVariant {
+        name,
+        variant_index,
+        variant,
+        len,
+        ops: ops
+            .into_iter()
+            .map(|op| f.fold_tuple_variant_op(op))
+            .collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::Map].
+pub fn fold_map<F, X>(f: &mut F, len: Option<usize>, ops: Vec<Map<X>>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Map {
+        len,
+        ops: ops.into_iter().map(|op| f.fold_map_op(op)).collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::Struct].
+pub fn fold_struct<F, X>(f: &mut F, name: Name, len: usize, ops: Vec<Struct<X>>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Struct {
+        name,
+        len,
+        ops: ops.into_iter().map(|op| f.fold_struct_op(op)).collect(),
+    }
+}
+
+/// Fold the `ops` of [XAst::StructVariant].
+pub fn fold_struct_variant<F, X>(
+    f: &mut F,
+    name: Name,
+    variant_index: u32,
+    variant: Name,
+    len: usize,
+    ops: Vec<StructVariant<X>>,
+) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::StructVariant {
+        name,
+        variant_index,
+        variant,
+        len,
+        ops: ops
+            .into_iter()
+            .map(|op| f.fold_struct_variant_op(op))
+            .collect(),
+    }
+}
+
+/// Fold both values of [XAst::Readability].
+pub fn fold_readability<F, X>(f: &mut F, human_readable: XAst<X>, compact: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    XAst::Readability {
+        human_readable: Box::new(f.fold_ast(human_readable)),
+        compact: Box::new(f.fold_ast(compact)),
+    }
+}
+
+/// Fold a [Seq] operation.
+pub fn fold_seq_op<F, X>(f: &mut F, op: Seq<X>) -> Seq<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        Seq::Element { value } => Seq::Element {
+            value: Box::new(f.fold_ast(*value)),
+        },
+    }
+}
+
+/// Fold a [Tuple] operation.
+pub fn fold_tuple_op<F, X>(f: &mut F, op: Tuple<X>) -> Tuple<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        Tuple::Element { value } => Tuple::Element {
+            value: Box::new(f.fold_ast(*value)),
+        },
+    }
+}
+
+/// Fold a [TupleStruct] operation.
+pub fn fold_tuple_struct_op<F, X>(f: &mut F, op: TupleStruct<X>) -> TupleStruct<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        TupleStruct::Field { value } => TupleStruct::Field {
+            value: Box::new(f.fold_ast(*value)),
+        },
+    }
+}
+
+/// Fold a [TupleVariant] operation.
+pub fn fold_tuple_variant_op<F, X>(f: &mut F, op: TupleVariant<X>) -> TupleVariant<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        TupleVariant::Field { value } => TupleVariant::Field {
+            value: Box::new(f.fold_ast(*value)),
+        },
+    }
+}
+
+/// Dispatch a [Map] operation.
+pub fn fold_map_op<F, X>(f: &mut F, op: Map<X>) -> Map<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        Map::Key { key } => Map::Key {
+            key: Box::new(f.fold_map_key(*key)),
+        },
+        Map::Value { value } => Map::Value {
+            value: Box::new(f.fold_map_value(*value)),
+        },
+    }
+}
+
+/// Dispatch a [Struct] operation.
+pub fn fold_struct_op<F, X>(f: &mut F, op: Struct<X>) -> Struct<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        Struct::Field { key, value } => f.fold_struct_field(key, *value),
+        Struct::Skip { key } => f.fold_struct_skip(key),
+    }
+}
+
+/// Dispatch a [StructVariant] operation.
+pub fn fold_struct_variant_op<F, X>(f: &mut F, op: StructVariant<X>) -> StructVariant<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    match op {
+        StructVariant::Field { key, value } => f.fold_struct_variant_field(key, *value),
+        StructVariant::Skip { key } => f.fold_struct_variant_skip(key),
+    }
+}
+
+/// Fold the `key` of [Map::Key].
+pub fn fold_map_key<F, X>(f: &mut F, key: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    f.fold_ast(key)
+}
+
+/// Fold the `value` of [Map::Value].
+pub fn fold_map_value<F, X>(f: &mut F, value: XAst<X>) -> XAst<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    f.fold_ast(value)
+}
+
+/// Fold the `value` of [Struct::Field].
+pub fn fold_struct_field<F, X>(f: &mut F, key: Name, value: XAst<X>) -> Struct<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    Struct::Field {
+        key,
+        value: Box::new(f.fold_ast(value)),
+    }
+}
+
+/// Fold the `value` of [StructVariant::Field].
+pub fn fold_struct_variant_field<F, X>(f: &mut F, key: Name, value: XAst<X>) -> StructVariant<X>
+where
+    F: Fold<X> + ?Sized,
+{
+    StructVariant::Field {
+        key,
+        value: Box::new(f.fold_ast(value)),
+    }
+}
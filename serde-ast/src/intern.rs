@@ -0,0 +1,48 @@
+//! Intern runtime strings as `&'static str`.
+//!
+//! [serde::Serializer] requires `&'static str` names, while an [Ast](crate::Ast) built or decoded
+//! at runtime owns its [Name]s. The global interner bridges the two: every distinct string is
+//! leaked once and reused afterwards, so memory grows with the number of distinct names, not
+//! with the number of serializations.
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::ast::Name;
+
+/// Return a `&'static str` equal to `s`.
+///
+/// Every distinct string is leaked once and reused afterwards.
+///
+/// ```
+/// # use serde_ast::intern::intern;
+/// let name = String::from("Example");
+/// assert!(std::ptr::eq(intern(&name), intern("Example")));
+/// ```
+pub fn intern(s: &str) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match interned.get(s) {
+        Some(s) => s,
+        None => {
+            let s: &'static str = Box::leak(s.to_owned().into_boxed_str());
+            interned.insert(s);
+            s
+        }
+    }
+}
+
+/// Return a `&'static str` equal to `name`, [interning](intern) only owned names.
+pub fn intern_name(name: &Name) -> &'static str {
+    match name {
+        Cow::Borrowed(name) => name,
+        Cow::Owned(name) => intern(name),
+    }
+}
@@ -26,17 +26,8 @@
 //! println!("{}", ast);
 //! ```
 //! ```text
-//! Struct {
-//!     name: "Example",
-//!     len: 1,
-//!     ops: [
-//!         Field {
-//!             key: "hello",
-//!             value: Str(
-//!                 "World",
-//!             ),
-//!         },
-//!     ],
+//! Example {
+//!   hello: "World",
 //! }
 //! ```
 //!
@@ -58,22 +49,99 @@
 //! // the result is the same
 //! assert_eq!(output, direct);
 //! ```
+//!
+//! The [Ast] can also be deserialized into a value, without going through any data format.
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_ast::{from_ast, to_ast};
+//! # #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! # struct Example {
+//! #     hello: String,
+//! # }
+//! # let example = Example { hello: "World".to_string() };
+//! let ast = to_ast(&example).expect("serialize to_ast");
+//! let output: Example = from_ast(&ast).expect("deserialize from_ast");
+//! assert_eq!(output, example);
+//! ```
 
 pub mod ast;
+pub mod de;
+pub mod diff;
+pub mod events;
+pub mod fold;
+pub mod intern;
+pub mod merge;
+pub mod normalize;
+pub mod patch;
+pub mod path;
+pub mod persist;
+pub mod pretty;
 pub mod ser;
+pub mod text;
+pub mod total;
+pub mod validate;
+pub mod visit;
+pub mod visit_mut;
 
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
 pub use ast::Ast;
-pub use ser::Serializer;
+pub use de::Deserializer;
+pub use diff::diff;
+pub use ser::{Readability, Serializer};
 
-/// Serialize a value into [Ast].
+/// Serialize a value into human-readable [Ast].
 ///
-/// Serializing the [Ast] is equivalent to directly serializing the value.
+/// Serializing the [Ast] is equivalent to directly serializing the value into a human-readable
+/// format, see [Serializer::serialize_both] to also support compact formats.
 pub fn to_ast<T>(value: &T) -> Result<Ast, ser::Error>
 where
     T: Serialize + ?Sized,
 {
-    let serializer = Serializer::new();
+    let serializer = Serializer::new(Readability::HumanReadable);
     value.serialize(serializer)
 }
+
+/// Serialize a value into human-readable [Ast], keeping what was recorded on failure.
+///
+/// The value is serialized once with the [ser::Partial] hook, continuing past the failed values
+/// and marking each of them with an [XAst::X](ast::XAst::X) [ser::Failure] node. The first of
+/// them, at its full path, is the returned [ser::PartialError::error].
+///
+/// ```
+/// # use serde_ast::{ast::XAst, to_ast_partial};
+/// # struct Fails;
+/// # impl serde::Serialize for Fails {
+/// #     fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+/// #         Err(serde::ser::Error::custom("fails"))
+/// #     }
+/// # }
+/// let error = to_ast_partial(&(1, Fails)).unwrap_err();
+/// assert_eq!(error.to_string(), "fails at [1]");
+/// let XAst::Tuple { ops, .. } = *error.partial else { panic!() };
+/// assert_eq!(ops.len(), 2);
+/// ```
+pub fn to_ast_partial<T>(value: &T) -> Result<Ast, ser::PartialError>
+where
+    T: Serialize + ?Sized,
+{
+    let partial = value
+        .serialize(Serializer::with_hook(ser::Partial))
+        .unwrap_or_else(|error| ast::XAst::X(ser::Failure(error)));
+    match ser::first_failure(&partial) {
+        Some(error) => Err(ser::PartialError {
+            error,
+            partial: Box::new(partial),
+        }),
+        None => Ok(partial.map_x(|_| unreachable!("no failure was recorded"))),
+    }
+}
+
+/// Deserialize a value from [Ast].
+pub fn from_ast<T>(ast: &Ast) -> Result<T, de::Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Deserializer::new(ast))
+}
@@ -0,0 +1,744 @@
+//! Deep [merge](XAst::merge) of two [XAst], for layering configuration.
+//!
+//! The overlay is merged into the base:
+//! - [XAst::Struct] and [XAst::StructVariant] fields are merged by key, keeping the order and
+//!   shape of the base, new fields are appended
+//! - a [XAst::Map] with string-like keys (see [key_segment]) can be merged into a [XAst::Struct],
+//!   e.g. an override loaded from a data format
+//! - [XAst::Map] entries are merged by their key [XAst], string-like keys also match by their
+//!   [key_segment] (e.g. `"22"` matches `22u16`), new entries are appended
+//! - [XAst::Seq] elements are merged according to [SeqStrategy]
+//! - [XAst::Tuple], [XAst::TupleStruct] and [XAst::TupleVariant] of the same length are merged by index
+//! - [XAst::Some], [XAst::NewtypeStruct] and [XAst::NewtypeVariant] merge their values,
+//!   [XAst::Some] and [XAst::NewtypeStruct] are transparent to an overlay value which is not wrapped
+//! - leaves are replaced by an overlay leaf of the same variant, [XAst::None] and [XAst::Some]
+//!   replace each other, an overlay [XAst::Unit] (e.g. a `null` loaded from a data format)
+//!   replaces either with [XAst::None]
+//! - an overlay [XAst::Str] replaces a [XAst::UnitVariant] with the variant of that name, the
+//!   index of another variant is unknown and set to 0 like for a deserialized [XAst]
+//! - numbers are converted to the type of the base if the value fits, e.g. a [XAst::U64] loaded
+//!   from a data format replaces a [XAst::U16], integers also replace floats
+//!
+//! Any other pair of nodes, which differ in variant, name, variant index or tuple length, is a
+//! conflict resolved according to [ConflictStrategy].
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{ast::XAst, merge::MergeStrategy, to_ast};
+//! #[derive(Serialize)]
+//! struct Config {
+//!     host: String,
+//!     port: u16,
+//! }
+//! let defaults = to_ast(&Config { host: "localhost".to_string(), port: 80 }).unwrap();
+//! let overrides = serde_json::from_str(r#"{ "port": 443 }"#).unwrap();
+//!
+//! let merged = XAst::merge(defaults, overrides, &MergeStrategy::default()).unwrap();
+//! assert_eq!(serde_json::to_string(&merged).unwrap(), r#"{"host":"localhost","port":443}"#);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    path::{key_segment, AstPath},
+    total::Total,
+};
+
+/// How to merge [XAst::Seq] elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeqStrategy {
+    /// Replace the base sequence with the overlay sequence.
+    #[default]
+    Replace,
+    /// Append the overlay elements after the base elements.
+    Append,
+    /// Merge elements at the same index, keeping the extra elements of the longer sequence.
+    ByIndex,
+}
+
+/// How to resolve a conflict between nodes of different types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Fail the merge with an [Error].
+    #[default]
+    Error,
+    /// Replace the base node with the overlay node.
+    OverlayWins,
+}
+
+/// Options for [XAst::merge].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    /// How to merge [XAst::Seq] elements.
+    pub seq: SeqStrategy,
+    /// How to resolve a conflict between nodes of different types.
+    pub conflict: ConflictStrategy,
+}
+
+/// Error merging two [XAst].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("cannot merge {overlay} into {base} at `{path}`")]
+pub struct Error {
+    /// Path of the conflicting nodes.
+    pub path: AstPath,
+    /// Variant of the base node.
+    pub base: &'static str,
+    /// Variant of the overlay node.
+    pub overlay: &'static str,
+}
+
+impl<X> XAst<X>
+where
+    X: Ord,
+{
+    /// Deep merge `overlay` into `base`, see the [module documentation](crate::merge).
+    pub fn merge(base: Self, overlay: Self, strategy: &MergeStrategy) -> Result<Self, Error> {
+        Merger { strategy }.node(&mut AstPath::root(), base, overlay)
+    }
+}
+impl<X> XAst<X> {
+    /// Name of the variant of this node.
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "Bool",
+            Self::I8(_) => "I8",
+            Self::I16(_) => "I16",
+            Self::I32(_) => "I32",
+            Self::I64(_) => "I64",
+            Self::I128(_) => "I128",
+            Self::U8(_) => "U8",
+            Self::U16(_) => "U16",
+            Self::U32(_) => "U32",
+            Self::U64(_) => "U64",
+            Self::U128(_) => "U128",
+            Self::F32(_) => "F32",
+            Self::F64(_) => "F64",
+            Self::Char(_) => "Char",
+            Self::Str(_) => "Str",
+            Self::Bytes(_) => "Bytes",
+            Self::None => "None",
+            Self::Some(_) => "Some",
+            Self::Unit => "Unit",
+            Self::UnitStruct(_) => "UnitStruct",
+            Self::UnitVariant { .. } => "UnitVariant",
+            Self::NewtypeStruct { .. } => "NewtypeStruct",
+            Self::NewtypeVariant { .. } => "NewtypeVariant",
+            Self::Seq { .. } => "Seq",
+            Self::Tuple { .. } => "Tuple",
+            Self::TupleStruct { .. } => "TupleStruct",
+            Self::TupleVariant { .. } => "TupleVariant",
+            Self::Map { .. } => "Map",
+            Self::Struct { .. } => "Struct",
+            Self::StructVariant { .. } => "StructVariant",
+            Self::Readability { .. } => "Readability",
+            Self::X(_) => "X",
+        }
+    }
+
+    /// Check if this node has no children.
+    fn is_leaf(&self) -> bool {
+        !matches!(
+            self,
+            Self::Some(_)
+                | Self::NewtypeStruct { .. }
+                | Self::NewtypeVariant { .. }
+                | Self::Seq { .. }
+                | Self::Tuple { .. }
+                | Self::TupleStruct { .. }
+                | Self::TupleVariant { .. }
+                | Self::Map { .. }
+                | Self::Struct { .. }
+                | Self::StructVariant { .. }
+                | Self::Readability { .. }
+        )
+    }
+}
+
+struct Merger<'s> {
+    strategy: &'s MergeStrategy,
+}
+impl Merger<'_> {
+    fn node<X: Ord>(
+        &self,
+        path: &mut AstPath,
+        base: XAst<X>,
+        overlay: XAst<X>,
+    ) -> Result<XAst<X>, Error> {
+        match (base, overlay) {
+            (XAst::Some(base), XAst::Some(overlay)) => {
+                Ok(XAst::Some(Box::new(self.node(path, *base, *overlay)?)))
+            }
+            (XAst::None | XAst::Some(_), overlay @ (XAst::None | XAst::Some(_))) => Ok(overlay),
+            (XAst::None | XAst::Some(_), XAst::Unit) => Ok(XAst::None),
+            (XAst::Some(base), overlay) => {
+                Ok(XAst::Some(Box::new(self.node(path, *base, overlay)?)))
+            }
+            (XAst::None, overlay) => Ok(XAst::Some(Box::new(overlay))),
+            (
+                XAst::NewtypeStruct { name, value },
+                XAst::NewtypeStruct {
+                    name: overlay_name,
+                    value: overlay,
+                },
+            ) if name == overlay_name => Ok(XAst::NewtypeStruct {
+                name,
+                value: Box::new(self.node(path, *value, *overlay)?),
+            }),
+            (XAst::NewtypeStruct { name, value }, overlay)
+                if !matches!(overlay, XAst::NewtypeStruct { .. }) =>
+            {
+                Ok(XAst::NewtypeStruct {
+                    name,
+                    value: Box::new(self.node(path, *value, overlay)?),
+                })
+            }
+            (
+                XAst::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                },
+                XAst::Str(overlay),
+            ) => Ok(match variant == overlay.as_str() {
+                true => XAst::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                },
+                false => XAst::UnitVariant {
+                    name,
+                    variant_index: 0,
+                    variant: Name::Owned(overlay),
+                },
+            }),
+            (
+                XAst::NewtypeVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    value,
+                },
+                XAst::NewtypeVariant {
+                    name: overlay_name,
+                    variant_index: overlay_index,
+                    variant: overlay_variant,
+                    value: overlay,
+                },
+            ) if (&name, variant_index, &variant)
+                == (&overlay_name, overlay_index, &overlay_variant) =>
+            {
+                Ok(XAst::NewtypeVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    value: Box::new(self.node(path, *value, *overlay)?),
+                })
+            }
+            (
+                XAst::Seq { len, ops },
+                XAst::Seq {
+                    len: overlay_len,
+                    ops: overlay,
+                },
+            ) => {
+                let base = ops.into_iter().map(|op| match op {
+                    Seq::Element { value } => value,
+                });
+                let overlay = overlay.into_iter().map(|op| match op {
+                    Seq::Element { value } => value,
+                });
+                let (len, values) = match self.strategy.seq {
+                    SeqStrategy::Replace => (overlay_len, overlay.collect()),
+                    SeqStrategy::Append => (
+                        len.zip(overlay_len).map(|(len, overlay)| len + overlay),
+                        base.chain(overlay).collect(),
+                    ),
+                    SeqStrategy::ByIndex => {
+                        let values: Vec<_> = self.elements(path, base, overlay)?;
+                        (len.map(|_| values.len()), values)
+                    }
+                };
+                Ok(XAst::Seq {
+                    len,
+                    ops: values
+                        .into_iter()
+                        .map(|value| Seq::Element { value })
+                        .collect(),
+                })
+            }
+            (XAst::Tuple { len, ops }, XAst::Tuple { ops: overlay, .. })
+                if ops.len() == overlay.len() =>
+            {
+                let base = ops.into_iter().map(|op| match op {
+                    Tuple::Element { value } => value,
+                });
+                let overlay = overlay.into_iter().map(|op| match op {
+                    Tuple::Element { value } => value,
+                });
+                Ok(XAst::Tuple {
+                    len,
+                    ops: self
+                        .elements(path, base, overlay)?
+                        .into_iter()
+                        .map(|value| Tuple::Element { value })
+                        .collect(),
+                })
+            }
+            (
+                XAst::TupleStruct { name, len, ops },
+                XAst::TupleStruct {
+                    name: overlay_name,
+                    ops: overlay,
+                    ..
+                },
+            ) if name == overlay_name && ops.len() == overlay.len() => {
+                let base = ops.into_iter().map(|op| match op {
+                    TupleStruct::Field { value } => value,
+                });
+                let overlay = overlay.into_iter().map(|op| match op {
+                    TupleStruct::Field { value } => value,
+                });
+                Ok(XAst::TupleStruct {
+                    name,
+                    len,
+                    ops: self
+                        .elements(path, base, overlay)?
+                        .into_iter()
+                        .map(|value| TupleStruct::Field { value })
+                        .collect(),
+                })
+            }
+            (
+                XAst::TupleVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len,
+                    ops,
+                },
+                XAst::TupleVariant {
+                    name: overlay_name,
+                    variant_index: overlay_index,
+                    variant: overlay_variant,
+                    ops: overlay,
+                    ..
+                },
+            ) if (&name, variant_index, &variant)
+                == (&overlay_name, overlay_index, &overlay_variant)
+                && ops.len() == overlay.len() =>
+            {
+                let base = ops.into_iter().map(|op| match op {
+                    TupleVariant::Field { value } => value,
+                });
+                let overlay = overlay.into_iter().map(|op| match op {
+                    TupleVariant::Field { value } => value,
+                });
+                Ok(XAst::TupleVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len,
+                    ops: self
+                        .elements(path, base, overlay)?
+                        .into_iter()
+                        .map(|value| TupleVariant::Field { value })
+                        .collect(),
+                })
+            }
+            (
+                XAst::Struct { name, len, ops },
+                XAst::Struct {
+                    name: overlay_name,
+                    ops: overlay,
+                    ..
+                },
+            ) if name == overlay_name => {
+                let (len, fields) = self.fields(
+                    path,
+                    len,
+                    ops.into_iter().map(|op| match op {
+                        Struct::Field { key, value } => (key, Some(value)),
+                        Struct::Skip { key } => (key, None),
+                    }),
+                    overlay.into_iter().filter_map(|op| match op {
+                        Struct::Field { key, value } => Some((key, value)),
+                        Struct::Skip { .. } => None,
+                    }),
+                )?;
+                Ok(XAst::Struct {
+                    name,
+                    len,
+                    ops: fields.into_iter().map(struct_op).collect(),
+                })
+            }
+            (
+                XAst::StructVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len,
+                    ops,
+                },
+                XAst::StructVariant {
+                    name: overlay_name,
+                    variant_index: overlay_index,
+                    variant: overlay_variant,
+                    ops: overlay,
+                    ..
+                },
+            ) if (&name, variant_index, &variant)
+                == (&overlay_name, overlay_index, &overlay_variant) =>
+            {
+                let (len, fields) = self.fields(
+                    path,
+                    len,
+                    ops.into_iter().map(|op| match op {
+                        StructVariant::Field { key, value } => (key, Some(value)),
+                        StructVariant::Skip { key } => (key, None),
+                    }),
+                    overlay.into_iter().filter_map(|op| match op {
+                        StructVariant::Field { key, value } => Some((key, value)),
+                        StructVariant::Skip { .. } => None,
+                    }),
+                )?;
+                Ok(XAst::StructVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len,
+                    ops: fields
+                        .into_iter()
+                        .map(|(key, value)| match value {
+                            Some(value) => StructVariant::Field { key, value },
+                            None => StructVariant::Skip { key },
+                        })
+                        .collect(),
+                })
+            }
+            (XAst::Struct { name, len, ops }, XAst::Map { ops: overlay, .. })
+                if struct_entries(&overlay) =>
+            {
+                let (len, fields) = self.fields(
+                    path,
+                    len,
+                    ops.into_iter().map(|op| match op {
+                        Struct::Field { key, value } => (key, Some(value)),
+                        Struct::Skip { key } => (key, None),
+                    }),
+                    entries(overlay).into_iter().map(|(key, value)| {
+                        let key = key_segment(&key).expect("checked by struct_entries");
+                        (Name::Owned(key.into_owned()), value)
+                    }),
+                )?;
+                Ok(XAst::Struct {
+                    name,
+                    len,
+                    ops: fields.into_iter().map(struct_op).collect(),
+                })
+            }
+            (XAst::Map { len, ops }, XAst::Map { ops: overlay, .. })
+                if alternating(&ops) && alternating(&overlay) =>
+            {
+                let entries = self.entries(path, entries(ops), entries(overlay))?;
+                Ok(XAst::Map {
+                    len: len.map(|_| entries.len()),
+                    ops: entries
+                        .into_iter()
+                        .flat_map(|(key, value)| [Map::Key { key }, Map::Value { value }])
+                        .collect(),
+                })
+            }
+            (
+                XAst::Readability {
+                    human_readable,
+                    compact,
+                },
+                XAst::Readability {
+                    human_readable: overlay_human_readable,
+                    compact: overlay_compact,
+                },
+            ) => Ok(XAst::Readability {
+                human_readable: Box::new(self.node(
+                    path,
+                    *human_readable,
+                    *overlay_human_readable,
+                )?),
+                compact: Box::new(self.node(path, *compact, *overlay_compact)?),
+            }),
+            (base, overlay) if base.is_leaf() && overlay.is_leaf() => {
+                if std::mem::discriminant(&base) == std::mem::discriminant(&overlay) {
+                    return Ok(overlay);
+                }
+                match coerce(&base, &overlay) {
+                    Some(coerced) => Ok(coerced),
+                    None => self.conflict(path, base, overlay),
+                }
+            }
+            (base, overlay) => self.conflict(path, base, overlay),
+        }
+    }
+
+    fn conflict<X>(
+        &self,
+        path: &AstPath,
+        base: XAst<X>,
+        overlay: XAst<X>,
+    ) -> Result<XAst<X>, Error> {
+        match self.strategy.conflict {
+            ConflictStrategy::OverlayWins => Ok(overlay),
+            ConflictStrategy::Error => Err(Error {
+                path: path.clone(),
+                base: base.variant_name(),
+                overlay: overlay.variant_name(),
+            }),
+        }
+    }
+
+    /// Merge elements at the same index, keeping the extra elements of the longer sequence.
+    fn elements<X: Ord>(
+        &self,
+        path: &mut AstPath,
+        base: impl Iterator<Item = Box<XAst<X>>>,
+        overlay: impl Iterator<Item = Box<XAst<X>>>,
+    ) -> Result<Vec<Box<XAst<X>>>, Error> {
+        let mut base = base.fuse();
+        let mut overlay = overlay.fuse();
+        let mut values = Vec::new();
+        loop {
+            let value = match (base.next(), overlay.next()) {
+                (Some(base), Some(overlay)) => {
+                    path.push(values.len().to_string());
+                    let value = self.node(path, *base, *overlay);
+                    path.pop();
+                    Box::new(value?)
+                }
+                (Some(value), None) | (None, Some(value)) => value,
+                (None, None) => return Ok(values),
+            };
+            values.push(value);
+        }
+    }
+
+    /// Merge fields by key, [None] values are skipped fields.
+    #[allow(clippy::type_complexity)]
+    fn fields<X: Ord>(
+        &self,
+        path: &mut AstPath,
+        mut len: usize,
+        base: impl Iterator<Item = (Name, Option<Box<XAst<X>>>)>,
+        overlay: impl Iterator<Item = (Name, Box<XAst<X>>)>,
+    ) -> Result<(usize, Vec<(Name, Option<Box<XAst<X>>>)>), Error> {
+        let mut fields: Vec<_> = base.collect();
+        for (key, overlay) in overlay {
+            match fields.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, value @ Some(_))) => {
+                    let base = value.take().expect("matched Some");
+                    path.push(key.as_ref());
+                    let merged = self.node(path, *base, *overlay);
+                    path.pop();
+                    *value = Some(Box::new(merged?));
+                }
+                Some((_, value @ None)) => {
+                    *value = Some(overlay);
+                    len += 1;
+                }
+                None => {
+                    fields.push((key, Some(overlay)));
+                    len += 1;
+                }
+            }
+        }
+        Ok((len, fields))
+    }
+
+    /// Merge map entries by key.
+    #[allow(clippy::type_complexity)]
+    fn entries<X: Ord>(
+        &self,
+        path: &mut AstPath,
+        base: Vec<(Box<XAst<X>>, Box<XAst<X>>)>,
+        overlay: Vec<(Box<XAst<X>>, Box<XAst<X>>)>,
+    ) -> Result<Vec<(Box<XAst<X>>, Box<XAst<X>>)>, Error> {
+        let mut entries = Entries::default();
+        for (key, value) in base {
+            entries.push(*key, value);
+        }
+        for (key, overlay) in overlay {
+            let key = Total(*key);
+            match entries.find(&key) {
+                Some(index) => {
+                    path.push(
+                        key_segment(&key.0)
+                            .map(|segment| segment.into_owned())
+                            .unwrap_or_else(|| index.to_string()),
+                    );
+                    let base = std::mem::replace(&mut *entries.values[index], XAst::Unit);
+                    let merged = self.node(path, base, *overlay);
+                    path.pop();
+                    *entries.values[index] = merged?;
+                }
+                None => entries.push(key.0, overlay),
+            }
+        }
+        Ok(entries.into_vec())
+    }
+}
+
+/// Map entries in order, indexed by their key [Total] and their [key_segment].
+struct Entries<X> {
+    values: Vec<Box<XAst<X>>>,
+    keys: BTreeMap<Total<X>, usize>,
+    /// keys equal to an earlier key, with their index
+    duplicates: Vec<(usize, XAst<X>)>,
+    segments: HashMap<String, usize>,
+}
+impl<X> Default for Entries<X> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            keys: BTreeMap::new(),
+            duplicates: Vec::new(),
+            segments: HashMap::new(),
+        }
+    }
+}
+impl<X: Ord> Entries<X> {
+    fn push(&mut self, key: XAst<X>, value: Box<XAst<X>>) {
+        let index = self.values.len();
+        self.values.push(value);
+        if let Some(segment) = key_segment(&key) {
+            self.segments.entry(segment.into_owned()).or_insert(index);
+        }
+        let key = Total(key);
+        match self.keys.contains_key(&key) {
+            true => self.duplicates.push((index, key.0)),
+            false => {
+                self.keys.insert(key, index);
+            }
+        }
+    }
+
+    /// Index of the first entry with the same key, string-like keys also match by [key_segment].
+    fn find(&self, key: &Total<X>) -> Option<usize> {
+        self.keys.get(key).copied().or_else(|| {
+            key_segment(&key.0).and_then(|segment| self.segments.get(segment.as_ref()).copied())
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn into_vec(self) -> Vec<(Box<XAst<X>>, Box<XAst<X>>)> {
+        let mut keys: Vec<_> = self.values.iter().map(|_| None).collect();
+        for (Total(key), index) in self.keys {
+            keys[index] = Some(key);
+        }
+        for (index, key) in self.duplicates {
+            keys[index] = Some(key);
+        }
+        keys.into_iter()
+            .zip(self.values)
+            .map(|(key, value)| (Box::new(key.expect("every entry has a key")), value))
+            .collect()
+    }
+}
+
+fn struct_op<X>((key, value): (Name, Option<Box<XAst<X>>>)) -> Struct<X> {
+    match value {
+        Some(value) => Struct::Field { key, value },
+        None => Struct::Skip { key },
+    }
+}
+
+/// Check if the [XAst::Map] operations alternate between [Map::Key] and [Map::Value].
+fn alternating<X>(ops: &[Map<X>]) -> bool {
+    ops.len().is_multiple_of(2)
+        && ops
+            .chunks(2)
+            .all(|pair| matches!(pair, [Map::Key { .. }, Map::Value { .. }]))
+}
+
+/// Pair up alternating [Map::Key] and [Map::Value] operations.
+#[allow(clippy::type_complexity)]
+fn entries<X>(ops: Vec<Map<X>>) -> Vec<(Box<XAst<X>>, Box<XAst<X>>)> {
+    let mut ops = ops.into_iter();
+    let mut entries = Vec::new();
+    while let (Some(Map::Key { key }), Some(Map::Value { value })) = (ops.next(), ops.next()) {
+        entries.push((key, value));
+    }
+    entries
+}
+
+/// Check if the [XAst::Map] operations can be merged into a [XAst::Struct].
+fn struct_entries<X>(ops: &[Map<X>]) -> bool {
+    alternating(ops)
+        && ops.chunks(2).all(|pair| match pair {
+            [Map::Key { key }, _] => key_segment(key).is_some(),
+            _ => false,
+        })
+}
+
+/// A number leaf, widened to compare and convert across types.
+enum Number {
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+}
+impl Number {
+    fn of<X>(node: &XAst<X>) -> Option<Self> {
+        Some(match *node {
+            XAst::I8(v) => Self::Int(v.into()),
+            XAst::I16(v) => Self::Int(v.into()),
+            XAst::I32(v) => Self::Int(v.into()),
+            XAst::I64(v) => Self::Int(v.into()),
+            XAst::I128(v) => Self::Int(v),
+            XAst::U8(v) => Self::UInt(v.into()),
+            XAst::U16(v) => Self::UInt(v.into()),
+            XAst::U32(v) => Self::UInt(v.into()),
+            XAst::U64(v) => Self::UInt(v.into()),
+            XAst::U128(v) => Self::UInt(v),
+            XAst::F32(v) => Self::Float(v.into()),
+            XAst::F64(v) => Self::Float(v),
+            _ => return None,
+        })
+    }
+
+    /// Convert to an integer of type `T`, if it fits.
+    fn int<T>(&self) -> Option<T>
+    where
+        T: TryFrom<i128> + TryFrom<u128>,
+    {
+        match *self {
+            Self::Int(v) => v.try_into().ok(),
+            Self::UInt(v) => v.try_into().ok(),
+            Self::Float(_) => None,
+        }
+    }
+
+    fn float(&self) -> f64 {
+        match *self {
+            Self::Int(v) => v as f64,
+            Self::UInt(v) => v as f64,
+            Self::Float(v) => v,
+        }
+    }
+}
+
+/// Convert the number `overlay` to the type of the number `base`, if the value fits.
+fn coerce<X>(base: &XAst<X>, overlay: &XAst<X>) -> Option<XAst<X>> {
+    let overlay = Number::of(overlay)?;
+    Some(match base {
+        XAst::I8(_) => XAst::I8(overlay.int()?),
+        XAst::I16(_) => XAst::I16(overlay.int()?),
+        XAst::I32(_) => XAst::I32(overlay.int()?),
+        XAst::I64(_) => XAst::I64(overlay.int()?),
+        XAst::I128(_) => XAst::I128(overlay.int()?),
+        XAst::U8(_) => XAst::U8(overlay.int()?),
+        XAst::U16(_) => XAst::U16(overlay.int()?),
+        XAst::U32(_) => XAst::U32(overlay.int()?),
+        XAst::U64(_) => XAst::U64(overlay.int()?),
+        XAst::U128(_) => XAst::U128(overlay.int()?),
+        XAst::F32(_) => XAst::F32(overlay.float() as f32),
+        XAst::F64(_) => XAst::F64(overlay.float()),
+        _ => return None,
+    })
+}
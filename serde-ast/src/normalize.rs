@@ -0,0 +1,164 @@
+//! Normalize incidental differences between [XAst] captures of the same data.
+//!
+//! Each [Pass] rewrites an [XAst] in place, passes compose as tuples and run in order:
+//! - [CollapseNewtypes] replaces [XAst::NewtypeStruct] wrappers with their value
+//! - [DropSkips] removes skipped fields of [XAst::Struct] and [XAst::StructVariant]
+//! - [FillLengths] sets the unknown `len` of [XAst::Seq] and [XAst::Map]
+//! - [SortMapEntries] sorts the entries of [XAst::Map] by key, e.g. captured from a `HashMap`
+//!
+//! [canonical] runs all of them, so that normalized [XAst]s can be compared directly.
+//!
+//! ```
+//! # use std::collections::HashMap;
+//! # use serde_ast::{normalize::{self, Pass}, to_ast};
+//! let a = HashMap::from([("a", 1), ("b", 2), ("c", 3)]);
+//! let b = a.clone().into_iter().collect::<HashMap<_, _>>();
+//!
+//! let mut a = to_ast(&a).unwrap();
+//! let mut b = to_ast(&b).unwrap();
+//! normalize::canonical().apply(&mut a);
+//! normalize::canonical().apply(&mut b);
+//! assert_eq!(a, b);
+//! ```
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, XAst},
+    visit_mut::{self, VisitMut},
+};
+
+/// A normalization pass, see the [module documentation](self).
+///
+/// Implemented by tuples of passes, running them in order.
+pub trait Pass<X> {
+    /// Normalize `ast` in place.
+    fn apply(&mut self, ast: &mut XAst<X>);
+}
+
+macro_rules! tuple_pass {
+    ($($pass:ident),+) => {
+        impl<X, $($pass),+> Pass<X> for ($($pass,)+)
+        where
+            $($pass: Pass<X>),+
+        {
+            #[allow(non_snake_case)]
+            fn apply(&mut self, ast: &mut XAst<X>) {
+                let ($($pass,)+) = self;
+                $($pass.apply(ast);)+
+            }
+        }
+    };
+}
+tuple_pass!(A);
+tuple_pass!(A, B);
+tuple_pass!(A, B, C);
+tuple_pass!(A, B, C, D);
+tuple_pass!(A, B, C, D, E);
+tuple_pass!(A, B, C, D, E, F);
+
+/// Run every pass: [CollapseNewtypes], [DropSkips], [FillLengths] and [SortMapEntries].
+pub fn canonical<X>() -> impl Pass<X>
+where
+    X: Ord,
+{
+    (CollapseNewtypes, DropSkips, FillLengths, SortMapEntries)
+}
+
+/// Replace every [XAst::NewtypeStruct] with its value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CollapseNewtypes;
+impl<X> VisitMut<X> for CollapseNewtypes {
+    fn visit_ast_mut(&mut self, node: &mut XAst<X>) {
+        visit_mut::visit_ast_mut(self, node);
+        if let XAst::NewtypeStruct { value, .. } = node {
+            *node = std::mem::replace(&mut **value, XAst::Unit);
+        }
+    }
+}
+
+/// Remove the [Struct::Skip] and [StructVariant::Skip] operations.
+///
+/// The `len` counts only the serialized fields, so it is unchanged.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DropSkips;
+impl<X> VisitMut<X> for DropSkips {
+    fn visit_struct_mut(&mut self, name: &mut Name, len: &mut usize, ops: &mut Vec<Struct<X>>) {
+        ops.retain(|op| matches!(op, Struct::Field { .. }));
+        visit_mut::visit_struct_mut(self, name, len, ops)
+    }
+
+    fn visit_struct_variant_mut(
+        &mut self,
+        name: &mut Name,
+        variant_index: &mut u32,
+        variant: &mut Name,
+        len: &mut usize,
+        ops: &mut Vec<StructVariant<X>>,
+    ) {
+        ops.retain(|op| matches!(op, StructVariant::Field { .. }));
+        visit_mut::visit_struct_variant_mut(self, name, variant_index, variant, len, ops)
+    }
+}
+
+/// Set the `len` of [XAst::Seq] and [XAst::Map] to the number of elements or entries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FillLengths;
+impl<X> VisitMut<X> for FillLengths {
+    fn visit_seq_mut(&mut self, len: &mut Option<usize>, ops: &mut Vec<Seq<X>>) {
+        *len = Some(ops.len());
+        visit_mut::visit_seq_mut(self, len, ops)
+    }
+
+    fn visit_map_mut(&mut self, len: &mut Option<usize>, ops: &mut Vec<Map<X>>) {
+        *len = Some(
+            ops.iter()
+                .filter(|op| matches!(op, Map::Value { .. }))
+                .count(),
+        );
+        visit_mut::visit_map_mut(self, len, ops)
+    }
+}
+
+/// Sort the entries of [XAst::Map] by key, after normalizing the keys and values.
+///
+/// Keys are ordered by [XAst::total_cmp]. Maps whose operations don't alternate
+/// [Map::Key] and [Map::Value] are left unchanged, see [validate](crate::validate).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SortMapEntries;
+impl<X> VisitMut<X> for SortMapEntries
+where
+    X: Ord,
+{
+    fn visit_map_mut(&mut self, len: &mut Option<usize>, ops: &mut Vec<Map<X>>) {
+        visit_mut::visit_map_mut(self, len, ops);
+        let paired = ops
+            .chunks(2)
+            .all(|pair| matches!(pair, [Map::Key { .. }, Map::Value { .. }]));
+        if !paired {
+            return;
+        }
+        let mut taken = std::mem::take(ops).into_iter();
+        let mut entries = Vec::new();
+        while let (Some(key), Some(value)) = (taken.next(), taken.next()) {
+            entries.push([key, value]);
+        }
+        entries.sort_by(|[a, _], [b, _]| match (a, b) {
+            (Map::Key { key: a }, Map::Key { key: b }) => a.total_cmp(b),
+            _ => unreachable!("entries are checked to be paired"),
+        });
+        *ops = entries.into_iter().flatten().collect();
+    }
+}
+
+macro_rules! visit_pass {
+    ($($pass:ident$(: $bound:path)?),*) => {$(
+        impl<X> Pass<X> for $pass
+        where
+            $(X: $bound)?
+        {
+            fn apply(&mut self, ast: &mut XAst<X>) {
+                self.visit_ast_mut(ast)
+            }
+        }
+    )*};
+}
+visit_pass!(CollapseNewtypes, DropSkips, FillLengths, SortMapEntries: Ord);
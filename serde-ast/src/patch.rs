@@ -0,0 +1,387 @@
+//! Apply a [Patch] of operations to an [Ast].
+//!
+//! Operations follow the [JSON Patch](https://www.rfc-editor.org/rfc/rfc6902) semantics,
+//! addressed by an [AstPath] (see [crate::path] for how paths resolve):
+//! - [Operation::Add] inserts a sequence element, or sets a field or map entry
+//! - [Operation::Remove] removes a node
+//! - [Operation::Replace] replaces an existing node
+//! - [Operation::Move] removes a node and adds it at another path
+//! - [Operation::Copy] adds a copy of a node at another path
+//! - [Operation::Test] checks a node is equal to a value
+//!
+//! A [Patch] implements [Serialize] and [Deserialize] in the JSON Patch format,
+//! operation values are loaded as an [Ast] with its [Deserialize] implementation.
+//! [Operation::Test] compares nodes as the JSON values they serialize to, e.g. a number loaded
+//! from JSON as a [XAst::U64] equals the [XAst::U16] a `u16` serializes to:
+//! - numbers are equal by value, regardless of their type
+//! - [XAst::Struct] and [XAst::Map] are objects, equal if they have the same keys and values,
+//!   map keys which are not string-like are compared with [XAst::total_cmp]
+//! - [XAst::Some], [XAst::NewtypeStruct] and the human-readable branch of [XAst::Readability]
+//!   are transparent, [XAst::None] and units are `null`
+//! - enum variants are externally tagged, e.g. a unit variant is equal to its name
+//!
+//! [XAst::U64]: crate::ast::XAst::U64
+//! [XAst::U16]: crate::ast::XAst::U16
+//! [XAst::Struct]: crate::ast::XAst::Struct
+//! [XAst::Map]: crate::ast::XAst::Map
+//! [XAst::total_cmp]: crate::ast::XAst::total_cmp
+//! [XAst::Some]: crate::ast::XAst::Some
+//! [XAst::NewtypeStruct]: crate::ast::XAst::NewtypeStruct
+//! [XAst::Readability]: crate::ast::XAst::Readability
+//! [XAst::None]: crate::ast::XAst::None
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{patch::Patch, to_ast};
+//! #[derive(Serialize)]
+//! struct Server {
+//!     host: String,
+//!     tags: Vec<String>,
+//! }
+//! let server = Server { host: "localhost".to_string(), tags: vec!["a".to_string()] };
+//! let mut ast = to_ast(&server).expect("serialize to_ast");
+//!
+//! let patch: Patch = serde_json::from_str(r#"[
+//!     { "op": "test", "path": "/host", "value": "localhost" },
+//!     { "op": "replace", "path": "/host", "value": "example.com" },
+//!     { "op": "add", "path": "/tags/-", "value": "b" }
+//! ]"#).expect("load patch");
+//! patch.apply(&mut ast).expect("apply patch");
+//!
+//! assert_eq!(
+//!     serde_json::to_string(&ast).unwrap(),
+//!     r#"{"host":"example.com","tags":["a","b"]}"#,
+//! );
+//! ```
+
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ast::{Map, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    path::{self, key_segment, AstPath},
+    Ast,
+};
+
+/// A single JSON Patch operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    /// Insert a sequence element, or set a field or map entry, replacing the root at the root path.
+    Add {
+        /// target path
+        path: AstPath,
+        /// node to add
+        value: Ast,
+    },
+    /// Remove the node at `path`.
+    Remove {
+        /// target path
+        path: AstPath,
+    },
+    /// Replace the existing node at `path`.
+    Replace {
+        /// target path
+        path: AstPath,
+        /// replacement node
+        value: Ast,
+    },
+    /// Remove the node at `from` and add it at `path`.
+    Move {
+        /// source path
+        from: AstPath,
+        /// target path
+        path: AstPath,
+    },
+    /// Add a copy of the node at `from` at `path`.
+    Copy {
+        /// source path
+        from: AstPath,
+        /// target path
+        path: AstPath,
+    },
+    /// Check the node at `path` is equal to `value`, compared as JSON values.
+    Test {
+        /// target path
+        path: AstPath,
+        /// expected node
+        value: Ast,
+    },
+}
+impl Operation {
+    /// Apply this [Operation] to `ast`.
+    ///
+    /// A failed operation may leave `ast` partially modified, see [Patch::apply].
+    pub fn apply(&self, ast: &mut Ast) -> Result<(), ErrorKind> {
+        match self {
+            Self::Add { path, value } => {
+                ast.insert(path, value.clone())?;
+            }
+            Self::Remove { path } => {
+                ast.remove(path)?;
+            }
+            Self::Replace { path, value } => {
+                *ast.get_mut(path)? = value.clone();
+            }
+            Self::Move { from, path } => {
+                if from != path {
+                    if path.starts_with(from) {
+                        return Err(ErrorKind::MoveIntoChild {
+                            from: from.clone(),
+                            path: path.clone(),
+                        });
+                    }
+                    let value = ast.remove(from)?;
+                    ast.insert(path, value)?;
+                } else {
+                    ast.get(from)?;
+                }
+            }
+            Self::Copy { from, path } => {
+                let value = ast.get(from)?.clone();
+                ast.insert(path, value)?;
+            }
+            Self::Test { path, value } => {
+                if Json::from(ast.get(path)?) != Json::from(value) {
+                    return Err(ErrorKind::TestFailed { path: path.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A list of [Operation], see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch {
+    operations: Vec<Operation>,
+}
+impl Patch {
+    /// Create a [Patch] from a list of [Operation].
+    pub fn new(operations: Vec<Operation>) -> Self {
+        Self { operations }
+    }
+
+    /// List of [Operation], in application order.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Apply all [Operation] in order to `ast`.
+    ///
+    /// The application is atomic, if any [Operation] fails `ast` is left unmodified.
+    pub fn apply(&self, ast: &mut Ast) -> Result<(), Error> {
+        let mut patched = ast.clone();
+        for (index, operation) in self.operations.iter().enumerate() {
+            operation
+                .apply(&mut patched)
+                .map_err(|kind| Error { index, kind })?;
+        }
+        *ast = patched;
+        Ok(())
+    }
+}
+impl From<Vec<Operation>> for Patch {
+    fn from(operations: Vec<Operation>) -> Self {
+        Self::new(operations)
+    }
+}
+impl FromIterator<Operation> for Patch {
+    fn from_iter<T: IntoIterator<Item = Operation>>(iter: T) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+impl IntoIterator for Patch {
+    type Item = Operation;
+    type IntoIter = std::vec::IntoIter<Operation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.operations.into_iter()
+    }
+}
+
+/// Error applying a [Patch].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("operation {index} failed: {kind}")]
+pub struct Error {
+    /// Index of the failed [Operation] in the [Patch].
+    pub index: usize,
+    /// Reason the [Operation] failed.
+    pub kind: ErrorKind,
+}
+
+/// Reason applying an [Operation] failed.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ErrorKind {
+    #[error(transparent)]
+    Path(#[from] path::Error),
+    #[error("test failed at `{path}`")]
+    TestFailed { path: AstPath },
+    #[error("cannot move `{from}` into its child `{path}`")]
+    MoveIntoChild { from: AstPath, path: AstPath },
+}
+
+/// An [Ast] viewed as the JSON value it serializes to, for [Operation::Test].
+#[derive(Debug, PartialEq)]
+enum Json<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<Json<'a>>),
+    Object(BTreeMap<JsonKey<'a>, Json<'a>>),
+}
+impl<'a> Json<'a> {
+    fn array(values: impl IntoIterator<Item = &'a Ast>) -> Self {
+        Self::Array(values.into_iter().map(Self::from).collect())
+    }
+
+    fn object(entries: impl IntoIterator<Item = (JsonKey<'a>, &'a Ast)>) -> Self {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, Self::from(value)))
+                .collect(),
+        )
+    }
+
+    fn tagged(variant: &'a str, value: Self) -> Self {
+        Self::Object(BTreeMap::from([(
+            JsonKey::String(Cow::Borrowed(variant)),
+            value,
+        )]))
+    }
+}
+impl<'a> From<&'a Ast> for Json<'a> {
+    fn from(ast: &'a Ast) -> Self {
+        match ast {
+            XAst::Bool(v) => Self::Bool(*v),
+            XAst::I8(v) => Self::Number(Number::Int((*v).into())),
+            XAst::I16(v) => Self::Number(Number::Int((*v).into())),
+            XAst::I32(v) => Self::Number(Number::Int((*v).into())),
+            XAst::I64(v) => Self::Number(Number::Int((*v).into())),
+            XAst::I128(v) => Self::Number(Number::Int(*v)),
+            XAst::U8(v) => Self::Number(Number::Int((*v).into())),
+            XAst::U16(v) => Self::Number(Number::Int((*v).into())),
+            XAst::U32(v) => Self::Number(Number::Int((*v).into())),
+            XAst::U64(v) => Self::Number(Number::Int((*v).into())),
+            XAst::U128(v) => Self::Number(match i128::try_from(*v) {
+                Ok(v) => Number::Int(v),
+                Err(_) => Number::Big(*v),
+            }),
+            XAst::F32(v) => Self::Number(Number::Float((*v).into())),
+            XAst::F64(v) => Self::Number(Number::Float(*v)),
+            XAst::Char(v) => Self::String(Cow::Owned(v.to_string())),
+            XAst::Str(v) => Self::String(Cow::Borrowed(v)),
+            XAst::Bytes(v) => Self::Array(
+                v.iter()
+                    .map(|v| Self::Number(Number::Int((*v).into())))
+                    .collect(),
+            ),
+            XAst::None | XAst::Unit | XAst::UnitStruct(_) => Self::Null,
+            XAst::Some(value) | XAst::NewtypeStruct { value, .. } => Self::from(value.as_ref()),
+            XAst::Readability { human_readable, .. } => Self::from(human_readable.as_ref()),
+            XAst::UnitVariant { variant, .. } => Self::String(Cow::Borrowed(variant)),
+            XAst::NewtypeVariant { variant, value, .. } => {
+                Self::tagged(variant, Self::from(value.as_ref()))
+            }
+            XAst::Seq { ops, .. } => Self::array(ops.iter().map(|op| match op {
+                Seq::Element { value } => value.as_ref(),
+            })),
+            XAst::Tuple { ops, .. } => Self::array(ops.iter().map(|op| match op {
+                Tuple::Element { value } => value.as_ref(),
+            })),
+            XAst::TupleStruct { ops, .. } => Self::array(ops.iter().map(|op| match op {
+                TupleStruct::Field { value } => value.as_ref(),
+            })),
+            XAst::TupleVariant { variant, ops, .. } => Self::tagged(
+                variant,
+                Self::array(ops.iter().map(|op| match op {
+                    TupleVariant::Field { value } => value.as_ref(),
+                })),
+            ),
+            XAst::Map { ops, .. } => Self::object(ops.windows(2).filter_map(|pair| match pair {
+                [Map::Key { key }, Map::Value { value }] => {
+                    let key = match key_segment(key) {
+                        Some(key) => JsonKey::String(key),
+                        None => JsonKey::Other(key),
+                    };
+                    Some((key, value.as_ref()))
+                }
+                _ => None,
+            })),
+            XAst::Struct { ops, .. } => Self::object(ops.iter().filter_map(|op| match op {
+                Struct::Field { key, value } => {
+                    Some((JsonKey::String(Cow::Borrowed(key.as_ref())), value.as_ref()))
+                }
+                Struct::Skip { .. } => None,
+            })),
+            XAst::StructVariant { variant, ops, .. } => Self::tagged(
+                variant,
+                Self::object(ops.iter().filter_map(|op| match op {
+                    StructVariant::Field { key, value } => {
+                        Some((JsonKey::String(Cow::Borrowed(key.as_ref())), value.as_ref()))
+                    }
+                    StructVariant::Skip { .. } => None,
+                })),
+            ),
+            XAst::X(x) => match *x {},
+        }
+    }
+}
+
+/// Key of a [Json::Object], map keys which are not string-like are compared with [XAst::total_cmp].
+#[derive(Debug)]
+enum JsonKey<'a> {
+    String(Cow<'a, str>),
+    Other(&'a Ast),
+}
+impl PartialEq for JsonKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl Eq for JsonKey<'_> {}
+impl PartialOrd for JsonKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for JsonKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Other(a), Self::Other(b)) => a.total_cmp(b),
+            (Self::String(_), Self::Other(_)) => Ordering::Less,
+            (Self::Other(_), Self::String(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// A JSON number, equal to another by value.
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i128),
+    /// [u128] above [i128::MAX]
+    Big(u128),
+    Float(f64),
+}
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Big(a), Self::Big(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (Self::Float(f), Self::Int(i)) | (Self::Int(i), Self::Float(f)) => {
+                f.fract() == 0.0 && f == i as f64 && f as i128 == i
+            }
+            (Self::Float(f), Self::Big(u)) | (Self::Big(u), Self::Float(f)) => {
+                f.fract() == 0.0 && f == u as f64 && f as u128 == u
+            }
+            (Self::Int(_), Self::Big(_)) | (Self::Big(_), Self::Int(_)) => false,
+        }
+    }
+}
@@ -0,0 +1,680 @@
+//! Address nodes inside an [XAst] with an [AstPath].
+//!
+//! An [AstPath] is written like a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+//! e.g. `/servers/0/port`. Each segment resolves through:
+//! - [XAst::Struct] and [XAst::StructVariant] fields by key, skipped fields are absent
+//! - [XAst::Map] entries by key, if the key is a string-like leaf (see [key_segment])
+//! - [XAst::Seq], [XAst::Tuple], [XAst::TupleStruct] and [XAst::TupleVariant] elements by index
+//!
+//! [XAst::Some], [XAst::NewtypeStruct] and [XAst::NewtypeVariant] are transparent and don't
+//! consume a segment, [XAst::Readability] resolves through its human-readable value.
+//! Editing through a [XAst::Readability] would leave its compact value out of sync, so
+//! [XAst::get_mut], [XAst::insert] and [XAst::remove] fail with [ErrorKind::Readability] instead,
+//! the node itself can still be replaced as a whole.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{ast::XAst, path::AstPath, to_ast};
+//! #[derive(Serialize)]
+//! struct Config {
+//!     servers: Vec<Server>,
+//! }
+//! #[derive(Serialize)]
+//! struct Server {
+//!     port: u16,
+//! }
+//! let config = Config { servers: vec![Server { port: 80 }] };
+//! let mut ast = to_ast(&config).expect("serialize to_ast");
+//!
+//! let path: AstPath = "/servers/0/port".parse().expect("valid path");
+//! assert_eq!(ast.get(&path).expect("resolve path"), &XAst::U16(80));
+//!
+//! ast.insert(&path, XAst::U16(443)).expect("insert at path");
+//! assert_eq!(serde_json::to_string(&ast).unwrap(), r#"{"servers":[{"port":443}]}"#);
+//! ```
+
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+use serde::{de::Visitor, Deserialize, Serialize};
+
+use crate::ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst};
+
+/// Path to a node inside an [XAst].
+///
+/// See the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AstPath {
+    segments: Vec<String>,
+}
+impl AstPath {
+    /// Create an empty [AstPath] pointing to the root node.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parse an [AstPath] from its JSON Pointer representation.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        if s.is_empty() {
+            return Ok(Self::root());
+        }
+        let Some(s) = s.strip_prefix('/') else {
+            return Err(ParseError::MissingSlash);
+        };
+        s.split('/').map(unescape).collect()
+    }
+
+    /// Segments of this [AstPath], unescaped.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// Check if this [AstPath] points to the root node.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Append a `segment` to this [AstPath].
+    pub fn push(&mut self, segment: impl Into<String>) {
+        self.segments.push(segment.into());
+    }
+
+    /// Remove the last segment of this [AstPath].
+    pub fn pop(&mut self) -> Option<String> {
+        self.segments.pop()
+    }
+
+    /// Create a new [AstPath] with `segment` appended.
+    pub fn join(&self, segment: impl Into<String>) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    /// Split this [AstPath] into its parent and its last segment.
+    pub fn split_last(&self) -> Option<(AstPath, &str)> {
+        let (last, parent) = self.segments.split_last()?;
+        Some((
+            AstPath {
+                segments: parent.to_vec(),
+            },
+            last,
+        ))
+    }
+
+    /// Check if `prefix` is a prefix of this [AstPath], or equal to it.
+    pub fn starts_with(&self, prefix: &AstPath) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+
+    /// Prefix of this [AstPath] with the first `len` segments.
+    fn prefix(&self, len: usize) -> Self {
+        Self {
+            segments: self.segments[..len].to_vec(),
+        }
+    }
+}
+impl Display for AstPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{}", segment.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for AstPath {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl Serialize for AstPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for AstPath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct AstPathVisitor;
+        impl Visitor<'_> for AstPathVisitor {
+            type Value = AstPath;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a JSON Pointer")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                AstPath::parse(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(AstPathVisitor)
+    }
+}
+impl<S> FromIterator<S> for AstPath
+where
+    S: Into<String>,
+{
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        Self {
+            segments: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+fn unescape(segment: &str) -> Result<String, ParseError> {
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return Err(ParseError::InvalidEscape(segment.to_owned())),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Error parsing an [AstPath].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ParseError {
+    #[error("path must be empty or start with `/`")]
+    MissingSlash,
+    #[error("invalid escape in segment `{0}`, expected `~0` or `~1`")]
+    InvalidEscape(String),
+}
+
+/// Error resolving an [AstPath] inside an [XAst].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at `{path}`")]
+pub struct Error {
+    /// Prefix of the requested path up to the segment which failed to resolve.
+    pub path: AstPath,
+    /// Reason the resolution failed.
+    pub kind: ErrorKind,
+}
+
+/// Reason resolving an [AstPath] failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ErrorKind {
+    #[error("no such key")]
+    MissingKey,
+    #[error("index out of bounds")]
+    OutOfBounds,
+    #[error("invalid index")]
+    InvalidIndex,
+    #[error("invalid key for the map")]
+    InvalidKey,
+    #[error("node has no children")]
+    NotAContainer,
+    #[error("the root node cannot be removed")]
+    RemoveRoot,
+    #[error("cannot edit through a readability node")]
+    Readability,
+}
+
+/// Represent a [XAst::Map] key as a path segment.
+///
+/// Only string-like leaves are addressable: strings, chars, bools, integers and unit variants.
+pub fn key_segment<X>(key: &XAst<X>) -> Option<Cow<'_, str>> {
+    match key {
+        XAst::Bool(v) => Some(Cow::Owned(v.to_string())),
+        XAst::I8(v) => Some(Cow::Owned(v.to_string())),
+        XAst::I16(v) => Some(Cow::Owned(v.to_string())),
+        XAst::I32(v) => Some(Cow::Owned(v.to_string())),
+        XAst::I64(v) => Some(Cow::Owned(v.to_string())),
+        XAst::I128(v) => Some(Cow::Owned(v.to_string())),
+        XAst::U8(v) => Some(Cow::Owned(v.to_string())),
+        XAst::U16(v) => Some(Cow::Owned(v.to_string())),
+        XAst::U32(v) => Some(Cow::Owned(v.to_string())),
+        XAst::U64(v) => Some(Cow::Owned(v.to_string())),
+        XAst::U128(v) => Some(Cow::Owned(v.to_string())),
+        XAst::Char(v) => Some(Cow::Owned(v.to_string())),
+        XAst::Str(v) => Some(Cow::Borrowed(v)),
+        XAst::UnitVariant { variant, .. } => Some(Cow::Borrowed(variant)),
+        _ => None,
+    }
+}
+
+/// Parse a sequence index, without leading zeros.
+fn parse_index(segment: &str) -> Result<usize, ErrorKind> {
+    match segment.as_bytes() {
+        [b'0'] => Ok(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => {
+            segment.parse().map_err(|_| ErrorKind::InvalidIndex)
+        }
+        _ => Err(ErrorKind::InvalidIndex),
+    }
+}
+
+impl<X> XAst<X> {
+    /// Skip transparent wrappers: [XAst::Some], [XAst::NewtypeStruct] and [XAst::NewtypeVariant],
+    /// and the human-readable value of [XAst::Readability].
+    fn unwrap_transparent(&self) -> &Self {
+        match self {
+            Self::Some(value)
+            | Self::NewtypeStruct { value, .. }
+            | Self::NewtypeVariant { value, .. }
+            | Self::Readability {
+                human_readable: value,
+                ..
+            } => value.unwrap_transparent(),
+            node => node,
+        }
+    }
+    /// Skip transparent wrappers to edit the node, failing on [XAst::Readability].
+    fn unwrap_transparent_mut(&mut self) -> Result<&mut Self, ErrorKind> {
+        match self {
+            Self::Some(value)
+            | Self::NewtypeStruct { value, .. }
+            | Self::NewtypeVariant { value, .. } => value.unwrap_transparent_mut(),
+            Self::Readability { .. } => Err(ErrorKind::Readability),
+            node => Ok(node),
+        }
+    }
+
+    fn child(&self, segment: &str) -> Result<&Self, ErrorKind> {
+        let index = || parse_index(segment);
+        match self.unwrap_transparent() {
+            Self::Seq { ops, .. } => match ops.get(index()?) {
+                Some(Seq::Element { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::Tuple { ops, .. } => match ops.get(index()?) {
+                Some(Tuple::Element { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::TupleStruct { ops, .. } => match ops.get(index()?) {
+                Some(TupleStruct::Field { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::TupleVariant { ops, .. } => match ops.get(index()?) {
+                Some(TupleVariant::Field { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::Struct { ops, .. } => ops
+                .iter()
+                .find_map(|op| match op {
+                    Struct::Field { key, value } if *key == segment => Some(value.as_ref()),
+                    _ => None,
+                })
+                .ok_or(ErrorKind::MissingKey),
+            Self::StructVariant { ops, .. } => ops
+                .iter()
+                .find_map(|op| match op {
+                    StructVariant::Field { key, value } if *key == segment => Some(value.as_ref()),
+                    _ => None,
+                })
+                .ok_or(ErrorKind::MissingKey),
+            Self::Map { ops, .. } => {
+                let index = map_value_index(ops, segment).ok_or(ErrorKind::MissingKey)?;
+                match &ops[index] {
+                    Map::Value { value } => Ok(value),
+                    Map::Key { .. } => unreachable!("map_value_index returns a value"),
+                }
+            }
+            _ => Err(ErrorKind::NotAContainer),
+        }
+    }
+
+    fn child_mut(&mut self, segment: &str) -> Result<&mut Self, ErrorKind> {
+        let index = || parse_index(segment);
+        match self.unwrap_transparent_mut()? {
+            Self::Seq { ops, .. } => match ops.get_mut(index()?) {
+                Some(Seq::Element { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::Tuple { ops, .. } => match ops.get_mut(index()?) {
+                Some(Tuple::Element { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::TupleStruct { ops, .. } => match ops.get_mut(index()?) {
+                Some(TupleStruct::Field { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::TupleVariant { ops, .. } => match ops.get_mut(index()?) {
+                Some(TupleVariant::Field { value }) => Ok(value),
+                None => Err(ErrorKind::OutOfBounds),
+            },
+            Self::Struct { ops, .. } => ops
+                .iter_mut()
+                .find_map(|op| match op {
+                    Struct::Field { key, value } if *key == segment => Some(value.as_mut()),
+                    _ => None,
+                })
+                .ok_or(ErrorKind::MissingKey),
+            Self::StructVariant { ops, .. } => ops
+                .iter_mut()
+                .find_map(|op| match op {
+                    StructVariant::Field { key, value } if *key == segment => Some(value.as_mut()),
+                    _ => None,
+                })
+                .ok_or(ErrorKind::MissingKey),
+            Self::Map { ops, .. } => {
+                let index = map_value_index(ops, segment).ok_or(ErrorKind::MissingKey)?;
+                match &mut ops[index] {
+                    Map::Value { value } => Ok(value),
+                    Map::Key { .. } => unreachable!("map_value_index returns a value"),
+                }
+            }
+            _ => Err(ErrorKind::NotAContainer),
+        }
+    }
+
+    /// Get the node at `path`.
+    pub fn get(&self, path: &AstPath) -> Result<&Self, Error> {
+        path.segments
+            .iter()
+            .enumerate()
+            .try_fold(self, |node, (i, segment)| {
+                node.child(segment).map_err(|kind| Error {
+                    path: path.prefix(i + 1),
+                    kind,
+                })
+            })
+    }
+
+    /// Get the node at `path` mutably.
+    pub fn get_mut(&mut self, path: &AstPath) -> Result<&mut Self, Error> {
+        path.segments
+            .iter()
+            .enumerate()
+            .try_fold(self, |node, (i, segment)| {
+                node.child_mut(segment).map_err(|kind| Error {
+                    path: path.prefix(i + 1),
+                    kind,
+                })
+            })
+    }
+
+    /// Insert `value` at `path`, returning the replaced node if any.
+    ///
+    /// - fields and map entries are replaced if the key is present, appended otherwise
+    /// - new map keys are parsed as the type of the existing keys, strings for an empty map
+    /// - elements are inserted at the index, shifting the following elements, `-` appends
+    /// - the root node is replaced
+    ///
+    /// The `len` of the container is updated to account for an added element.
+    pub fn insert(&mut self, path: &AstPath, value: Self) -> Result<Option<Self>, Error> {
+        let Some((parent, segment)) = path.split_last() else {
+            return Ok(Some(std::mem::replace(self, value)));
+        };
+        let error = |kind| Error {
+            path: path.clone(),
+            kind,
+        };
+        let index = |len: usize| match segment {
+            "-" => Ok(len),
+            segment => match parse_index(segment)? {
+                index if index <= len => Ok(index),
+                _ => Err(ErrorKind::OutOfBounds),
+            },
+        };
+
+        match self
+            .get_mut(&parent)?
+            .unwrap_transparent_mut()
+            .map_err(error)?
+        {
+            Self::Seq { len, ops } => {
+                let index = index(ops.len()).map_err(error)?;
+                ops.insert(
+                    index,
+                    Seq::Element {
+                        value: Box::new(value),
+                    },
+                );
+                *len = len.map(|len| len + 1);
+                Ok(None)
+            }
+            Self::Tuple { len, ops } => {
+                let index = index(ops.len()).map_err(error)?;
+                ops.insert(
+                    index,
+                    Tuple::Element {
+                        value: Box::new(value),
+                    },
+                );
+                *len += 1;
+                Ok(None)
+            }
+            Self::TupleStruct { len, ops, .. } => {
+                let index = index(ops.len()).map_err(error)?;
+                ops.insert(
+                    index,
+                    TupleStruct::Field {
+                        value: Box::new(value),
+                    },
+                );
+                *len += 1;
+                Ok(None)
+            }
+            Self::TupleVariant { len, ops, .. } => {
+                let index = index(ops.len()).map_err(error)?;
+                ops.insert(
+                    index,
+                    TupleVariant::Field {
+                        value: Box::new(value),
+                    },
+                );
+                *len += 1;
+                Ok(None)
+            }
+            Self::Struct { len, ops, .. } => {
+                let position = ops.iter().position(|op| match op {
+                    Struct::Field { key, .. } | Struct::Skip { key } => *key == segment,
+                });
+                match position.map(|i| &mut ops[i]) {
+                    Some(Struct::Field { value: old, .. }) => {
+                        Ok(Some(std::mem::replace(old, value)))
+                    }
+                    Some(op @ Struct::Skip { .. }) => {
+                        *op = Struct::Field {
+                            key: Name::Owned(segment.to_owned()),
+                            value: Box::new(value),
+                        };
+                        *len += 1;
+                        Ok(None)
+                    }
+                    None => {
+                        ops.push(Struct::Field {
+                            key: Name::Owned(segment.to_owned()),
+                            value: Box::new(value),
+                        });
+                        *len += 1;
+                        Ok(None)
+                    }
+                }
+            }
+            Self::StructVariant { len, ops, .. } => {
+                let position = ops.iter().position(|op| match op {
+                    StructVariant::Field { key, .. } | StructVariant::Skip { key } => {
+                        *key == segment
+                    }
+                });
+                match position.map(|i| &mut ops[i]) {
+                    Some(StructVariant::Field { value: old, .. }) => {
+                        Ok(Some(std::mem::replace(old, value)))
+                    }
+                    Some(op @ StructVariant::Skip { .. }) => {
+                        *op = StructVariant::Field {
+                            key: Name::Owned(segment.to_owned()),
+                            value: Box::new(value),
+                        };
+                        *len += 1;
+                        Ok(None)
+                    }
+                    None => {
+                        ops.push(StructVariant::Field {
+                            key: Name::Owned(segment.to_owned()),
+                            value: Box::new(value),
+                        });
+                        *len += 1;
+                        Ok(None)
+                    }
+                }
+            }
+            Self::Map { len, ops } => match map_value_index(ops, segment) {
+                Some(index) => match &mut ops[index] {
+                    Map::Value { value: old } => Ok(Some(std::mem::replace(old, value))),
+                    Map::Key { .. } => unreachable!("map_value_index returns a value"),
+                },
+                None => {
+                    let key = map_key(ops, segment).map_err(error)?;
+                    ops.push(Map::Key { key: Box::new(key) });
+                    ops.push(Map::Value {
+                        value: Box::new(value),
+                    });
+                    *len = len.map(|len| len + 1);
+                    Ok(None)
+                }
+            },
+            _ => Err(error(ErrorKind::NotAContainer)),
+        }
+    }
+
+    /// Remove the node at `path`, returning it.
+    ///
+    /// The `len` of the container is updated to account for the removed element.
+    pub fn remove(&mut self, path: &AstPath) -> Result<Self, Error> {
+        let Some((parent, segment)) = path.split_last() else {
+            return Err(Error {
+                path: path.clone(),
+                kind: ErrorKind::RemoveRoot,
+            });
+        };
+        let error = |kind| Error {
+            path: path.clone(),
+            kind,
+        };
+        let index = |len: usize| match parse_index(segment)? {
+            index if index < len => Ok(index),
+            _ => Err(ErrorKind::OutOfBounds),
+        };
+
+        match self
+            .get_mut(&parent)?
+            .unwrap_transparent_mut()
+            .map_err(error)?
+        {
+            Self::Seq { len, ops } => {
+                let index = index(ops.len()).map_err(error)?;
+                *len = len.map(|len| len.saturating_sub(1));
+                match ops.remove(index) {
+                    Seq::Element { value } => Ok(*value),
+                }
+            }
+            Self::Tuple { len, ops } => {
+                let index = index(ops.len()).map_err(error)?;
+                *len = len.saturating_sub(1);
+                match ops.remove(index) {
+                    Tuple::Element { value } => Ok(*value),
+                }
+            }
+            Self::TupleStruct { len, ops, .. } => {
+                let index = index(ops.len()).map_err(error)?;
+                *len = len.saturating_sub(1);
+                match ops.remove(index) {
+                    TupleStruct::Field { value } => Ok(*value),
+                }
+            }
+            Self::TupleVariant { len, ops, .. } => {
+                let index = index(ops.len()).map_err(error)?;
+                *len = len.saturating_sub(1);
+                match ops.remove(index) {
+                    TupleVariant::Field { value } => Ok(*value),
+                }
+            }
+            Self::Struct { len, ops, .. } => {
+                let index = ops
+                    .iter()
+                    .position(|op| matches!(op, Struct::Field { key, .. } if *key == segment))
+                    .ok_or_else(|| error(ErrorKind::MissingKey))?;
+                *len = len.saturating_sub(1);
+                match ops.remove(index) {
+                    Struct::Field { value, .. } => Ok(*value),
+                    Struct::Skip { .. } => unreachable!("position matches a field"),
+                }
+            }
+            Self::StructVariant { len, ops, .. } => {
+                let index = ops
+                    .iter()
+                    .position(
+                        |op| matches!(op, StructVariant::Field { key, .. } if *key == segment),
+                    )
+                    .ok_or_else(|| error(ErrorKind::MissingKey))?;
+                *len = len.saturating_sub(1);
+                match ops.remove(index) {
+                    StructVariant::Field { value, .. } => Ok(*value),
+                    StructVariant::Skip { .. } => unreachable!("position matches a field"),
+                }
+            }
+            Self::Map { len, ops } => {
+                let index =
+                    map_value_index(ops, segment).ok_or_else(|| error(ErrorKind::MissingKey))?;
+                *len = len.map(|len| len.saturating_sub(1));
+                let value = ops.remove(index);
+                ops.remove(index - 1);
+                match value {
+                    Map::Value { value } => Ok(*value),
+                    Map::Key { .. } => unreachable!("map_value_index returns a value"),
+                }
+            }
+            _ => Err(error(ErrorKind::NotAContainer)),
+        }
+    }
+}
+
+/// Parse `segment` as a new key of a [XAst::Map] with `ops`, see [key_segment].
+///
+/// The key takes the type of the first existing key, it is a [XAst::Str] if the map is empty or
+/// its keys are not numbers, bools or chars.
+fn map_key<X>(ops: &[Map<X>], segment: &str) -> Result<XAst<X>, ErrorKind> {
+    fn parse<X, T: FromStr>(segment: &str, f: fn(T) -> XAst<X>) -> Result<XAst<X>, ErrorKind> {
+        segment.parse().map(f).map_err(|_| ErrorKind::InvalidKey)
+    }
+    let first = ops.iter().find_map(|op| match op {
+        Map::Key { key } => Some(key.as_ref()),
+        Map::Value { .. } => None,
+    });
+    match first {
+        Some(XAst::Bool(_)) => parse(segment, XAst::Bool),
+        Some(XAst::I8(_)) => parse(segment, XAst::I8),
+        Some(XAst::I16(_)) => parse(segment, XAst::I16),
+        Some(XAst::I32(_)) => parse(segment, XAst::I32),
+        Some(XAst::I64(_)) => parse(segment, XAst::I64),
+        Some(XAst::I128(_)) => parse(segment, XAst::I128),
+        Some(XAst::U8(_)) => parse(segment, XAst::U8),
+        Some(XAst::U16(_)) => parse(segment, XAst::U16),
+        Some(XAst::U32(_)) => parse(segment, XAst::U32),
+        Some(XAst::U64(_)) => parse(segment, XAst::U64),
+        Some(XAst::U128(_)) => parse(segment, XAst::U128),
+        Some(XAst::Char(_)) => parse(segment, XAst::Char),
+        _ => Ok(XAst::Str(segment.to_owned())),
+    }
+}
+
+/// Find the index of the [Map::Value] following a [Map::Key] matching `segment`.
+fn map_value_index<X>(ops: &[Map<X>], segment: &str) -> Option<usize> {
+    ops.windows(2)
+        .position(|pair| match pair {
+            [Map::Key { key }, Map::Value { .. }] => {
+                key_segment(key).is_some_and(|key| key == segment)
+            }
+            _ => false,
+        })
+        .map(|index| index + 1)
+}
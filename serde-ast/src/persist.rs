@@ -0,0 +1,1117 @@
+//! Lossless persistence of the [Ast] structure.
+//!
+//! Unlike serializing an [Ast], which reproduces the value it represents, these encodings keep
+//! the [Ast] itself: every variant, name, `variant_index`, `len` and integer width.
+//!
+//! - [Repr] implements [Serialize] and [Deserialize] for any self-describing data format, e.g. JSON
+//! - [to_bytes] and [from_bytes] use a compact binary encoding
+//!
+//! Decoded names and keys are owned, they are [interned](crate::intern) only when the decoded
+//! [Ast] is serialized.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{persist::{self, Repr}, to_ast, Ast};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     id: u16,
+//! }
+//! let ast = to_ast(&Example { id: 7 }).unwrap();
+//!
+//! let json = serde_json::to_string(&Repr(&ast)).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"Struct":{"name":"Example","len":1,"ops":[{"Field":{"key":"id","value":{"U16":7}}}]}}"#,
+//! );
+//! let Repr(decoded): Repr<Ast> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(decoded, ast);
+//!
+//! let bytes = persist::to_bytes(&ast);
+//! assert_eq!(persist::from_bytes(&bytes).unwrap(), ast);
+//! ```
+//!
+//! # Binary encoding
+//!
+//! The binary encoding starts with the magic bytes `SAST` and a version byte, currently `2`.
+//! Each node is a tag byte, in the order of the [XAst] variants starting at `0` with the later
+//! [XAst::I128], [XAst::U128] and [XAst::Readability] appended as `28`, `29` and `30` in version
+//! `2`, followed by:
+//! - fixed width little endian integers, floats as their bits, chars as `u32`, bools as a byte
+//! - strings, bytes, names and keys as a length followed by the bytes
+//! - lengths as unsigned LEB128 varints, an optional `len` as a `0` or `1` byte followed by the length
+//! - operations as their count followed by each operation, [Map] and [Struct] operations
+//!   prefixed by a tag byte, `0` for a key or field and `1` for a value or skip
+//!
+//! Version `1`, without the appended tags, is still decoded. Nodes nested deeper than
+//! [MAX_DEPTH] fail to decode with [ErrorKind::DepthLimit].
+
+use std::borrow::Borrow;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    Ast,
+};
+
+/// Magic bytes starting the binary encoding.
+const MAGIC: &[u8; 4] = b"SAST";
+/// Version of the binary encoding, bumped when the tags change.
+const VERSION: u8 = 2;
+/// Maximum nesting depth of nodes decoded by [from_bytes].
+pub const MAX_DEPTH: usize = 128;
+
+/// Wrap an [Ast] to [Serialize] or [Deserialize] its structure, see the [module documentation](self).
+///
+/// [Serialize] is implemented for any `A` borrowing an [Ast], e.g. `Repr(&ast)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repr<A = Ast>(pub A);
+
+impl<A> Serialize for Repr<A>
+where
+    A: Borrow<Ast>,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Node::from(self.0.borrow()).serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Repr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Node::deserialize(deserializer).map(|node| Repr(node.into()))
+    }
+}
+
+/// Mirror of [XAst] deriving the structural [Serialize] and [Deserialize].
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Ast")]
+enum Node {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(#[serde(with = "float")] f32),
+    F64(#[serde(with = "float")] f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Node>),
+    Unit,
+    UnitStruct(Name),
+    UnitVariant {
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+    },
+    NewtypeStruct {
+        name: Name,
+        value: Box<Node>,
+    },
+    NewtypeVariant {
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        value: Box<Node>,
+    },
+    Seq {
+        len: Option<usize>,
+        ops: Vec<ElementOp>,
+    },
+    Tuple {
+        len: usize,
+        ops: Vec<ElementOp>,
+    },
+    TupleStruct {
+        name: Name,
+        len: usize,
+        ops: Vec<FieldOp>,
+    },
+    TupleVariant {
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        len: usize,
+        ops: Vec<FieldOp>,
+    },
+    Map {
+        len: Option<usize>,
+        ops: Vec<MapOp>,
+    },
+    Struct {
+        name: Name,
+        len: usize,
+        ops: Vec<StructOp>,
+    },
+    StructVariant {
+        name: Name,
+        variant_index: u32,
+        variant: Name,
+        len: usize,
+        ops: Vec<StructOp>,
+    },
+    Readability {
+        human_readable: Box<Node>,
+        compact: Box<Node>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+enum ElementOp {
+    Element { value: Node },
+}
+
+#[derive(Serialize, Deserialize)]
+enum FieldOp {
+    Field { value: Node },
+}
+
+#[derive(Serialize, Deserialize)]
+enum MapOp {
+    Key { key: Node },
+    Value { value: Node },
+}
+
+#[derive(Serialize, Deserialize)]
+enum StructOp {
+    Field { key: Name, value: Node },
+    Skip { key: Name },
+}
+
+impl From<&Ast> for Node {
+    fn from(ast: &Ast) -> Self {
+        let node = |value: &Ast| Box::new(Node::from(value));
+        match ast {
+            XAst::Bool(v) => Self::Bool(*v),
+            XAst::I8(v) => Self::I8(*v),
+            XAst::I16(v) => Self::I16(*v),
+            XAst::I32(v) => Self::I32(*v),
+            XAst::I64(v) => Self::I64(*v),
+            XAst::I128(v) => Self::I128(*v),
+            XAst::U8(v) => Self::U8(*v),
+            XAst::U16(v) => Self::U16(*v),
+            XAst::U32(v) => Self::U32(*v),
+            XAst::U64(v) => Self::U64(*v),
+            XAst::U128(v) => Self::U128(*v),
+            XAst::F32(v) => Self::F32(*v),
+            XAst::F64(v) => Self::F64(*v),
+            XAst::Char(v) => Self::Char(*v),
+            XAst::Str(v) => Self::Str(v.clone()),
+            XAst::Bytes(v) => Self::Bytes(v.clone()),
+            XAst::None => Self::None,
+            XAst::Some(v) => Self::Some(node(v)),
+            XAst::Unit => Self::Unit,
+            XAst::UnitStruct(name) => Self::UnitStruct(name.clone()),
+            XAst::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            } => Self::UnitVariant {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+            },
+            XAst::NewtypeStruct { name, value } => Self::NewtypeStruct {
+                name: name.clone(),
+                value: node(value),
+            },
+            XAst::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value,
+            } => Self::NewtypeVariant {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+                value: node(value),
+            },
+            XAst::Seq { len, ops } => Self::Seq {
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        Seq::Element { value } => ElementOp::Element {
+                            value: value.as_ref().into(),
+                        },
+                    })
+                    .collect(),
+            },
+            XAst::Tuple { len, ops } => Self::Tuple {
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        Tuple::Element { value } => ElementOp::Element {
+                            value: value.as_ref().into(),
+                        },
+                    })
+                    .collect(),
+            },
+            XAst::TupleStruct { name, len, ops } => Self::TupleStruct {
+                name: name.clone(),
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        TupleStruct::Field { value } => FieldOp::Field {
+                            value: value.as_ref().into(),
+                        },
+                    })
+                    .collect(),
+            },
+            XAst::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => Self::TupleVariant {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        TupleVariant::Field { value } => FieldOp::Field {
+                            value: value.as_ref().into(),
+                        },
+                    })
+                    .collect(),
+            },
+            XAst::Map { len, ops } => Self::Map {
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        Map::Key { key } => MapOp::Key {
+                            key: key.as_ref().into(),
+                        },
+                        Map::Value { value } => MapOp::Value {
+                            value: value.as_ref().into(),
+                        },
+                    })
+                    .collect(),
+            },
+            XAst::Struct { name, len, ops } => Self::Struct {
+                name: name.clone(),
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        Struct::Field { key, value } => StructOp::Field {
+                            key: key.clone(),
+                            value: value.as_ref().into(),
+                        },
+                        Struct::Skip { key } => StructOp::Skip { key: key.clone() },
+                    })
+                    .collect(),
+            },
+            XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => Self::StructVariant {
+                name: name.clone(),
+                variant_index: *variant_index,
+                variant: variant.clone(),
+                len: *len,
+                ops: ops
+                    .iter()
+                    .map(|op| match op {
+                        StructVariant::Field { key, value } => StructOp::Field {
+                            key: key.clone(),
+                            value: value.as_ref().into(),
+                        },
+                        StructVariant::Skip { key } => StructOp::Skip { key: key.clone() },
+                    })
+                    .collect(),
+            },
+            XAst::Readability {
+                human_readable,
+                compact,
+            } => Self::Readability {
+                human_readable: node(human_readable),
+                compact: node(compact),
+            },
+            XAst::X(x) => match *x {},
+        }
+    }
+}
+
+impl From<Node> for Ast {
+    fn from(node: Node) -> Self {
+        let ast = |value: Node| Box::new(Ast::from(value));
+        match node {
+            Node::Bool(v) => Self::Bool(v),
+            Node::I8(v) => Self::I8(v),
+            Node::I16(v) => Self::I16(v),
+            Node::I32(v) => Self::I32(v),
+            Node::I64(v) => Self::I64(v),
+            Node::I128(v) => Self::I128(v),
+            Node::U8(v) => Self::U8(v),
+            Node::U16(v) => Self::U16(v),
+            Node::U32(v) => Self::U32(v),
+            Node::U64(v) => Self::U64(v),
+            Node::U128(v) => Self::U128(v),
+            Node::F32(v) => Self::F32(v),
+            Node::F64(v) => Self::F64(v),
+            Node::Char(v) => Self::Char(v),
+            Node::Str(v) => Self::Str(v),
+            Node::Bytes(v) => Self::Bytes(v),
+            Node::None => Self::None,
+            Node::Some(v) => Self::Some(ast(*v)),
+            Node::Unit => Self::Unit,
+            Node::UnitStruct(name) => Self::UnitStruct(name),
+            Node::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            } => Self::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            },
+            Node::NewtypeStruct { name, value } => Self::NewtypeStruct {
+                name,
+                value: ast(*value),
+            },
+            Node::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value,
+            } => Self::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value: ast(*value),
+            },
+            Node::Seq { len, ops } => Self::Seq {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|ElementOp::Element { value }| Seq::Element { value: ast(value) })
+                    .collect(),
+            },
+            Node::Tuple { len, ops } => Self::Tuple {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|ElementOp::Element { value }| Tuple::Element { value: ast(value) })
+                    .collect(),
+            },
+            Node::TupleStruct { name, len, ops } => Self::TupleStruct {
+                name,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|FieldOp::Field { value }| TupleStruct::Field { value: ast(value) })
+                    .collect(),
+            },
+            Node::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => Self::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|FieldOp::Field { value }| TupleVariant::Field { value: ast(value) })
+                    .collect(),
+            },
+            Node::Map { len, ops } => Self::Map {
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        MapOp::Key { key } => Map::Key { key: ast(key) },
+                        MapOp::Value { value } => Map::Value { value: ast(value) },
+                    })
+                    .collect(),
+            },
+            Node::Struct { name, len, ops } => Self::Struct {
+                name,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        StructOp::Field { key, value } => Struct::Field {
+                            key,
+                            value: ast(value),
+                        },
+                        StructOp::Skip { key } => Struct::Skip { key },
+                    })
+                    .collect(),
+            },
+            Node::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => Self::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .map(|op| match op {
+                        StructOp::Field { key, value } => StructVariant::Field {
+                            key,
+                            value: ast(value),
+                        },
+                        StructOp::Skip { key } => StructVariant::Skip { key },
+                    })
+                    .collect(),
+            },
+            Node::Readability {
+                human_readable,
+                compact,
+            } => Self::Readability {
+                human_readable: ast(*human_readable),
+                compact: ast(*compact),
+            },
+        }
+    }
+}
+
+/// Serialize finite floats as numbers, and non-finite floats as their bits in a hex string,
+/// since formats like JSON can't represent them.
+mod float {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub trait Float: Copy + Serialize + for<'de> Deserialize<'de> {
+        fn is_finite(self) -> bool;
+        fn to_hex(self) -> String;
+        fn from_hex(hex: &str) -> Option<Self>;
+    }
+    impl Float for f32 {
+        fn is_finite(self) -> bool {
+            f32::is_finite(self)
+        }
+        fn to_hex(self) -> String {
+            format!("{:#x}", self.to_bits())
+        }
+        fn from_hex(hex: &str) -> Option<Self> {
+            u32::from_str_radix(hex, 16).ok().map(f32::from_bits)
+        }
+    }
+    impl Float for f64 {
+        fn is_finite(self) -> bool {
+            f64::is_finite(self)
+        }
+        fn to_hex(self) -> String {
+            format!("{:#x}", self.to_bits())
+        }
+        fn from_hex(hex: &str) -> Option<Self> {
+            u64::from_str_radix(hex, 16).ok().map(f64::from_bits)
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<F> {
+        Number(F),
+        Bits(String),
+    }
+
+    pub fn serialize<F: Float, S: Serializer>(v: &F, serializer: S) -> Result<S::Ok, S::Error> {
+        match v.is_finite() {
+            true => v.serialize(serializer),
+            false => serializer.serialize_str(&v.to_hex()),
+        }
+    }
+
+    pub fn deserialize<'de, F: Float, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<F, D::Error> {
+        match Repr::<F>::deserialize(deserializer)? {
+            Repr::Number(v) => Ok(v),
+            Repr::Bits(bits) => bits
+                .strip_prefix("0x")
+                .and_then(F::from_hex)
+                .ok_or_else(|| D::Error::custom(format!("invalid float bits `{bits}`"))),
+        }
+    }
+}
+
+/// Encode `ast` in the compact binary encoding, see the [module documentation](self).
+pub fn to_bytes(ast: &Ast) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    encode(&mut out, ast);
+    out
+}
+
+/// Decode an [Ast] from the compact binary encoding, see the [module documentation](self).
+pub fn from_bytes(bytes: &[u8]) -> Result<Ast, Error> {
+    let mut decoder = Decoder {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(decoder.error(0, ErrorKind::InvalidMagic));
+    }
+    match decoder.u8()? {
+        1 | VERSION => {}
+        version => return Err(decoder.error(4, ErrorKind::UnsupportedVersion(version))),
+    }
+    let ast = decoder.node()?;
+    match decoder.pos == bytes.len() {
+        true => Ok(ast),
+        false => Err(decoder.error(decoder.pos, ErrorKind::TrailingBytes)),
+    }
+}
+
+/// Error decoding the binary encoding.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at byte {offset}")]
+pub struct Error {
+    /// Offset of the error in the input.
+    pub offset: usize,
+    /// Reason decoding failed.
+    pub kind: ErrorKind,
+}
+
+/// Reason decoding the binary encoding failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ErrorKind {
+    #[error("invalid magic bytes")]
+    InvalidMagic,
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("invalid tag {0}")]
+    InvalidTag(u8),
+    #[error("invalid UTF-8")]
+    InvalidUtf8,
+    #[error("invalid char {0:#x}")]
+    InvalidChar(u32),
+    #[error("length overflow")]
+    LengthOverflow,
+    #[error("depth limit of {MAX_DEPTH} exceeded")]
+    DepthLimit,
+    #[error("trailing bytes")]
+    TrailingBytes,
+}
+
+fn encode_len(out: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_optional_len(out: &mut Vec<u8>, len: Option<usize>) {
+    match len {
+        None => out.push(0),
+        Some(len) => {
+            out.push(1);
+            encode_len(out, len);
+        }
+    }
+}
+
+fn encode_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    encode_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn encode_variant(out: &mut Vec<u8>, name: &str, variant_index: u32, variant: &str) {
+    encode_bytes(out, name.as_bytes());
+    out.extend_from_slice(&variant_index.to_le_bytes());
+    encode_bytes(out, variant.as_bytes());
+}
+
+fn encode_values<'a>(out: &mut Vec<u8>, values: impl ExactSizeIterator<Item = &'a Ast>) {
+    encode_len(out, values.len());
+    for value in values {
+        encode(out, value);
+    }
+}
+
+fn encode_fields<'a>(
+    out: &mut Vec<u8>,
+    fields: impl ExactSizeIterator<Item = (&'a str, Option<&'a Ast>)>,
+) {
+    encode_len(out, fields.len());
+    for (key, value) in fields {
+        match value {
+            Some(value) => {
+                out.push(0);
+                encode_bytes(out, key.as_bytes());
+                encode(out, value);
+            }
+            None => {
+                out.push(1);
+                encode_bytes(out, key.as_bytes());
+            }
+        }
+    }
+}
+
+fn encode(out: &mut Vec<u8>, ast: &Ast) {
+    match ast {
+        XAst::Bool(v) => out.extend([0, *v as u8]),
+        XAst::I8(v) => out.extend([1, *v as u8]),
+        XAst::I16(v) => {
+            out.push(2);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::I32(v) => {
+            out.push(3);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::I64(v) => {
+            out.push(4);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::I128(v) => {
+            out.push(28);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::U8(v) => out.extend([5, *v]),
+        XAst::U16(v) => {
+            out.push(6);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::U32(v) => {
+            out.push(7);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::U64(v) => {
+            out.push(8);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::U128(v) => {
+            out.push(29);
+            out.extend(v.to_le_bytes());
+        }
+        XAst::F32(v) => {
+            out.push(9);
+            out.extend(v.to_bits().to_le_bytes());
+        }
+        XAst::F64(v) => {
+            out.push(10);
+            out.extend(v.to_bits().to_le_bytes());
+        }
+        XAst::Char(v) => {
+            out.push(11);
+            out.extend((*v as u32).to_le_bytes());
+        }
+        XAst::Str(v) => {
+            out.push(12);
+            encode_bytes(out, v.as_bytes());
+        }
+        XAst::Bytes(v) => {
+            out.push(13);
+            encode_bytes(out, v);
+        }
+        XAst::None => out.push(14),
+        XAst::Some(v) => {
+            out.push(15);
+            encode(out, v);
+        }
+        XAst::Unit => out.push(16),
+        XAst::UnitStruct(name) => {
+            out.push(17);
+            encode_bytes(out, name.as_bytes());
+        }
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => {
+            out.push(18);
+            encode_variant(out, name, *variant_index, variant);
+        }
+        XAst::NewtypeStruct { name, value } => {
+            out.push(19);
+            encode_bytes(out, name.as_bytes());
+            encode(out, value);
+        }
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => {
+            out.push(20);
+            encode_variant(out, name, *variant_index, variant);
+            encode(out, value);
+        }
+        XAst::Seq { len, ops } => {
+            out.push(21);
+            encode_optional_len(out, *len);
+            encode_values(
+                out,
+                ops.iter().map(|op| match op {
+                    Seq::Element { value } => value.as_ref(),
+                }),
+            );
+        }
+        XAst::Tuple { len, ops } => {
+            out.push(22);
+            encode_len(out, *len);
+            encode_values(
+                out,
+                ops.iter().map(|op| match op {
+                    Tuple::Element { value } => value.as_ref(),
+                }),
+            );
+        }
+        XAst::TupleStruct { name, len, ops } => {
+            out.push(23);
+            encode_bytes(out, name.as_bytes());
+            encode_len(out, *len);
+            encode_values(
+                out,
+                ops.iter().map(|op| match op {
+                    TupleStruct::Field { value } => value.as_ref(),
+                }),
+            );
+        }
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            out.push(24);
+            encode_variant(out, name, *variant_index, variant);
+            encode_len(out, *len);
+            encode_values(
+                out,
+                ops.iter().map(|op| match op {
+                    TupleVariant::Field { value } => value.as_ref(),
+                }),
+            );
+        }
+        XAst::Map { len, ops } => {
+            out.push(25);
+            encode_optional_len(out, *len);
+            encode_len(out, ops.len());
+            for op in ops {
+                match op {
+                    Map::Key { key } => {
+                        out.push(0);
+                        encode(out, key);
+                    }
+                    Map::Value { value } => {
+                        out.push(1);
+                        encode(out, value);
+                    }
+                }
+            }
+        }
+        XAst::Struct { name, len, ops } => {
+            out.push(26);
+            encode_bytes(out, name.as_bytes());
+            encode_len(out, *len);
+            encode_fields(
+                out,
+                ops.iter().map(|op| match op {
+                    Struct::Field { key, value } => (key.as_ref(), Some(value.as_ref())),
+                    Struct::Skip { key } => (key.as_ref(), None),
+                }),
+            );
+        }
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            out.push(27);
+            encode_variant(out, name, *variant_index, variant);
+            encode_len(out, *len);
+            encode_fields(
+                out,
+                ops.iter().map(|op| match op {
+                    StructVariant::Field { key, value } => (key.as_ref(), Some(value.as_ref())),
+                    StructVariant::Skip { key } => (key.as_ref(), None),
+                }),
+            );
+        }
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => {
+            out.push(30);
+            encode(out, human_readable);
+            encode(out, compact);
+        }
+        XAst::X(x) => match *x {},
+    }
+}
+
+struct Decoder<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+    depth: usize,
+}
+impl<'b> Decoder<'b> {
+    fn error(&self, offset: usize, kind: ErrorKind) -> Error {
+        Error { offset, kind }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'b [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error(self.bytes.len(), ErrorKind::UnexpectedEnd))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        let start = self.pos;
+        let mut len: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as usize;
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(self.error(start, ErrorKind::LengthOverflow));
+            }
+            len |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+            shift += 7;
+        }
+    }
+
+    fn optional_len(&mut self) -> Result<Option<usize>, Error> {
+        let start = self.pos;
+        match self.u8()? {
+            0 => Ok(None),
+            1 => self.len().map(Some),
+            tag => Err(self.error(start, ErrorKind::InvalidTag(tag))),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'b [u8], Error> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<&'b str, Error> {
+        let start = self.pos;
+        let bytes = self.bytes()?;
+        std::str::from_utf8(bytes).map_err(|_| self.error(start, ErrorKind::InvalidUtf8))
+    }
+
+    fn name(&mut self) -> Result<Name, Error> {
+        self.string().map(|name| Name::Owned(name.to_owned()))
+    }
+
+    fn variant(&mut self) -> Result<(Name, u32, Name), Error> {
+        let name = self.name()?;
+        let variant_index = u32::from_le_bytes(self.array()?);
+        let variant = self.name()?;
+        Ok((name, variant_index, variant))
+    }
+
+    fn values<T>(&mut self, op: impl Fn(Box<Ast>) -> T) -> Result<Vec<T>, Error> {
+        let count = self.len()?;
+        // don't trust the count for the allocation, each value takes at least one byte
+        let mut ops = Vec::with_capacity(count.min(self.bytes.len() - self.pos));
+        for _ in 0..count {
+            ops.push(op(Box::new(self.node()?)));
+        }
+        Ok(ops)
+    }
+
+    fn fields<T>(&mut self, op: impl Fn(Name, Option<Box<Ast>>) -> T) -> Result<Vec<T>, Error> {
+        let count = self.len()?;
+        let mut ops = Vec::with_capacity(count.min(self.bytes.len() - self.pos));
+        for _ in 0..count {
+            let start = self.pos;
+            ops.push(match self.u8()? {
+                0 => op(self.name()?, Some(Box::new(self.node()?))),
+                1 => op(self.name()?, None),
+                tag => return Err(self.error(start, ErrorKind::InvalidTag(tag))),
+            });
+        }
+        Ok(ops)
+    }
+
+    /// Decode a node, nested one level deeper.
+    fn node(&mut self) -> Result<Ast, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(self.pos, ErrorKind::DepthLimit));
+        }
+        self.depth += 1;
+        let node = self.tagged();
+        self.depth -= 1;
+        node
+    }
+
+    fn tagged(&mut self) -> Result<Ast, Error> {
+        let start = self.pos;
+        Ok(match self.u8()? {
+            0 => match self.u8()? {
+                0 => XAst::Bool(false),
+                1 => XAst::Bool(true),
+                tag => return Err(self.error(start + 1, ErrorKind::InvalidTag(tag))),
+            },
+            1 => XAst::I8(i8::from_le_bytes(self.array()?)),
+            2 => XAst::I16(i16::from_le_bytes(self.array()?)),
+            3 => XAst::I32(i32::from_le_bytes(self.array()?)),
+            4 => XAst::I64(i64::from_le_bytes(self.array()?)),
+            28 => XAst::I128(i128::from_le_bytes(self.array()?)),
+            5 => XAst::U8(self.u8()?),
+            6 => XAst::U16(u16::from_le_bytes(self.array()?)),
+            7 => XAst::U32(u32::from_le_bytes(self.array()?)),
+            8 => XAst::U64(u64::from_le_bytes(self.array()?)),
+            29 => XAst::U128(u128::from_le_bytes(self.array()?)),
+            9 => XAst::F32(f32::from_bits(u32::from_le_bytes(self.array()?))),
+            10 => XAst::F64(f64::from_bits(u64::from_le_bytes(self.array()?))),
+            11 => {
+                let v = u32::from_le_bytes(self.array()?);
+                XAst::Char(
+                    char::from_u32(v)
+                        .ok_or_else(|| self.error(start + 1, ErrorKind::InvalidChar(v)))?,
+                )
+            }
+            12 => XAst::Str(self.string()?.to_owned()),
+            13 => XAst::Bytes(self.bytes()?.to_vec()),
+            14 => XAst::None,
+            15 => XAst::Some(Box::new(self.node()?)),
+            16 => XAst::Unit,
+            17 => XAst::UnitStruct(self.name()?),
+            18 => {
+                let (name, variant_index, variant) = self.variant()?;
+                XAst::UnitVariant {
+                    name,
+                    variant_index,
+                    variant,
+                }
+            }
+            19 => XAst::NewtypeStruct {
+                name: self.name()?,
+                value: Box::new(self.node()?),
+            },
+            20 => {
+                let (name, variant_index, variant) = self.variant()?;
+                XAst::NewtypeVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    value: Box::new(self.node()?),
+                }
+            }
+            21 => XAst::Seq {
+                len: self.optional_len()?,
+                ops: self.values(|value| Seq::Element { value })?,
+            },
+            22 => XAst::Tuple {
+                len: self.len()?,
+                ops: self.values(|value| Tuple::Element { value })?,
+            },
+            23 => XAst::TupleStruct {
+                name: self.name()?,
+                len: self.len()?,
+                ops: self.values(|value| TupleStruct::Field { value })?,
+            },
+            24 => {
+                let (name, variant_index, variant) = self.variant()?;
+                XAst::TupleVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len: self.len()?,
+                    ops: self.values(|value| TupleVariant::Field { value })?,
+                }
+            }
+            25 => {
+                let len = self.optional_len()?;
+                let count = self.len()?;
+                let mut ops = Vec::with_capacity(count.min(self.bytes.len() - self.pos));
+                for _ in 0..count {
+                    let start = self.pos;
+                    ops.push(match self.u8()? {
+                        0 => Map::Key {
+                            key: Box::new(self.node()?),
+                        },
+                        1 => Map::Value {
+                            value: Box::new(self.node()?),
+                        },
+                        tag => return Err(self.error(start, ErrorKind::InvalidTag(tag))),
+                    });
+                }
+                XAst::Map { len, ops }
+            }
+            26 => XAst::Struct {
+                name: self.name()?,
+                len: self.len()?,
+                ops: self.fields(|key, value| match value {
+                    Some(value) => Struct::Field { key, value },
+                    None => Struct::Skip { key },
+                })?,
+            },
+            27 => {
+                let (name, variant_index, variant) = self.variant()?;
+                XAst::StructVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len: self.len()?,
+                    ops: self.fields(|key, value| match value {
+                        Some(value) => StructVariant::Field { key, value },
+                        None => StructVariant::Skip { key },
+                    })?,
+                }
+            }
+            30 => XAst::Readability {
+                human_readable: Box::new(self.node()?),
+                compact: Box::new(self.node()?),
+            },
+            tag => return Err(self.error(start, ErrorKind::InvalidTag(tag))),
+        })
+    }
+}
@@ -0,0 +1,512 @@
+//! Pretty-print an [XAst] as a compact, readable tree.
+//!
+//! The [Display](std::fmt::Display) implementation of [XAst] uses the default [Printer].
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{pretty::Printer, to_ast};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     hello: String,
+//!     list: Vec<u8>,
+//! }
+//! let example = Example { hello: "World".to_string(), list: vec![1, 2] };
+//! let ast = to_ast(&example).expect("serialize to_ast");
+//!
+//! assert_eq!(
+//!     ast.to_string(),
+//!     "Example {\n  hello: \"World\",\n  list: [\n    1u8,\n    2u8,\n  ],\n}",
+//! );
+//!
+//! let printer = Printer::new().indent(None).show_len(true).max_str_len(Some(3));
+//! assert_eq!(
+//!     printer.display(&ast).to_string(),
+//!     "Example { hello: \"Wor…\", list: [1u8, 2u8; len 2]; len 2 }",
+//! );
+//! ```
+//!
+//! Nodes are printed as:
+//! - leaves like Rust literals with a type suffix, e.g. `true`, `80u16`, `1.5f64`, `'c'`, `"str"`, `b"bytes"`
+//! - `None`, `Some(..)`, `()`, `Name`, `Name::Variant`, `Name(..)`, `Name::Variant(..)`
+//! - [XAst::Seq] as `[..]`, [XAst::Tuple] as `(..)`, [XAst::Map] as `{ key => value }`
+//! - [XAst::TupleStruct] and [XAst::TupleVariant] as `Name(..)` and `Name::Variant(..)`
+//! - [XAst::Struct] and [XAst::StructVariant] as `Name { key: value, skip key }`
+//!
+//! With [Printer::show_variant_index] the variant index follows the variant as `Name::Variant@1`,
+//! with [Printer::show_len] the `len` of containers is appended as `; len 2`.
+
+use std::fmt::{Debug, Display, Formatter, Result};
+
+use crate::ast::{Map, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst};
+
+/// Display hook for [XAst::X] extension nodes.
+///
+/// Implemented by closures `Fn(&X, &mut Formatter) -> std::fmt::Result`.
+pub trait DisplayExtension<X> {
+    /// Format the extension node.
+    fn fmt_extension(&self, x: &X, f: &mut Formatter<'_>) -> Result;
+}
+
+/// [DisplayExtension] formatting extension nodes with [Debug].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugExtension;
+impl<X> DisplayExtension<X> for DebugExtension
+where
+    X: Debug,
+{
+    fn fmt_extension(&self, x: &X, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{x:?}")
+    }
+}
+impl<X, F> DisplayExtension<X> for F
+where
+    F: Fn(&X, &mut Formatter<'_>) -> Result,
+{
+    fn fmt_extension(&self, x: &X, f: &mut Formatter<'_>) -> Result {
+        self(x, f)
+    }
+}
+
+/// Configurable [XAst] printer, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Printer<E = DebugExtension> {
+    indent: Option<usize>,
+    show_len: bool,
+    show_variant_index: bool,
+    max_depth: Option<usize>,
+    max_str_len: Option<usize>,
+    colors: bool,
+    extension: E,
+}
+impl Printer {
+    /// Create a [Printer] with the default options.
+    ///
+    /// Indent by 2 spaces, hide `len` and `variant_index`, no limits and no colors.
+    pub fn new() -> Self {
+        Self {
+            indent: Some(2),
+            show_len: false,
+            show_variant_index: false,
+            max_depth: None,
+            max_str_len: None,
+            colors: false,
+            extension: DebugExtension,
+        }
+    }
+}
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<E> Printer<E> {
+    /// Indent nested nodes by this many spaces, or print on a single line with [None].
+    pub fn indent(mut self, indent: Option<usize>) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Show the `len` of containers.
+    pub fn show_len(mut self, show_len: bool) -> Self {
+        self.show_len = show_len;
+        self
+    }
+
+    /// Show the `variant_index` of enum variants.
+    pub fn show_variant_index(mut self, show_variant_index: bool) -> Self {
+        self.show_variant_index = show_variant_index;
+        self
+    }
+
+    /// Elide the children of containers nested deeper than `max_depth`.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Truncate strings and bytes longer than `max_str_len`, with an ellipsis.
+    pub fn max_str_len(mut self, max_str_len: Option<usize>) -> Self {
+        self.max_str_len = max_str_len;
+        self
+    }
+
+    /// Color nodes by type with ANSI escape codes.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Use a [DisplayExtension] hook to print [XAst::X] extension nodes.
+    pub fn extension<F>(self, extension: F) -> Printer<F> {
+        Printer {
+            indent: self.indent,
+            show_len: self.show_len,
+            show_variant_index: self.show_variant_index,
+            max_depth: self.max_depth,
+            max_str_len: self.max_str_len,
+            colors: self.colors,
+            extension,
+        }
+    }
+
+    /// Wrap `ast` to [Display] it with this [Printer].
+    pub fn display<'a, X>(&'a self, ast: &'a XAst<X>) -> Pretty<'a, X, E>
+    where
+        E: DisplayExtension<X>,
+    {
+        Pretty { printer: self, ast }
+    }
+}
+
+/// [Display] an [XAst] with a [Printer], see [Printer::display].
+#[derive(Debug)]
+pub struct Pretty<'a, X, E> {
+    printer: &'a Printer<E>,
+    ast: &'a XAst<X>,
+}
+impl<X, E> Display for Pretty<'_, X, E>
+where
+    E: DisplayExtension<X>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Writer {
+            printer: self.printer,
+            f,
+        }
+        .node(self.ast, 0)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Style {
+    Keyword,
+    Number,
+    Str,
+    Name,
+    Meta,
+    Extension,
+}
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Keyword => "33",
+            Self::Number => "36",
+            Self::Str => "32",
+            Self::Name => "34",
+            Self::Meta => "2",
+            Self::Extension => "35",
+        }
+    }
+}
+
+enum Item<'a, X> {
+    Value(&'a XAst<X>),
+    Field(&'a str, &'a XAst<X>),
+    Skip(&'a str),
+    Entry(Option<&'a XAst<X>>, Option<&'a XAst<X>>),
+}
+
+struct Writer<'p, 'f, 'g, E> {
+    printer: &'p Printer<E>,
+    f: &'f mut Formatter<'g>,
+}
+impl<E> Writer<'_, '_, '_, E> {
+    fn paint(&mut self, style: Style, text: impl Display) -> Result {
+        match self.printer.colors {
+            true => write!(self.f, "\x1b[{}m{text}\x1b[0m", style.code()),
+            false => write!(self.f, "{text}"),
+        }
+    }
+
+    fn str(&mut self, v: &str) -> Result {
+        match self.printer.max_str_len {
+            Some(max) if v.chars().count() > max => {
+                let truncated: String = v.chars().take(max).collect();
+                let quoted = format!("{truncated:?}");
+                let quoted = &quoted[..quoted.len() - 1];
+                self.paint(Style::Str, format_args!("{quoted}…\""))
+            }
+            _ => self.paint(Style::Str, format_args!("{v:?}")),
+        }
+    }
+
+    fn bytes(&mut self, v: &[u8]) -> Result {
+        match self.printer.max_str_len {
+            Some(max) if v.len() > max => self.paint(
+                Style::Str,
+                format_args!("b\"{}…\"", v[..max].escape_ascii()),
+            ),
+            _ => self.paint(Style::Str, format_args!("b\"{}\"", v.escape_ascii())),
+        }
+    }
+
+    fn variant(&mut self, name: &str, variant_index: u32, variant: &str) -> Result {
+        if !name.is_empty() {
+            self.paint(Style::Name, name)?;
+            self.f.write_str("::")?;
+        }
+        self.paint(Style::Name, variant)?;
+        if self.printer.show_variant_index {
+            self.paint(Style::Meta, format_args!("@{variant_index}"))?;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, depth: usize) -> Result {
+        if let Some(indent) = self.printer.indent {
+            write!(self.f, "\n{:width$}", "", width = indent * depth)?;
+        }
+        Ok(())
+    }
+}
+impl<E> Writer<'_, '_, '_, E> {
+    fn node<X>(&mut self, ast: &XAst<X>, depth: usize) -> Result
+    where
+        E: DisplayExtension<X>,
+    {
+        match ast {
+            XAst::Bool(v) => self.paint(Style::Keyword, v),
+            XAst::I8(v) => self.paint(Style::Number, format_args!("{v}i8")),
+            XAst::I16(v) => self.paint(Style::Number, format_args!("{v}i16")),
+            XAst::I32(v) => self.paint(Style::Number, format_args!("{v}i32")),
+            XAst::I64(v) => self.paint(Style::Number, format_args!("{v}i64")),
+            XAst::I128(v) => self.paint(Style::Number, format_args!("{v}i128")),
+            XAst::U8(v) => self.paint(Style::Number, format_args!("{v}u8")),
+            XAst::U16(v) => self.paint(Style::Number, format_args!("{v}u16")),
+            XAst::U32(v) => self.paint(Style::Number, format_args!("{v}u32")),
+            XAst::U64(v) => self.paint(Style::Number, format_args!("{v}u64")),
+            XAst::U128(v) => self.paint(Style::Number, format_args!("{v}u128")),
+            XAst::F32(v) => self.paint(Style::Number, format_args!("{v:?}f32")),
+            XAst::F64(v) => self.paint(Style::Number, format_args!("{v:?}f64")),
+            XAst::Char(v) => self.paint(Style::Str, format_args!("{v:?}")),
+            XAst::Str(v) => self.str(v),
+            XAst::Bytes(v) => self.bytes(v),
+            XAst::None => self.paint(Style::Keyword, "None"),
+            XAst::Some(v) => {
+                self.paint(Style::Keyword, "Some")?;
+                self.f.write_str("(")?;
+                self.node(v, depth)?;
+                self.f.write_str(")")
+            }
+            XAst::Unit => self.paint(Style::Keyword, "()"),
+            XAst::UnitStruct(name) => self.paint(Style::Name, name),
+            XAst::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            } => self.variant(name, *variant_index, variant),
+            XAst::NewtypeStruct { name, value } => {
+                self.paint(Style::Name, name)?;
+                self.f.write_str("(")?;
+                self.node(value, depth)?;
+                self.f.write_str(")")
+            }
+            XAst::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value,
+            } => {
+                self.variant(name, *variant_index, variant)?;
+                self.f.write_str("(")?;
+                self.node(value, depth)?;
+                self.f.write_str(")")
+            }
+            XAst::Seq { len, ops } => self.container(
+                ("[", "]"),
+                *len,
+                ops.iter().map(|op| match op {
+                    Seq::Element { value } => Item::Value(value),
+                }),
+                depth,
+            ),
+            XAst::Tuple { len, ops } => self.container(
+                ("(", ")"),
+                Some(*len),
+                ops.iter().map(|op| match op {
+                    Tuple::Element { value } => Item::Value(value),
+                }),
+                depth,
+            ),
+            XAst::TupleStruct { name, len, ops } => {
+                self.paint(Style::Name, name)?;
+                self.container(
+                    ("(", ")"),
+                    Some(*len),
+                    ops.iter().map(|op| match op {
+                        TupleStruct::Field { value } => Item::Value(value),
+                    }),
+                    depth,
+                )
+            }
+            XAst::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => {
+                self.variant(name, *variant_index, variant)?;
+                self.container(
+                    ("(", ")"),
+                    Some(*len),
+                    ops.iter().map(|op| match op {
+                        TupleVariant::Field { value } => Item::Value(value),
+                    }),
+                    depth,
+                )
+            }
+            XAst::Map { len, ops } => {
+                let mut items = Vec::new();
+                let mut ops = ops.iter().peekable();
+                while let Some(op) = ops.next() {
+                    items.push(match op {
+                        Map::Key { key } => match ops.next_if(|op| matches!(op, Map::Value { .. }))
+                        {
+                            Some(Map::Value { value }) => Item::Entry(Some(key), Some(value)),
+                            _ => Item::Entry(Some(key), None),
+                        },
+                        Map::Value { value } => Item::Entry(None, Some(value)),
+                    });
+                }
+                self.container(("{", "}"), *len, items.into_iter(), depth)
+            }
+            XAst::Struct { name, len, ops } => {
+                if !name.is_empty() {
+                    self.paint(Style::Name, name)?;
+                    self.f.write_str(" ")?;
+                }
+                self.container(
+                    ("{", "}"),
+                    Some(*len),
+                    ops.iter().map(|op| match op {
+                        Struct::Field { key, value } => Item::Field(key, value),
+                        Struct::Skip { key } => Item::Skip(key),
+                    }),
+                    depth,
+                )
+            }
+            XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => {
+                self.variant(name, *variant_index, variant)?;
+                self.f.write_str(" ")?;
+                self.container(
+                    ("{", "}"),
+                    Some(*len),
+                    ops.iter().map(|op| match op {
+                        StructVariant::Field { key, value } => Item::Field(key, value),
+                        StructVariant::Skip { key } => Item::Skip(key),
+                    }),
+                    depth,
+                )
+            }
+            XAst::Readability {
+                human_readable,
+                compact,
+            } => {
+                self.paint(Style::Keyword, "readable")?;
+                self.f.write_str("(")?;
+                self.node(human_readable, depth)?;
+                self.f.write_str(") ")?;
+                self.paint(Style::Keyword, "compact")?;
+                self.f.write_str("(")?;
+                self.node(compact, depth)?;
+                self.f.write_str(")")
+            }
+            XAst::X(x) => {
+                if self.printer.colors {
+                    write!(self.f, "\x1b[{}m", Style::Extension.code())?;
+                    self.printer.extension.fmt_extension(x, self.f)?;
+                    write!(self.f, "\x1b[0m")
+                } else {
+                    self.printer.extension.fmt_extension(x, self.f)
+                }
+            }
+        }
+    }
+
+    /// Write the `items` of a container between `open` and `close`.
+    fn container<'a, X: 'a>(
+        &mut self,
+        (open, close): (&str, &str),
+        len: Option<usize>,
+        items: impl ExactSizeIterator<Item = Item<'a, X>>,
+        depth: usize,
+    ) -> Result
+    where
+        E: DisplayExtension<X>,
+    {
+        let padded = open == "{" && self.printer.indent.is_none();
+        let count = items.len();
+        self.f.write_str(open)?;
+
+        if count > 0 && self.printer.max_depth.is_some_and(|max| depth >= max) {
+            if padded {
+                self.f.write_str(" ")?;
+            }
+            self.paint(Style::Meta, "…")?;
+            if padded {
+                self.f.write_str(" ")?;
+            }
+            return self.f.write_str(close);
+        }
+
+        let show_len = self.printer.show_len;
+        if padded && (count > 0 || show_len) {
+            self.f.write_str(" ")?;
+        }
+        for (i, item) in items.enumerate() {
+            self.line(depth + 1)?;
+            match item {
+                Item::Value(value) => self.node(value, depth + 1)?,
+                Item::Field(key, value) => {
+                    write!(self.f, "{key}: ")?;
+                    self.node(value, depth + 1)?;
+                }
+                Item::Skip(key) => {
+                    self.paint(Style::Meta, "skip")?;
+                    write!(self.f, " {key}")?;
+                }
+                Item::Entry(key, value) => {
+                    match key {
+                        Some(key) => self.node(key, depth + 1)?,
+                        None => self.paint(Style::Meta, "_")?,
+                    }
+                    self.f.write_str(" => ")?;
+                    match value {
+                        Some(value) => self.node(value, depth + 1)?,
+                        None => self.paint(Style::Meta, "_")?,
+                    }
+                }
+            }
+            match self.printer.indent {
+                Some(_) => self.f.write_str(",")?,
+                // trailing comma to tell a single element tuple apart
+                None if open == "(" && count == 1 => self.f.write_str(",")?,
+                None if i + 1 < count => self.f.write_str(", ")?,
+                None => {}
+            }
+        }
+        if show_len {
+            if count > 0 {
+                self.line(depth + 1)?;
+            }
+            match len {
+                Some(len) => self.paint(Style::Meta, format_args!("; len {len}"))?,
+                None => self.paint(Style::Meta, "; len none")?,
+            }
+        }
+        if count > 0 {
+            self.line(depth)?;
+        }
+        if padded && (count > 0 || show_len) {
+            self.f.write_str(" ")?;
+        }
+        self.f.write_str(close)
+    }
+}
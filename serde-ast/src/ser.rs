@@ -1,108 +1,760 @@
-//! Implement [Serializer] for [Ast].
+//! Implement [Serializer] for [XAst].
 
-use std::fmt::Display;
+use std::fmt::{self, Display};
+
+use serde::Serialize;
 
 use crate::{
-    ast::{self, Final},
-    to_ast, Ast,
+    ast::{self, Final, SerializeExtension, XAst},
+    intern::intern_name,
+    path::{key_segment, AstPath},
 };
 
+mod checked;
+mod limits;
+mod tee;
+
+pub use checked::{CheckedCompound, CheckedSerializer};
+pub use tee::{TeeCompound, TeeError, TeeSerializer};
+
+use limits::{Budget, Limits, Spent};
+
 /// [serde::Serializer::Error] for [Serializer]
-#[derive(Debug, thiserror::Error)]
-#[allow(missing_docs)] // errors are self-documenting
-pub enum Error {
-    #[error("error")]
-    Custom(String),
+///
+/// The [Error::path] is accumulated as the error bubbles out of nested values, so it points to
+/// the value which failed, e.g. `.servers[1].port`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// What went wrong.
+    pub kind: ErrorKind,
+    /// Location of the failed value, outermost [Segment] first.
+    pub path: Vec<Segment>,
+}
+impl Error {
+    /// Create a new [Error] of `kind` at the root of the value.
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            path: Vec::new(),
+        }
+    }
+
+    /// Prepend `segment` to the [Error::path], when bubbling out of a nested value.
+    pub fn at(mut self, segment: Segment) -> Self {
+        self.path.insert(0, segment);
+        self
+    }
+
+    /// Address of the failed value as an [AstPath], [Segment::Variant]s are transparent to it.
+    pub fn ast_path(&self) -> AstPath {
+        let mut path = AstPath::root();
+        for segment in &self.path {
+            match segment {
+                Segment::Field(key) => path.push(*key),
+                Segment::Index(index) => path.push(index.to_string()),
+                Segment::Key(key) => path.push(key.as_str()),
+                Segment::Variant(_) => {}
+            }
+        }
+        path
+    }
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if !self.path.is_empty() {
+            write!(f, " at ")?;
+            for segment in &self.path {
+                write!(f, "{segment}")?;
+            }
+        }
+        Ok(())
+    }
 }
+impl std::error::Error for Error {}
 impl serde::ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
         T: Display,
     {
-        Self::Custom(msg.to_string())
+        Self::new(ErrorKind::Custom(msg.to_string()))
     }
 }
 
-/// [Serializer] is a [serde::Serializer] for [Ast].
-#[derive(Debug, Clone)]
-pub struct Serializer {}
+/// Kind of [Error]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ErrorKind {
+    /// Raised by a [Serialize] implementation or a [Hook].
+    #[error("{0}")]
+    Custom(String),
+    /// The value is not supported.
+    #[error("unsupported {0}")]
+    Unsupported(String),
+    /// The value exceeds a configured `limit` of `max`.
+    #[error("{limit} limit of {max} exceeded")]
+    LimitExceeded {
+        /// Name of the limit.
+        limit: &'static str,
+        /// Maximum allowed.
+        max: usize,
+    },
+    /// The [serde::Serializer] protocol was not followed, e.g. a map value without a key.
+    #[error("invalid protocol: {0}")]
+    InvalidProtocol(String),
+}
+
+/// Segment of an [Error::path].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Field of a struct or struct variant.
+    Field(&'static str),
+    /// Element of a sequence or tuple, field of a tuple struct or variant, or entry of a map.
+    Index(usize),
+    /// Value of a map entry with a string-like key, see [key_segment].
+    Key(String),
+    /// Variant of an enum.
+    Variant(&'static str),
+}
+impl Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(key) => write!(f, ".{key}"),
+            Self::Index(index) => write!(f, "[{index}]"),
+            Self::Key(key) => write!(f, "[{key:?}]"),
+            Self::Variant(variant) => write!(f, "::{variant}"),
+        }
+    }
+}
+
+/// [Hook] intercepts calls made to [Serializer], allowing it to produce [XAst::X] extension nodes.
+///
+/// Every method defaults to building the same node as [Serializer] without any [Hook].
+pub trait Hook: Clone {
+    /// Extension type of the produced [XAst].
+    type X;
+
+    /// Intercept [serde::Serializer::serialize_newtype_struct] before `value` is serialized.
+    ///
+    /// Returning `Some` node replaces [XAst::NewtypeStruct], e.g. for magic names like
+    /// `serde_json`'s `RawValue`.
+    fn newtype_struct<T>(
+        &self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Option<XAst<Self::X>>, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = (name, value);
+        Ok(None)
+    }
+
+    /// Intercept the `value` of a field `key` of [XAst::Struct] or [XAst::StructVariant].
+    fn field(&self, key: &'static str, value: XAst<Self::X>) -> Result<XAst<Self::X>, Error> {
+        let _ = key;
+        Ok(value)
+    }
+
+    /// Intercept every `node` built by [Serializer], e.g. to replace a struct with a specific name.
+    fn node(&self, node: XAst<Self::X>) -> Result<XAst<Self::X>, Error> {
+        Ok(node)
+    }
+
+    /// Intercept the `error` of a nested value, returning a node to record in its place instead.
+    fn error(&self, error: Error) -> Result<XAst<Self::X>, Error> {
+        Err(error)
+    }
+}
+
+/// No-op [Hook], producing a closed [Ast](crate::Ast).
+impl Hook for () {
+    type X = Final;
+}
+
+/// [Hook] recording errors of nested values as [Failure] markers, see [to_ast_partial](crate::to_ast_partial).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Partial;
+impl Hook for Partial {
+    type X = Failure;
+
+    fn error(&self, error: Error) -> Result<XAst<Self::X>, Error> {
+        Ok(XAst::X(Failure(error)))
+    }
+}
+
+/// Extension node marking the site of a failed value in an [XAst] built with [Partial].
+///
+/// Serializing it fails again with the same [Error].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure(pub Error);
+impl SerializeExtension for Failure {
+    fn serialize_extension<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Err(serde::ser::Error::custom(&self.0))
+    }
+}
+
+/// Error of [to_ast_partial](crate::to_ast_partial), keeping the partially built [XAst].
+#[derive(Debug, thiserror::Error)]
+#[error("{error}")]
+pub struct PartialError {
+    /// The first error, at its full path.
+    pub error: Error,
+    /// Everything recorded, with a [Failure] marker at each failed value.
+    pub partial: Box<XAst<Failure>>,
+}
+
+/// Find the first [Failure] of `node` in serialization order, at its full path.
+///
+/// [Partial] records errors of nested values before their [Segment]s are prepended, so they are
+/// prepended here instead, the same way [Serializer] does while an error bubbles out.
+pub(crate) fn first_failure(node: &XAst<Failure>) -> Option<Error> {
+    fn first<'a>(values: impl IntoIterator<Item = &'a XAst<Failure>>) -> Option<(usize, Error)> {
+        values
+            .into_iter()
+            .enumerate()
+            .find_map(|(index, value)| Some((index, first_failure(value)?)))
+    }
+
+    match node {
+        XAst::X(Failure(error)) => Some(error.clone()),
+        XAst::Some(value) | XAst::NewtypeStruct { value, .. } => first_failure(value),
+        XAst::NewtypeVariant { variant, value, .. } => {
+            first_failure(value).map(|e| e.at(Segment::Variant(intern_name(variant))))
+        }
+        XAst::Seq { ops, .. } => first(ops.iter().map(|ast::Seq::Element { value }| &**value))
+            .map(|(index, e)| e.at(Segment::Index(index))),
+        XAst::Tuple { ops, .. } => first(ops.iter().map(|ast::Tuple::Element { value }| &**value))
+            .map(|(index, e)| e.at(Segment::Index(index))),
+        XAst::TupleStruct { ops, .. } => {
+            first(ops.iter().map(|ast::TupleStruct::Field { value }| &**value))
+                .map(|(index, e)| e.at(Segment::Index(index)))
+        }
+        XAst::TupleVariant { variant, ops, .. } => first(
+            ops.iter()
+                .map(|ast::TupleVariant::Field { value }| &**value),
+        )
+        .map(|(index, e)| {
+            e.at(Segment::Index(index))
+                .at(Segment::Variant(intern_name(variant)))
+        }),
+        XAst::Map { ops, .. } => {
+            let mut last_key = None;
+            ops.iter().enumerate().find_map(|(index, op)| match op {
+                ast::Map::Key { key } => {
+                    last_key = Some(key);
+                    first_failure(key).map(|e| e.at(Segment::Index(index / 2)))
+                }
+                ast::Map::Value { value } => {
+                    let segment = last_key
+                        .take()
+                        .and_then(|key| key_segment(key))
+                        .map(|key| Segment::Key(key.into_owned()))
+                        .unwrap_or(Segment::Index(index / 2));
+                    first_failure(value).map(|e| e.at(segment))
+                }
+            })
+        }
+        XAst::Struct { ops, .. } => ops.iter().find_map(|op| match op {
+            ast::Struct::Field { key, value } => {
+                first_failure(value).map(|e| e.at(Segment::Field(intern_name(key))))
+            }
+            ast::Struct::Skip { .. } => None,
+        }),
+        XAst::StructVariant { variant, ops, .. } => ops.iter().find_map(|op| match op {
+            ast::StructVariant::Field { key, value } => first_failure(value).map(|e| {
+                e.at(Segment::Field(intern_name(key)))
+                    .at(Segment::Variant(intern_name(variant)))
+            }),
+            ast::StructVariant::Skip { .. } => None,
+        }),
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => first_failure(human_readable).or_else(|| first_failure(compact)),
+        _ => None,
+    }
+}
+
+/// Mode reported by [serde::Serializer::is_human_readable] of [Serializer].
+///
+/// Types like `IpAddr` serialize differently depending on the mode, an [XAst] replays the same
+/// calls into any format, so it only matches direct serialization into formats of its mode.
+/// [Serializer::serialize_both] captures both modes into [XAst::Readability] nodes instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Readability {
+    /// Human-readable, like `serde_json`.
+    #[default]
+    HumanReadable,
+    /// Compact, like `bincode`.
+    Compact,
+}
+
+/// [Serializer] is a [serde::Serializer] for [XAst].
+///
+/// The [Hook] `H` decides which [XAst] nodes are produced, the default builds a closed [Ast](crate::Ast).
+///
+/// Values can be bounded in depth and size, e.g. with [Serializer::max_depth], exceeding a limit
+/// fails with [ErrorKind::LimitExceeded]. Each clone starts with an unused budget.
+///
+/// ```
+/// # use serde_ast::ser::{ErrorKind, Readability, Serializer};
+/// # use serde::Serialize;
+/// let serializer = Serializer::new(Readability::HumanReadable).max_depth(Some(1));
+/// let error = vec![vec![vec![1]]].serialize(serializer).unwrap_err();
+/// assert_eq!(error.kind, ErrorKind::LimitExceeded { limit: "depth", max: 1 });
+/// assert_eq!(error.to_string(), "depth limit of 1 exceeded at [0][0]");
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Serializer<H = ()> {
+    hook: H,
+    readability: Readability,
+    strict: bool,
+    limits: Limits,
+    budget: Budget,
+}
+impl<H> Clone for Serializer<H>
+where
+    H: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            hook: self.hook.clone(),
+            readability: self.readability,
+            strict: self.strict,
+            limits: self.limits,
+            budget: Budget::default(),
+        }
+    }
+}
 impl Serializer {
-    /// Create a new [Serializer].
-    #[allow(clippy::new_without_default)] // might accept args in the future
-    pub fn new() -> Self {
-        Self {}
+    /// Create a new [Serializer] in the `readability` mode.
+    pub fn new(readability: Readability) -> Self {
+        Self::with_hook(()).with_readability(readability)
     }
 }
-impl serde::Serializer for Serializer {
-    type Ok = Ast;
+impl<H> Serializer<H>
+where
+    H: Hook,
+{
+    /// Create a new human-readable [Serializer] intercepting calls with `hook`.
+    pub fn with_hook(hook: H) -> Self {
+        Self {
+            hook,
+            readability: Readability::HumanReadable,
+            strict: false,
+            limits: Limits::default(),
+            budget: Budget::default(),
+        }
+    }
+
+    /// Use the `readability` mode.
+    pub fn with_readability(self, readability: Readability) -> Self {
+        Self {
+            readability,
+            ..self
+        }
+    }
+
+    /// Check every built node with [validate::check](crate::validate::check) once all [Hook]s
+    /// ran, failing with [ErrorKind::InvalidProtocol].
+    ///
+    /// Only [Serialize] implementations not following the protocol fail, e.g. serializing fewer
+    /// elements than the declared `len`.
+    pub fn strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Serialize `value` in both [Readability] modes, ignoring the configured one.
+    ///
+    /// Values serializing the same way in both modes are kept once, the smallest values which
+    /// differ become [XAst::Readability] nodes. Serializing the result into any format is then
+    /// equivalent to directly serializing the value.
+    ///
+    /// ```
+    /// # use std::net::Ipv4Addr;
+    /// # use serde_ast::{ast::XAst, ser::{Readability, Serializer}};
+    /// let ip = Ipv4Addr::new(127, 0, 0, 1);
+    /// let ast = Serializer::new(Readability::HumanReadable)
+    ///     .serialize_both(&Some(ip))
+    ///     .unwrap();
+    /// let XAst::Some(ip) = ast else { panic!() };
+    /// assert!(matches!(*ip, XAst::Readability { .. }));
+    /// ```
+    pub fn serialize_both<T>(&self, value: &T) -> Result<XAst<H::X>, Error>
+    where
+        T: ?Sized + Serialize,
+        H::X: PartialEq,
+    {
+        let human_readable =
+            value.serialize(self.clone().with_readability(Readability::HumanReadable))?;
+        let compact = value.serialize(self.clone().with_readability(Readability::Compact))?;
+        Ok(split(human_readable, compact))
+    }
+
+    /// Serialize a nested `value` with the same [Hook].
+    fn to_ast<T>(&self, value: &T) -> Result<XAst<H::X>, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value
+            .serialize(self.nested()?)
+            .or_else(|error| self.hook.error(error))
+    }
+
+    fn node(&self, node: XAst<H::X>) -> Result<XAst<H::X>, Error> {
+        self.spend_node()?;
+        let spent = Spent::of(&node);
+        self.hooked(node, spent)
+    }
+
+    /// Pass `node` through [Hook::node], then account what was not [Spent] yet.
+    fn hooked(&self, node: XAst<H::X>, spent: Spent<H::X>) -> Result<XAst<H::X>, Error> {
+        let node = self.hook.node(node)?;
+        self.account(&node, &spent, 0)?;
+        Ok(node)
+    }
+
+    /// Pass the `value` of a field `key` through [Hook::field], then account what it added.
+    fn field(&self, key: &'static str, value: XAst<H::X>) -> Result<XAst<H::X>, Error> {
+        let spent = Spent::of(&value);
+        let value = self.hook.field(key, value)?;
+        self.account(&value, &spent, 1)?;
+        Ok(value)
+    }
+}
+/// Merge the serializations of the same value in both [Readability] modes, keeping the equal
+/// parts once and splitting the differing values into [XAst::Readability] nodes.
+fn split<X>(human_readable: XAst<X>, compact: XAst<X>) -> XAst<X>
+where
+    X: PartialEq,
+{
+    if human_readable == compact {
+        return human_readable;
+    }
+    let value = |h: Box<XAst<X>>, c: Box<XAst<X>>| Box::new(split(*h, *c));
+    match (human_readable, compact) {
+        (XAst::Some(h), XAst::Some(c)) => XAst::Some(value(h, c)),
+        (
+            XAst::NewtypeStruct { name, value: h },
+            XAst::NewtypeStruct {
+                name: c_name,
+                value: c,
+            },
+        ) if name == c_name => XAst::NewtypeStruct {
+            name,
+            value: value(h, c),
+        },
+        (
+            XAst::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value: h,
+            },
+            XAst::NewtypeVariant {
+                name: c_name,
+                variant_index: c_index,
+                variant: c_variant,
+                value: c,
+            },
+        ) if (&name, variant_index, &variant) == (&c_name, c_index, &c_variant) => {
+            XAst::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value: value(h, c),
+            }
+        }
+        (XAst::Seq { len, ops }, XAst::Seq { len: c_len, ops: c })
+            if len == c_len && ops.len() == c.len() =>
+        {
+            XAst::Seq {
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(
+                        |(ast::Seq::Element { value: h }, ast::Seq::Element { value: c })| {
+                            ast::Seq::Element { value: value(h, c) }
+                        },
+                    )
+                    .collect(),
+            }
+        }
+        (XAst::Tuple { len, ops }, XAst::Tuple { len: c_len, ops: c })
+            if len == c_len && ops.len() == c.len() =>
+        {
+            XAst::Tuple {
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(
+                        |(ast::Tuple::Element { value: h }, ast::Tuple::Element { value: c })| {
+                            ast::Tuple::Element { value: value(h, c) }
+                        },
+                    )
+                    .collect(),
+            }
+        }
+        (
+            XAst::TupleStruct { name, len, ops },
+            XAst::TupleStruct {
+                name: c_name,
+                len: c_len,
+                ops: c,
+            },
+        ) if (&name, len, ops.len()) == (&c_name, c_len, c.len()) => XAst::TupleStruct {
+            name,
+            len,
+            ops: ops
+                .into_iter()
+                .zip(c)
+                .map(
+                    |(
+                        ast::TupleStruct::Field { value: h },
+                        ast::TupleStruct::Field { value: c },
+                    )| { ast::TupleStruct::Field { value: value(h, c) } },
+                )
+                .collect(),
+        },
+        (
+            XAst::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            },
+            XAst::TupleVariant {
+                name: c_name,
+                variant_index: c_index,
+                variant: c_variant,
+                len: c_len,
+                ops: c,
+            },
+        ) if (&name, variant_index, &variant, len, ops.len())
+            == (&c_name, c_index, &c_variant, c_len, c.len()) =>
+        {
+            XAst::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(
+                        |(
+                            ast::TupleVariant::Field { value: h },
+                            ast::TupleVariant::Field { value: c },
+                        )| ast::TupleVariant::Field { value: value(h, c) },
+                    )
+                    .collect(),
+            }
+        }
+        (XAst::Map { len, ops }, XAst::Map { len: c_len, ops: c })
+            if len == c_len
+                && ops.len() == c.len()
+                && ops.iter().zip(&c).all(|ops| {
+                    matches!(
+                        ops,
+                        (ast::Map::Key { .. }, ast::Map::Key { .. })
+                            | (ast::Map::Value { .. }, ast::Map::Value { .. })
+                    )
+                }) =>
+        {
+            XAst::Map {
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(|ops| match ops {
+                        (ast::Map::Key { key: h }, ast::Map::Key { key: c }) => {
+                            ast::Map::Key { key: value(h, c) }
+                        }
+                        (ast::Map::Value { value: h }, ast::Map::Value { value: c }) => {
+                            ast::Map::Value { value: value(h, c) }
+                        }
+                        _ => unreachable!("operations are checked to match"),
+                    })
+                    .collect(),
+            }
+        }
+        (
+            XAst::Struct { name, len, ops },
+            XAst::Struct {
+                name: c_name,
+                len: c_len,
+                ops: c,
+            },
+        ) if (&name, len, ops.len()) == (&c_name, c_len, c.len())
+            && ops.iter().zip(&c).all(|ops| match ops {
+                (ast::Struct::Field { key: h, .. }, ast::Struct::Field { key: c, .. })
+                | (ast::Struct::Skip { key: h }, ast::Struct::Skip { key: c }) => h == c,
+                _ => false,
+            }) =>
+        {
+            XAst::Struct {
+                name,
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(|ops| match ops {
+                        (
+                            ast::Struct::Field { key, value: h },
+                            ast::Struct::Field { value: c, .. },
+                        ) => ast::Struct::Field {
+                            key,
+                            value: value(h, c),
+                        },
+                        (skip, _) => skip,
+                    })
+                    .collect(),
+            }
+        }
+        (
+            XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            },
+            XAst::StructVariant {
+                name: c_name,
+                variant_index: c_index,
+                variant: c_variant,
+                len: c_len,
+                ops: c,
+            },
+        ) if (&name, variant_index, &variant, len, ops.len())
+            == (&c_name, c_index, &c_variant, c_len, c.len())
+            && ops.iter().zip(&c).all(|ops| match ops {
+                (
+                    ast::StructVariant::Field { key: h, .. },
+                    ast::StructVariant::Field { key: c, .. },
+                )
+                | (ast::StructVariant::Skip { key: h }, ast::StructVariant::Skip { key: c }) => {
+                    h == c
+                }
+                _ => false,
+            }) =>
+        {
+            XAst::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops: ops
+                    .into_iter()
+                    .zip(c)
+                    .map(|ops| match ops {
+                        (
+                            ast::StructVariant::Field { key, value: h },
+                            ast::StructVariant::Field { value: c, .. },
+                        ) => ast::StructVariant::Field {
+                            key,
+                            value: value(h, c),
+                        },
+                        (skip, _) => skip,
+                    })
+                    .collect(),
+            }
+        }
+        (human_readable, compact) => XAst::Readability {
+            human_readable: Box::new(human_readable),
+            compact: Box::new(compact),
+        },
+    }
+}
+
+impl<H> serde::Serializer for Serializer<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
-    type SerializeSeq = SerializeSeq;
-    type SerializeTuple = SerializeTuple;
-    type SerializeTupleStruct = SerializeTupleStruct;
-    type SerializeTupleVariant = SerializeTupleVariant;
-    type SerializeMap = SerializeMap;
-    type SerializeStruct = SerializeStruct;
-    type SerializeStructVariant = SerializeStructVariant;
+    type SerializeSeq = SerializeSeq<H>;
+    type SerializeTuple = SerializeTuple<H>;
+    type SerializeTupleStruct = SerializeTupleStruct<H>;
+    type SerializeTupleVariant = SerializeTupleVariant<H>;
+    type SerializeMap = SerializeMap<H>;
+    type SerializeStruct = SerializeStruct<H>;
+    type SerializeStructVariant = SerializeStructVariant<H>;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::Bool(v))
+        self.node(XAst::Bool(v))
     }
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::I8(v))
+        self.node(XAst::I8(v))
     }
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::I16(v))
+        self.node(XAst::I16(v))
     }
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::I32(v))
+        self.node(XAst::I32(v))
     }
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::I64(v))
+        self.node(XAst::I64(v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.node(XAst::I128(v))
     }
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::U8(v))
+        self.node(XAst::U8(v))
     }
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::U16(v))
+        self.node(XAst::U16(v))
     }
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::U32(v))
+        self.node(XAst::U32(v))
     }
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::U64(v))
+        self.node(XAst::U64(v))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.node(XAst::U128(v))
     }
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::F32(v))
+        self.node(XAst::F32(v))
     }
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::F64(v))
+        self.node(XAst::F64(v))
     }
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::Char(v))
+        self.node(XAst::Char(v))
     }
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::Str(v.to_owned()))
+        self.spend_bytes(v.len())?;
+        self.node(XAst::Str(v.to_owned()))
     }
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::Bytes(v.to_owned()))
+        self.spend_bytes(v.len())?;
+        self.node(XAst::Bytes(v.to_owned()))
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::None)
+        self.node(XAst::None)
     }
     fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
-        Ok(Ast::Some(Box::new(to_ast(value)?)))
+        let value = self.to_ast(value)?;
+        self.node(XAst::Some(Box::new(value)))
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::Unit)
+        self.node(XAst::Unit)
     }
     fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::UnitStruct(name))
+        self.node(XAst::UnitStruct(name.into()))
     }
     fn serialize_unit_variant(
         self,
@@ -110,10 +762,10 @@ impl serde::Serializer for Serializer {
         variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(Ast::UnitVariant {
-            name,
+        self.node(XAst::UnitVariant {
+            name: name.into(),
             variant_index,
-            variant,
+            variant: variant.into(),
         })
     }
 
@@ -125,9 +777,13 @@ impl serde::Serializer for Serializer {
     where
         T: ?Sized + serde::Serialize,
     {
-        Ok(Ast::NewtypeStruct {
-            name,
-            value: Box::new(to_ast(value)?),
+        if let Some(node) = self.hook.newtype_struct(name, value)? {
+            return self.hooked(node, Spent::none());
+        }
+        let value = self.to_ast(value)?;
+        self.node(XAst::NewtypeStruct {
+            name: name.into(),
+            value: Box::new(value),
         })
     }
     fn serialize_newtype_variant<T>(
@@ -140,27 +796,33 @@ impl serde::Serializer for Serializer {
     where
         T: ?Sized + serde::Serialize,
     {
-        Ok(Ast::NewtypeVariant {
-            name,
+        let value = self
+            .to_ast(value)
+            .map_err(|e| e.at(Segment::Variant(variant)))?;
+        self.node(XAst::NewtypeVariant {
+            name: name.into(),
             variant_index,
-            variant,
-            value: Box::new(to_ast(value)?),
+            variant: variant.into(),
+            value: Box::new(value),
         })
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Ok(SerializeSeq::new(len))
+        self.check_len(len.unwrap_or(0))?;
+        Ok(SerializeSeq::new(self, len))
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Ok(SerializeTuple::new(len))
+        self.check_len(len)?;
+        Ok(SerializeTuple::new(self, len))
     }
     fn serialize_tuple_struct(
         self,
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(SerializeTupleStruct::new(name, len))
+        self.check_len(len)?;
+        Ok(SerializeTupleStruct::new(self, name, len))
     }
     fn serialize_tuple_variant(
         self,
@@ -169,7 +831,9 @@ impl serde::Serializer for Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.check_len(len)?;
         Ok(SerializeTupleVariant::new(
+            self,
             name,
             variant_index,
             variant,
@@ -178,7 +842,8 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Ok(SerializeMap::new(len))
+        self.check_len(len.unwrap_or(0))?;
+        Ok(SerializeMap::new(self, len))
     }
 
     fn serialize_struct(
@@ -186,7 +851,8 @@ impl serde::Serializer for Serializer {
         name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(SerializeStruct::new(name, len))
+        self.check_len(len)?;
+        Ok(SerializeStruct::new(self, name, len))
     }
     fn serialize_struct_variant(
         self,
@@ -195,48 +861,75 @@ impl serde::Serializer for Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.check_len(len)?;
         Ok(SerializeStructVariant::new(
+            self,
             name,
             variant_index,
             variant,
             len,
         ))
     }
+
+    fn is_human_readable(&self) -> bool {
+        self.readability == Readability::HumanReadable
+    }
 }
 
 /// [serde::ser::SerializeTuple] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeTuple {
+pub struct SerializeTuple<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     len: usize,
-    inner_ops: Vec<ast::Tuple<Final>>,
+    inner_ops: Vec<ast::Tuple<H::X>>,
 }
-impl SerializeTuple {
+impl<H> SerializeTuple<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeTuple].
-    pub fn new(len: usize) -> Self {
+    pub fn new(serializer: Serializer<H>, len: usize) -> Self {
         Self {
+            serializer,
             len,
             inner_ops: Vec::new(),
         }
     }
 }
-impl serde::ser::SerializeTuple for SerializeTuple {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeTuple for SerializeTuple<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        let index = self.inner_ops.len();
+        self.serializer.check_len(index + 1)?;
+        let value = self
+            .serializer
+            .to_ast(value)
+            .map_err(|e| e.at(Segment::Index(index)))?;
         self.inner_ops.push(ast::Tuple::Element {
-            value: Box::new(to_ast(value)?),
+            value: Box::new(value),
         });
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let Self { len, inner_ops } = self;
+        let Self {
+            serializer,
+            len,
+            inner_ops,
+        } = self;
 
-        Ok(Ast::Tuple {
+        serializer.node(XAst::Tuple {
             len,
             ops: inner_ops,
         })
@@ -245,34 +938,46 @@ impl serde::ser::SerializeTuple for SerializeTuple {
 
 /// [serde::ser::SerializeTupleStruct] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeTupleStruct {
+pub struct SerializeTupleStruct<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     name: &'static str,
     len: usize,
-    inner_ops: Vec<ast::TupleStruct<Final>>,
+    inner_ops: Vec<ast::TupleStruct<H::X>>,
 }
-impl SerializeTupleStruct {
+impl<H> SerializeTupleStruct<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeTupleStruct].
-    pub fn new(name: &'static str, len: usize) -> Self {
+    pub fn new(serializer: Serializer<H>, name: &'static str, len: usize) -> Self {
         Self {
+            serializer,
             name,
             len,
             inner_ops: Vec::new(),
         }
     }
 }
-impl serde::ser::SerializeTupleStruct for SerializeTupleStruct {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeTupleStruct for SerializeTupleStruct<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         let Self {
+            serializer,
             name,
             len,
             inner_ops,
         } = self;
 
-        Ok(Ast::TupleStruct {
-            name,
+        serializer.node(XAst::TupleStruct {
+            name: name.into(),
             len,
             ops: inner_ops,
         })
@@ -282,8 +987,14 @@ impl serde::ser::SerializeTupleStruct for SerializeTupleStruct {
     where
         T: ?Sized + serde::Serialize,
     {
+        let index = self.inner_ops.len();
+        self.serializer.check_len(index + 1)?;
+        let value = self
+            .serializer
+            .to_ast(value)
+            .map_err(|e| e.at(Segment::Index(index)))?;
         self.inner_ops.push(ast::TupleStruct::Field {
-            value: Box::new(to_ast(value)?),
+            value: Box::new(value),
         });
         Ok(())
     }
@@ -291,17 +1002,31 @@ impl serde::ser::SerializeTupleStruct for SerializeTupleStruct {
 
 /// [serde::ser::SerializeTupleVariant] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeTupleVariant {
+pub struct SerializeTupleVariant<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     name: &'static str,
     variant_index: u32,
     variant: &'static str,
     len: usize,
-    inner_ops: Vec<ast::TupleVariant<Final>>,
+    inner_ops: Vec<ast::TupleVariant<H::X>>,
 }
-impl SerializeTupleVariant {
+impl<H> SerializeTupleVariant<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeTupleVariant].
-    pub fn new(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Self {
+    pub fn new(
+        serializer: Serializer<H>,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Self {
         Self {
+            serializer,
             name,
             variant_index,
             variant,
@@ -310,12 +1035,16 @@ impl SerializeTupleVariant {
         }
     }
 }
-impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeTupleVariant for SerializeTupleVariant<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         let Self {
+            serializer,
             name,
             variant_index,
             variant,
@@ -323,10 +1052,10 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
             inner_ops,
         } = self;
 
-        Ok(Ast::TupleVariant {
-            name,
+        serializer.node(XAst::TupleVariant {
+            name: name.into(),
             variant_index,
-            variant,
+            variant: variant.into(),
             len,
             ops: inner_ops,
         })
@@ -336,8 +1065,14 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
     where
         T: ?Sized + serde::Serialize,
     {
+        let index = self.inner_ops.len();
+        self.serializer.check_len(index + 1)?;
+        let value = self.serializer.to_ast(value).map_err(|e| {
+            e.at(Segment::Index(index))
+                .at(Segment::Variant(self.variant))
+        })?;
         self.inner_ops.push(ast::TupleVariant::Field {
-            value: Box::new(to_ast(value)?),
+            value: Box::new(value),
         });
         Ok(())
     }
@@ -345,37 +1080,58 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
 
 /// [serde::ser::SerializeSeq] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeSeq {
+pub struct SerializeSeq<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     len: Option<usize>,
-    inner_ops: Vec<ast::Seq<Final>>,
+    inner_ops: Vec<ast::Seq<H::X>>,
 }
-impl SerializeSeq {
+impl<H> SerializeSeq<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeSeq].
-    pub fn new(len: Option<usize>) -> Self {
+    pub fn new(serializer: Serializer<H>, len: Option<usize>) -> Self {
         Self {
+            serializer,
             len,
             inner_ops: Vec::new(),
         }
     }
 }
-impl serde::ser::SerializeSeq for SerializeSeq {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeSeq for SerializeSeq<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        let index = self.inner_ops.len();
+        self.serializer.check_len(index + 1)?;
+        let value = self
+            .serializer
+            .to_ast(value)
+            .map_err(|e| e.at(Segment::Index(index)))?;
         self.inner_ops.push(ast::Seq::Element {
-            value: Box::new(to_ast(value)?),
+            value: Box::new(value),
         });
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let Self { len, inner_ops } = self;
+        let Self {
+            serializer,
+            len,
+            inner_ops,
+        } = self;
 
-        Ok(Ast::Seq {
+        serializer.node(XAst::Seq {
             len,
             ops: inner_ops,
         })
@@ -384,46 +1140,72 @@ impl serde::ser::SerializeSeq for SerializeSeq {
 
 /// [serde::ser::SerializeMap] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeMap {
+pub struct SerializeMap<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     len: Option<usize>,
-    inner_ops: Vec<ast::Map<Final>>,
+    inner_ops: Vec<ast::Map<H::X>>,
 }
-impl SerializeMap {
+impl<H> SerializeMap<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeMap].
-    pub fn new(len: Option<usize>) -> Self {
+    pub fn new(serializer: Serializer<H>, len: Option<usize>) -> Self {
         Self {
+            serializer,
             len,
             inner_ops: Vec::new(),
         }
     }
 }
-impl serde::ser::SerializeMap for SerializeMap {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeMap for SerializeMap<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
-        self.inner_ops.push(ast::Map::Key {
-            key: Box::new(to_ast(key)?),
-        });
+        let index = self.inner_ops.len() / 2;
+        self.serializer.check_len(index + 1)?;
+        let key = self
+            .serializer
+            .to_ast(key)
+            .map_err(|e| e.at(Segment::Index(index)))?;
+        self.inner_ops.push(ast::Map::Key { key: Box::new(key) });
         Ok(())
     }
     fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        let segment = match self.inner_ops.last() {
+            Some(ast::Map::Key { key }) => key_segment(key)
+                .map(|key| Segment::Key(key.into_owned()))
+                .unwrap_or(Segment::Index(self.inner_ops.len() / 2)),
+            _ => Segment::Index(self.inner_ops.len() / 2),
+        };
+        let value = self.serializer.to_ast(value).map_err(|e| e.at(segment))?;
         self.inner_ops.push(ast::Map::Value {
-            value: Box::new(to_ast(value)?),
+            value: Box::new(value),
         });
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let Self { len, inner_ops } = self;
+        let Self {
+            serializer,
+            len,
+            inner_ops,
+        } = self;
 
-        Ok(Ast::Map {
+        serializer.node(XAst::Map {
             len,
             ops: inner_ops,
         })
@@ -432,69 +1214,102 @@ impl serde::ser::SerializeMap for SerializeMap {
 
 /// [serde::ser::SerializeStruct] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeStruct {
+pub struct SerializeStruct<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     name: &'static str,
     len: usize,
-    inner_ops: Vec<ast::Struct<Final>>,
+    inner_ops: Vec<ast::Struct<H::X>>,
 }
-impl SerializeStruct {
+impl<H> SerializeStruct<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeStruct].
-    pub fn new(name: &'static str, len: usize) -> Self {
+    pub fn new(serializer: Serializer<H>, name: &'static str, len: usize) -> Self {
         Self {
+            serializer,
             name,
             len,
             inner_ops: Vec::new(),
         }
     }
 }
-impl serde::ser::SerializeStruct for SerializeStruct {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeStruct for SerializeStruct<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        self.serializer.check_len(self.inner_ops.len() + 1)?;
+        let value = self
+            .serializer
+            .to_ast(value)
+            .and_then(|value| self.serializer.field(key, value))
+            .map_err(|e| e.at(Segment::Field(key)))?;
         self.inner_ops.push(ast::Struct::Field {
-            key,
-            value: Box::new(to_ast(value)?),
+            key: key.into(),
+            value: Box::new(value),
         });
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         let Self {
+            serializer,
             name,
             len,
             inner_ops,
         } = self;
 
-        Ok(Ast::Struct {
-            name,
+        serializer.node(XAst::Struct {
+            name: name.into(),
             len,
             ops: inner_ops,
         })
     }
 
     fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
-        self.inner_ops.push(ast::Struct::Skip { key });
+        self.serializer.check_len(self.inner_ops.len() + 1)?;
+        self.inner_ops.push(ast::Struct::Skip { key: key.into() });
         Ok(())
     }
 }
 
 /// [serde::ser::SerializeStructVariant] for [Serializer]
 #[derive(Debug, Clone, PartialEq)]
-pub struct SerializeStructVariant {
+pub struct SerializeStructVariant<H = ()>
+where
+    H: Hook,
+{
+    serializer: Serializer<H>,
     name: &'static str,
     variant_index: u32,
     variant: &'static str,
     len: usize,
-    inner_ops: Vec<ast::StructVariant<Final>>,
+    inner_ops: Vec<ast::StructVariant<H::X>>,
 }
-impl SerializeStructVariant {
+impl<H> SerializeStructVariant<H>
+where
+    H: Hook,
+{
     /// Create new [SerializeStructVariant].
-    pub fn new(name: &'static str, variant_index: u32, variant: &'static str, len: usize) -> Self {
+    pub fn new(
+        serializer: Serializer<H>,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Self {
         Self {
+            serializer,
             name,
             variant_index,
             variant,
@@ -503,23 +1318,33 @@ impl SerializeStructVariant {
         }
     }
 }
-impl serde::ser::SerializeStructVariant for SerializeStructVariant {
-    type Ok = Ast;
+impl<H> serde::ser::SerializeStructVariant for SerializeStructVariant<H>
+where
+    H: Hook,
+{
+    type Ok = XAst<H::X>;
     type Error = Error;
 
     fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + serde::Serialize,
     {
+        self.serializer.check_len(self.inner_ops.len() + 1)?;
+        let value = self
+            .serializer
+            .to_ast(value)
+            .and_then(|value| self.serializer.field(key, value))
+            .map_err(|e| e.at(Segment::Field(key)).at(Segment::Variant(self.variant)))?;
         self.inner_ops.push(ast::StructVariant::Field {
-            key,
-            value: Box::new(to_ast(value)?),
+            key: key.into(),
+            value: Box::new(value),
         });
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
         let Self {
+            serializer,
             name,
             variant_index,
             variant,
@@ -527,17 +1352,19 @@ impl serde::ser::SerializeStructVariant for SerializeStructVariant {
             inner_ops,
         } = self;
 
-        Ok(Ast::StructVariant {
-            name,
+        serializer.node(XAst::StructVariant {
+            name: name.into(),
             variant_index,
-            variant,
+            variant: variant.into(),
             len,
             ops: inner_ops,
         })
     }
 
     fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
-        self.inner_ops.push(ast::StructVariant::Skip { key });
+        self.serializer.check_len(self.inner_ops.len() + 1)?;
+        self.inner_ops
+            .push(ast::StructVariant::Skip { key: key.into() });
         Ok(())
     }
 }
@@ -0,0 +1,501 @@
+//! [CheckedSerializer] forwards to another [serde::Serializer] while checking the protocol.
+
+use serde::Serialize;
+
+use crate::validate::{Checker, ViolationKind};
+
+use super::ErrorKind;
+
+/// [CheckedSerializer] is a [serde::Serializer] forwarding every call to an inner serializer,
+/// checking that the calls follow the protocol with the rules of [validate](crate::validate).
+///
+/// A [ViolationKind] fails with an [ErrorKind::InvalidProtocol] custom error of the inner
+/// serializer, or panics with [CheckedSerializer::panicking], e.g. to find the culprit in a test.
+///
+/// ```
+/// # use serde::{ser::SerializeMap, Serialize};
+/// # use serde_ast::ser::CheckedSerializer;
+/// struct Dangling;
+/// impl Serialize for Dangling {
+///     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+///         let mut map = serializer.serialize_map(None)?;
+///         map.serialize_key("key")?;
+///         map.end()
+///     }
+/// }
+///
+/// let mut json = Vec::new();
+/// let error = Dangling
+///     .serialize(CheckedSerializer::new(&mut serde_json::Serializer::new(&mut json)))
+///     .unwrap_err();
+/// assert_eq!(error.to_string(), "invalid protocol: map key without a value");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedSerializer<S> {
+    inner: S,
+    panicking: bool,
+}
+impl<S> CheckedSerializer<S>
+where
+    S: serde::Serializer,
+{
+    /// Create a new [CheckedSerializer] forwarding to `inner`.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            panicking: false,
+        }
+    }
+
+    /// Panic on a violation instead of failing.
+    pub fn panicking(self, panicking: bool) -> Self {
+        Self { panicking, ..self }
+    }
+}
+
+/// Report the `violation` as an error, or panic.
+fn violation<E>(panicking: bool, violation: ViolationKind) -> E
+where
+    E: serde::ser::Error,
+{
+    let kind = ErrorKind::InvalidProtocol(violation.to_string());
+    if panicking {
+        panic!("{kind}");
+    }
+    E::custom(kind)
+}
+
+impl<S> serde::Serializer for CheckedSerializer<S>
+where
+    S: serde::Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeSeq = CheckedCompound<S::SerializeSeq>;
+    type SerializeTuple = CheckedCompound<S::SerializeTuple>;
+    type SerializeTupleStruct = CheckedCompound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = CheckedCompound<S::SerializeTupleVariant>;
+    type SerializeMap = CheckedCompound<S::SerializeMap>;
+    type SerializeStruct = CheckedCompound<S::SerializeStruct>;
+    type SerializeStructVariant = CheckedCompound<S::SerializeStructVariant>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_bool(v)
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_i8(v)
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_i16(v)
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_i32(v)
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_i64(v)
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_i128(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_u8(v)
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_u16(v)
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_u32(v)
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_u64(v)
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_u128(v)
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_f32(v)
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_f64(v)
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_char(v)
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_str(v)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_none()
+    }
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = Checked::new(value, self.panicking);
+        self.inner.serialize_some(&value)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_unit()
+    }
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = Checked::new(value, self.panicking);
+        self.inner.serialize_newtype_struct(name, &value)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = Checked::new(value, self.panicking);
+        self.inner
+            .serialize_newtype_variant(name, variant_index, variant, &value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self.inner.serialize_seq(len)?;
+        Ok(CheckedCompound::new(inner, len, panicking))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self.inner.serialize_tuple(len)?;
+        Ok(CheckedCompound::new(inner, Some(len), panicking))
+    }
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(CheckedCompound::new(inner, Some(len), panicking))
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, variant_index, variant, len)?;
+        Ok(CheckedCompound::new(inner, Some(len), panicking))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self.inner.serialize_map(len)?;
+        Ok(CheckedCompound::new(inner, len, panicking))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(CheckedCompound::new(inner, Some(len), panicking))
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let panicking = self.panicking;
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, variant_index, variant, len)?;
+        Ok(CheckedCompound::new(inner, Some(len), panicking))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Compound serializer of [CheckedSerializer], forwarding to the `inner` compound `C` while
+/// checking its operations with a [Checker].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedCompound<C> {
+    inner: C,
+    checker: Checker,
+    panicking: bool,
+}
+impl<C> CheckedCompound<C> {
+    fn new(inner: C, len: Option<usize>, panicking: bool) -> Self {
+        Self {
+            inner,
+            checker: Checker::new(len),
+            panicking,
+        }
+    }
+
+    /// Check an operation with `check`.
+    fn check<E>(
+        &mut self,
+        check: impl FnOnce(&mut Checker) -> Result<(), ViolationKind>,
+    ) -> Result<(), E>
+    where
+        E: serde::ser::Error,
+    {
+        check(&mut self.checker).map_err(|v| violation(self.panicking, v))
+    }
+
+    /// Check the end, then end the `inner` compound with `end`.
+    fn end<T, E>(self, end: impl FnOnce(C) -> Result<T, E>) -> Result<T, E>
+    where
+        E: serde::ser::Error,
+    {
+        self.checker
+            .end()
+            .map_err(|v| violation(self.panicking, v))?;
+        end(self.inner)
+    }
+
+    fn checked<'a, T>(&self, value: &'a T) -> Checked<'a, T>
+    where
+        T: ?Sized,
+    {
+        Checked::new(value, self.panicking)
+    }
+}
+
+impl<C> serde::ser::SerializeSeq for CheckedCompound<C>
+where
+    C: serde::ser::SerializeSeq,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::element)?;
+        let value = self.checked(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeTuple for CheckedCompound<C>
+where
+    C: serde::ser::SerializeTuple,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::element)?;
+        let value = self.checked(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeTupleStruct for CheckedCompound<C>
+where
+    C: serde::ser::SerializeTupleStruct,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::element)?;
+        let value = self.checked(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeTupleVariant for CheckedCompound<C>
+where
+    C: serde::ser::SerializeTupleVariant,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::element)?;
+        let value = self.checked(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeMap for CheckedCompound<C>
+where
+    C: serde::ser::SerializeMap,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::key)?;
+        let key = self.checked(key);
+        self.inner.serialize_key(&key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(Checker::value)?;
+        let value = self.checked(value);
+        self.inner.serialize_value(&value)
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
+    where
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
+    {
+        self.check(Checker::key)?;
+        self.check(Checker::value)?;
+        let (key, value) = (self.checked(key), self.checked(value));
+        self.inner.serialize_entry(&key, &value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeStruct for CheckedCompound<C>
+where
+    C: serde::ser::SerializeStruct,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(|checker| checker.field(&key.into()))?;
+        let value = self.checked(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.check(|checker| checker.skip(&key.into()))?;
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+impl<C> serde::ser::SerializeStructVariant for CheckedCompound<C>
+where
+    C: serde::ser::SerializeStructVariant,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.check(|checker| checker.field(&key.into()))?;
+        let value = self.checked(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.check(|checker| checker.skip(&key.into()))?;
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        CheckedCompound::end(self, C::end)
+    }
+}
+
+/// Nested value serialized with a [CheckedSerializer].
+struct Checked<'a, T: ?Sized> {
+    value: &'a T,
+    panicking: bool,
+}
+impl<'a, T> Checked<'a, T>
+where
+    T: ?Sized,
+{
+    fn new(value: &'a T, panicking: bool) -> Self {
+        Self { value, panicking }
+    }
+}
+impl<T> Serialize for Checked<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let serializer = CheckedSerializer::new(serializer).panicking(self.panicking);
+        self.value.serialize(serializer)
+    }
+}
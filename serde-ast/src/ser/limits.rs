@@ -0,0 +1,267 @@
+//! Limits of [Serializer] on the depth and size of the serialized value.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{Error, ErrorKind, Hook, Serializer};
+use crate::{
+    ast::{Struct, StructVariant, XAst},
+    validate,
+    visit::{self, Visit},
+};
+
+/// Configured limits, [None] is unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Limits {
+    pub(super) depth: Option<usize>,
+    pub(super) nodes: Option<usize>,
+    pub(super) bytes: Option<usize>,
+    pub(super) len: Option<usize>,
+}
+
+/// Usage of the [Limits] while serializing a value.
+///
+/// The counters are shared by the nested [Serializer]s of the value.
+#[derive(Debug, Default)]
+pub(super) struct Budget {
+    depth: usize,
+    nodes: Arc<AtomicUsize>,
+    bytes: Arc<AtomicUsize>,
+}
+impl PartialEq for Budget {
+    fn eq(&self, other: &Self) -> bool {
+        self.depth == other.depth
+            && self.nodes.load(Ordering::Relaxed) == other.nodes.load(Ordering::Relaxed)
+            && self.bytes.load(Ordering::Relaxed) == other.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Nodes of the input of a [Hook] already accounted by [Serializer], see [Serializer::account].
+///
+/// A [Hook] returning its input, or moving its children into a new node, keeps the boxes of the
+/// children, so they are recognized by address and not accounted again.
+pub(super) struct Spent<X> {
+    /// Bytes of the root, [None] if the root was not accounted.
+    root: Option<usize>,
+    children: HashSet<*const XAst<X>>,
+}
+impl<X> Spent<X> {
+    /// Nothing was accounted, e.g. for the output of [Hook::newtype_struct].
+    pub(super) fn none() -> Self {
+        Self {
+            root: None,
+            children: HashSet::new(),
+        }
+    }
+
+    /// `node` and its children were accounted.
+    pub(super) fn of(node: &XAst<X>) -> Self {
+        let mut spent = Self {
+            root: Some(match node {
+                XAst::Str(v) => v.len(),
+                XAst::Bytes(v) => v.len(),
+                _ => 0,
+            }),
+            children: HashSet::new(),
+        };
+        visit::visit_ast(&mut spent, node);
+        spent
+    }
+}
+impl<'ast, X: 'ast> Visit<'ast, X> for Spent<X> {
+    fn visit_ast(&mut self, node: &'ast XAst<X>) {
+        self.children.insert(node);
+    }
+}
+
+/// Accounting pass over the nodes of a [Hook] output which are not [Spent] yet.
+struct Account<'s, X> {
+    spent: &'s Spent<X>,
+    limits: Limits,
+    strict: bool,
+    depth: usize,
+    nodes: usize,
+    bytes: usize,
+    error: Option<Error>,
+}
+impl<'ast, X: 'ast> Visit<'ast, X> for Account<'_, X> {
+    fn visit_ast(&mut self, node: &'ast XAst<X>) {
+        if self.error.is_some() || self.spent.children.contains(&(node as *const _)) {
+            return;
+        }
+        self.error = match (self.limits.depth, self.limits.len, len(node)) {
+            (Some(max), _, _) if self.depth > max => Some(exceeded("depth", max)),
+            (_, Some(max), Some(len)) if len > max => Some(exceeded("len", max)),
+            _ if self.strict => validate::check(node)
+                .into_iter()
+                .next()
+                .map(|violation| Error::new(ErrorKind::InvalidProtocol(violation.to_string()))),
+            _ => None,
+        };
+        self.nodes += 1;
+        self.depth += 1;
+        visit::visit_ast(self, node);
+        self.depth -= 1;
+    }
+    fn visit_str(&mut self, v: &'ast str) {
+        self.bytes += v.len();
+    }
+    fn visit_bytes(&mut self, v: &'ast [u8]) {
+        self.bytes += v.len();
+    }
+}
+
+/// Length of a container checked against [Limits::len], the larger of its `len` and children.
+fn len<X>(node: &XAst<X>) -> Option<usize> {
+    match node {
+        XAst::Seq { len, ops } => Some(len.unwrap_or(0).max(ops.len())),
+        XAst::Tuple { len, ops } => Some((*len).max(ops.len())),
+        XAst::TupleStruct { len, ops, .. } => Some((*len).max(ops.len())),
+        XAst::TupleVariant { len, ops, .. } => Some((*len).max(ops.len())),
+        XAst::Map { len, ops } => Some(len.unwrap_or(0).max(ops.len() / 2)),
+        XAst::Struct { len, ops, .. } => Some(
+            (*len).max(
+                ops.iter()
+                    .filter(|op| matches!(op, Struct::Field { .. }))
+                    .count(),
+            ),
+        ),
+        XAst::StructVariant { len, ops, .. } => Some(
+            (*len).max(
+                ops.iter()
+                    .filter(|op| matches!(op, StructVariant::Field { .. }))
+                    .count(),
+            ),
+        ),
+        _ => None,
+    }
+}
+
+fn exceeded(limit: &'static str, max: usize) -> Error {
+    Error::new(ErrorKind::LimitExceeded { limit, max })
+}
+
+/// Add `n` to `counter`, failing past `max`.
+fn spend(
+    counter: &AtomicUsize,
+    n: usize,
+    limit: &'static str,
+    max: Option<usize>,
+) -> Result<(), Error> {
+    counter
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+            let used = used.saturating_add(n);
+            match max {
+                Some(max) if used > max => None,
+                _ => Some(used),
+            }
+        })
+        .map(|_| ())
+        .map_err(|_| exceeded(limit, max.unwrap_or_default()))
+}
+
+impl<H> Serializer<H>
+where
+    H: Hook,
+{
+    /// Limit the nesting depth of values, the root value is at depth 0.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.limits.depth = max_depth;
+        self
+    }
+
+    /// Limit the total number of nodes.
+    pub fn max_nodes(mut self, max_nodes: Option<usize>) -> Self {
+        self.limits.nodes = max_nodes;
+        self
+    }
+
+    /// Limit the total length of strings and bytes.
+    pub fn max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.limits.bytes = max_bytes;
+        self
+    }
+
+    /// Limit the length of each sequence, tuple, map and struct.
+    pub fn max_len(mut self, max_len: Option<usize>) -> Self {
+        self.limits.len = max_len;
+        self
+    }
+
+    /// [Serializer] of a value nested in this one, sharing its [Budget].
+    pub(super) fn nested(&self) -> Result<Self, Error> {
+        let depth = self.budget.depth + 1;
+        match self.limits.depth {
+            Some(max) if depth > max => Err(exceeded("depth", max)),
+            _ => Ok(Self {
+                hook: self.hook.clone(),
+                readability: self.readability,
+                strict: self.strict,
+                limits: self.limits,
+                budget: Budget {
+                    depth,
+                    nodes: self.budget.nodes.clone(),
+                    bytes: self.budget.bytes.clone(),
+                },
+            }),
+        }
+    }
+
+    /// Count a new node.
+    pub(super) fn spend_node(&self) -> Result<(), Error> {
+        spend(&self.budget.nodes, 1, "nodes", self.limits.nodes)
+    }
+
+    /// Count `len` bytes of a string or bytes.
+    pub(super) fn spend_bytes(&self, len: usize) -> Result<(), Error> {
+        spend(&self.budget.bytes, len, "bytes", self.limits.bytes)
+    }
+
+    /// Account the nodes of `node` returned by the [Hook] which are not [Spent] yet, `level`s
+    /// below this [Serializer].
+    ///
+    /// They count towards the nodes and bytes, their depth and len are checked, and in
+    /// [strict](Serializer::strict) mode they are validated.
+    pub(super) fn account(
+        &self,
+        node: &XAst<H::X>,
+        spent: &Spent<H::X>,
+        level: usize,
+    ) -> Result<(), Error> {
+        let mut account = Account {
+            spent,
+            limits: self.limits,
+            strict: self.strict,
+            depth: self.budget.depth + level,
+            nodes: 0,
+            bytes: 0,
+            error: None,
+        };
+        account.visit_ast(node);
+        if let Some(error) = account.error {
+            return Err(error);
+        }
+        let (nodes, bytes) = match spent.root {
+            Some(bytes) => (
+                account.nodes.saturating_sub(1),
+                account.bytes.saturating_sub(bytes),
+            ),
+            None => (account.nodes, account.bytes),
+        };
+        spend(&self.budget.nodes, nodes, "nodes", self.limits.nodes)?;
+        self.spend_bytes(bytes)
+    }
+
+    /// Check the `len` of a container.
+    pub(super) fn check_len(&self, len: usize) -> Result<(), Error> {
+        match self.limits.len {
+            Some(max) if len > max => Err(exceeded("len", max)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! [TeeSerializer] forwards to another [serde::Serializer] while recording an [Ast].
+
+use std::cell::Cell;
+
+use serde::{ser::Error as _, Serialize};
+
+use crate::{ast, Ast};
+
+use super::{
+    Readability, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+
+/// [serde::Serializer::Error] for [TeeSerializer]
+///
+/// Holds the `error` of the inner serializer and the `partial` [Ast] recorded until the failure.
+/// Compound nodes contain the values recorded before the failing one, followed by its partial
+/// [Ast]. The `partial` tree is `None` when the failure happened before anything was recorded,
+/// e.g. in [serde::ser::Error::custom].
+#[derive(Debug, thiserror::Error)]
+#[error("{error}")]
+pub struct TeeError<E> {
+    /// Error of the inner serializer.
+    pub error: E,
+    /// [Ast] recorded until the failure.
+    pub partial: Option<Ast>,
+}
+impl<E> serde::ser::Error for TeeError<E>
+where
+    E: serde::ser::Error,
+{
+    fn custom<T>(msg: T) -> Self
+    where
+        T: std::fmt::Display,
+    {
+        Self {
+            error: E::custom(msg),
+            partial: None,
+        }
+    }
+}
+
+/// [TeeSerializer] is a [serde::Serializer] forwarding every call to an inner serializer,
+/// recording the [Ast] of the serialized value at the same time.
+///
+/// ```
+/// # use serde::Serialize;
+/// # use serde_ast::{ser::TeeSerializer, to_ast};
+/// #[derive(Serialize)]
+/// struct Example {
+///     hello: String,
+/// }
+/// let example = Example { hello: "World".to_string() };
+///
+/// let mut json = Vec::new();
+/// let ((), ast) = example
+///     .serialize(TeeSerializer::new(&mut serde_json::Serializer::new(&mut json)))
+///     .unwrap();
+/// assert_eq!(json, br#"{"hello":"World"}"#);
+/// assert_eq!(ast, to_ast(&example).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeeSerializer<S> {
+    inner: S,
+}
+impl<S> TeeSerializer<S>
+where
+    S: serde::Serializer,
+{
+    /// Create a new [TeeSerializer] forwarding to `inner`.
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+/// Wrap the `result` of serializing a leaf `ast`.
+fn leaf<T, E>(result: Result<T, E>, ast: Ast) -> Result<(T, Ast), TeeError<E>> {
+    match result {
+        Ok(ok) => Ok((ok, ast)),
+        Err(error) => Err(TeeError {
+            error,
+            partial: Some(ast),
+        }),
+    }
+}
+
+/// Wrap the `result` of serializing a single nested value, building the node with `node`.
+fn wrap<T, E>(
+    result: Result<T, E>,
+    capture: Capture<'_, impl ?Sized + Serialize>,
+    node: impl FnOnce(Box<Ast>) -> Ast,
+) -> Result<(T, Ast), TeeError<E>>
+where
+    E: serde::ser::Error,
+{
+    match result {
+        Ok(ok) => Ok((ok, node(Box::new(capture.into_ast()?)))),
+        Err(error) => Err(TeeError {
+            error,
+            partial: capture.ast.take().map(|ast| node(Box::new(ast))),
+        }),
+    }
+}
+
+/// Wrap the `result` of beginning a compound, recording into `ast`.
+fn begin<C, A, E>(
+    result: Result<C, E>,
+    ast: A,
+    readability: Readability,
+) -> Result<TeeCompound<C, A>, TeeError<E>>
+where
+    A: Partial,
+{
+    match result {
+        Ok(inner) => Ok(TeeCompound {
+            inner,
+            ast,
+            readability,
+        }),
+        Err(error) => Err(TeeError {
+            error,
+            partial: Some(ast.partial()),
+        }),
+    }
+}
+
+impl<S> serde::Serializer for TeeSerializer<S>
+where
+    S: serde::Serializer,
+{
+    type Ok = (S::Ok, Ast);
+    type Error = TeeError<S::Error>;
+
+    type SerializeSeq = TeeCompound<S::SerializeSeq, SerializeSeq>;
+    type SerializeTuple = TeeCompound<S::SerializeTuple, SerializeTuple>;
+    type SerializeTupleStruct = TeeCompound<S::SerializeTupleStruct, SerializeTupleStruct>;
+    type SerializeTupleVariant = TeeCompound<S::SerializeTupleVariant, SerializeTupleVariant>;
+    type SerializeMap = TeeCompound<S::SerializeMap, SerializeMap>;
+    type SerializeStruct = TeeCompound<S::SerializeStruct, SerializeStruct>;
+    type SerializeStructVariant = TeeCompound<S::SerializeStructVariant, SerializeStructVariant>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_bool(v), Ast::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_i8(v), Ast::I8(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_i16(v), Ast::I16(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_i32(v), Ast::I32(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_i64(v), Ast::I64(v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_i128(v), Ast::I128(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_u8(v), Ast::U8(v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_u16(v), Ast::U16(v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_u32(v), Ast::U32(v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_u64(v), Ast::U64(v))
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_u128(v), Ast::U128(v))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_f32(v), Ast::F32(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_f64(v), Ast::F64(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_char(v), Ast::Char(v))
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_str(v), Ast::Str(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_bytes(v), Ast::Bytes(v.to_owned()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_none(), Ast::None)
+    }
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let capture = Capture::new(value, readability(&self.inner));
+        wrap(self.inner.serialize_some(&capture), capture, Ast::Some)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        leaf(self.inner.serialize_unit(), Ast::Unit)
+    }
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        leaf(
+            self.inner.serialize_unit_struct(name),
+            Ast::UnitStruct(name.into()),
+        )
+    }
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        leaf(
+            self.inner
+                .serialize_unit_variant(name, variant_index, variant),
+            Ast::UnitVariant {
+                name: name.into(),
+                variant_index,
+                variant: variant.into(),
+            },
+        )
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let capture = Capture::new(value, readability(&self.inner));
+        wrap(
+            self.inner.serialize_newtype_struct(name, &capture),
+            capture,
+            |value| Ast::NewtypeStruct {
+                name: name.into(),
+                value,
+            },
+        )
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let capture = Capture::new(value, readability(&self.inner));
+        wrap(
+            self.inner
+                .serialize_newtype_variant(name, variant_index, variant, &capture),
+            capture,
+            |value| Ast::NewtypeVariant {
+                name: name.into(),
+                variant_index,
+                variant: variant.into(),
+                value,
+            },
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner.serialize_seq(len),
+            SerializeSeq::new(Serializer::new(readability), len),
+            readability,
+        )
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner.serialize_tuple(len),
+            SerializeTuple::new(Serializer::new(readability), len),
+            readability,
+        )
+    }
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner.serialize_tuple_struct(name, len),
+            SerializeTupleStruct::new(Serializer::new(readability), name, len),
+            readability,
+        )
+    }
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner
+                .serialize_tuple_variant(name, variant_index, variant, len),
+            SerializeTupleVariant::new(
+                Serializer::new(readability),
+                name,
+                variant_index,
+                variant,
+                len,
+            ),
+            readability,
+        )
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner.serialize_map(len),
+            SerializeMap::new(Serializer::new(readability), len),
+            readability,
+        )
+    }
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner.serialize_struct(name, len),
+            SerializeStruct::new(Serializer::new(readability), name, len),
+            readability,
+        )
+    }
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let readability = readability(&self.inner);
+        begin(
+            self.inner
+                .serialize_struct_variant(name, variant_index, variant, len),
+            SerializeStructVariant::new(
+                Serializer::new(readability),
+                name,
+                variant_index,
+                variant,
+                len,
+            ),
+            readability,
+        )
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Compound serializer of [TeeSerializer], forwarding to the `inner` compound `C` while
+/// recording into the [Serializer] compound `A`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeeCompound<C, A> {
+    inner: C,
+    ast: A,
+    readability: Readability,
+}
+impl<C, A> TeeCompound<C, A>
+where
+    A: Partial,
+{
+    /// Forward a nested `value` with `forward`, then `record` its [Ast].
+    fn value<T, E>(
+        &mut self,
+        value: &T,
+        forward: impl FnOnce(&mut C, &Capture<'_, T>) -> Result<(), E>,
+        record: impl FnOnce(&mut A, Box<Ast>),
+    ) -> Result<(), TeeError<E>>
+    where
+        T: ?Sized + Serialize,
+        E: serde::ser::Error,
+    {
+        let capture = Capture::new(value, self.readability);
+        match forward(&mut self.inner, &capture) {
+            Ok(()) => {
+                record(&mut self.ast, Box::new(capture.into_ast()?));
+                Ok(())
+            }
+            Err(error) => {
+                if let Some(ast) = capture.ast.take() {
+                    record(&mut self.ast, Box::new(ast));
+                }
+                Err(self.error(error))
+            }
+        }
+    }
+
+    /// Forward a call without a value.
+    fn forward<E>(&mut self, result: Result<(), E>) -> Result<(), TeeError<E>> {
+        result.map_err(|error| self.error(error))
+    }
+
+    fn finish<T, E>(self, end: impl FnOnce(C) -> Result<T, E>) -> Result<(T, Ast), TeeError<E>> {
+        let Self { inner, ast, .. } = self;
+        match end(inner) {
+            Ok(ok) => Ok((ok, ast.finish())),
+            Err(error) => Err(TeeError {
+                error,
+                partial: Some(ast.finish()),
+            }),
+        }
+    }
+
+    fn error<E>(&self, error: E) -> TeeError<E> {
+        TeeError {
+            error,
+            partial: Some(self.ast.partial()),
+        }
+    }
+}
+
+impl<C> serde::ser::SerializeSeq for TeeCompound<C, SerializeSeq>
+where
+    C: serde::ser::SerializeSeq,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_element(value),
+            |ast, value| ast.inner_ops.push(ast::Seq::Element { value }),
+        )
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeTuple for TeeCompound<C, SerializeTuple>
+where
+    C: serde::ser::SerializeTuple,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_element(value),
+            |ast, value| ast.inner_ops.push(ast::Tuple::Element { value }),
+        )
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeTupleStruct for TeeCompound<C, SerializeTupleStruct>
+where
+    C: serde::ser::SerializeTupleStruct,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_field(value),
+            |ast, value| ast.inner_ops.push(ast::TupleStruct::Field { value }),
+        )
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeTupleVariant for TeeCompound<C, SerializeTupleVariant>
+where
+    C: serde::ser::SerializeTupleVariant,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_field(value),
+            |ast, value| ast.inner_ops.push(ast::TupleVariant::Field { value }),
+        )
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeMap for TeeCompound<C, SerializeMap>
+where
+    C: serde::ser::SerializeMap,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            key,
+            |inner, key| inner.serialize_key(key),
+            |ast, key| ast.inner_ops.push(ast::Map::Key { key }),
+        )
+    }
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_value(value),
+            |ast, value| ast.inner_ops.push(ast::Map::Value { value }),
+        )
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeStruct for TeeCompound<C, SerializeStruct>
+where
+    C: serde::ser::SerializeStruct,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_field(key, value),
+            |ast, value| {
+                ast.inner_ops.push(ast::Struct::Field {
+                    key: key.into(),
+                    value,
+                })
+            },
+        )
+    }
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        let result = self.inner.skip_field(key);
+        self.ast
+            .inner_ops
+            .push(ast::Struct::Skip { key: key.into() });
+        self.forward(result)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+impl<C> serde::ser::SerializeStructVariant for TeeCompound<C, SerializeStructVariant>
+where
+    C: serde::ser::SerializeStructVariant,
+{
+    type Ok = (C::Ok, Ast);
+    type Error = TeeError<C::Error>;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.value(
+            value,
+            |inner, value| inner.serialize_field(key, value),
+            |ast, value| {
+                ast.inner_ops.push(ast::StructVariant::Field {
+                    key: key.into(),
+                    value,
+                })
+            },
+        )
+    }
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        let result = self.inner.skip_field(key);
+        self.ast
+            .inner_ops
+            .push(ast::StructVariant::Skip { key: key.into() });
+        self.forward(result)
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish(|inner| inner.end())
+    }
+}
+
+/// [Readability] of the `inner` serializer, to record the same representation it emits.
+fn readability(inner: &impl serde::Serializer) -> Readability {
+    match inner.is_human_readable() {
+        true => Readability::HumanReadable,
+        false => Readability::Compact,
+    }
+}
+
+/// [Serialize] a nested value through a [TeeSerializer] wrapping whichever serializer the inner
+/// compound uses, keeping the recorded [Ast].
+struct Capture<'a, T>
+where
+    T: ?Sized,
+{
+    value: &'a T,
+    readability: Readability,
+    ast: Cell<Option<Ast>>,
+}
+impl<'a, T> Capture<'a, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn new(value: &'a T, readability: Readability) -> Self {
+        Self {
+            value,
+            readability,
+            ast: Cell::new(None),
+        }
+    }
+
+    /// Recorded [Ast], serializing the value again with the same [Readability] if the inner
+    /// serializer didn't.
+    fn into_ast<E>(self) -> Result<Ast, TeeError<E>>
+    where
+        E: serde::ser::Error,
+    {
+        match self.ast.into_inner() {
+            Some(ast) => Ok(ast),
+            None => self
+                .value
+                .serialize(Serializer::new(self.readability))
+                .map_err(TeeError::custom),
+        }
+    }
+}
+impl<T> Serialize for Capture<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.value.serialize(TeeSerializer::new(serializer)) {
+            Ok((ok, ast)) => {
+                self.ast.set(Some(ast));
+                Ok(ok)
+            }
+            Err(TeeError { error, partial }) => {
+                self.ast.set(partial);
+                Err(error)
+            }
+        }
+    }
+}
+
+/// [Serializer] compound that can produce the [Ast] recorded so far.
+pub trait Partial: Clone {
+    /// Build the [Ast] of the recorded values.
+    fn finish(self) -> Ast;
+
+    /// [Ast] of the values recorded so far.
+    fn partial(&self) -> Ast {
+        self.clone().finish()
+    }
+}
+
+macro_rules! partial {
+    ($($compound:ident),*) => {$(
+        impl Partial for $compound {
+            fn finish(self) -> Ast {
+                serde::ser::$compound::end(self).expect("Serializer without Hook can't fail")
+            }
+        }
+    )*};
+}
+partial!(
+    SerializeSeq,
+    SerializeTuple,
+    SerializeTupleStruct,
+    SerializeTupleVariant,
+    SerializeMap,
+    SerializeStruct,
+    SerializeStructVariant
+);
@@ -0,0 +1,1031 @@
+//! Lossless text syntax for [Ast].
+//!
+//! [to_string] prints an [Ast] that [from_str] parses back into an equal [Ast],
+//! keeping names, variant indices, integer widths, skipped fields and `len` values.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{text, to_ast};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     hello: String,
+//!     size: Option<u8>,
+//! }
+//! let ast = to_ast(&Example { hello: "World".to_string(), size: Some(3) }).unwrap();
+//!
+//! let text = text::to_string(&ast);
+//! assert_eq!(text, r#"Example { hello: "World", size: Some(u8 3) }"#);
+//! assert_eq!(text::from_str(&text).unwrap(), ast);
+//! ```
+//!
+//! | node | syntax |
+//! |------|--------|
+//! | [XAst::Bool] | `true`, `false` |
+//! | integers and floats | type followed by the value, e.g. `i8 -5`, `u64 7`, `f32 1.5`, `f64 inf` |
+//! | NaN floats | type followed by the bits, e.g. `f32 0x7fc00000` |
+//! | [XAst::Char] | `'c'` |
+//! | [XAst::Str] | `"str"` |
+//! | [XAst::Bytes] | `b"bytes\x00"` |
+//! | [XAst::None], [XAst::Some] | `None`, `Some(value)` |
+//! | [XAst::Unit] | `()` |
+//! | [XAst::UnitStruct] | `Name` |
+//! | [XAst::UnitVariant] | `Name::Variant@1` |
+//! | [XAst::NewtypeStruct] | `Name(value)` |
+//! | [XAst::NewtypeVariant] | `Name::Variant@1(value)` |
+//! | [XAst::Seq] | `[a, b]` |
+//! | [XAst::Tuple] | `(,)`, `(a,)`, `(a, b)` |
+//! | [XAst::TupleStruct] | `Name()`, `Name(a,)`, `Name(a, b)` |
+//! | [XAst::TupleVariant] | `Name::Variant@1()`, `Name::Variant@1(a,)`, `Name::Variant@1(a, b)` |
+//! | [XAst::Map] | `{ key => value }`, a missing key or value is written as `_` |
+//! | [XAst::Struct] | `Name { key: value, skip key }` |
+//! | [XAst::StructVariant] | `Name::Variant@1 { key: value, skip key }` |
+//! | [XAst::Readability] | `readable(value) compact(value)` |
+//!
+//! Strings, chars and bytes use Rust escapes. Names and keys which are not identifiers, or are
+//! reserved words of this syntax, are quoted in backticks, e.g. `` `my-name` ``.
+//! The variant index defaults to `0` if omitted.
+//!
+//! A container `len` is written as a `; len 2` (or `; len none`) suffix before the closing
+//! delimiter, e.g. `[u8 1; len none]`. It is only printed when it differs from the default,
+//! which is the number of elements, entries or fields.
+
+use std::{fmt::Write, str::FromStr};
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    Ast,
+};
+
+/// Words which are quoted in backticks when used as a name or key.
+const RESERVED: &[&str] = &[
+    "true", "false", "None", "Some", "skip", "len", "none", "_", "i8", "i16", "i32", "i64", "i128",
+    "u8", "u16", "u32", "u64", "u128", "f32", "f64", "readable", "compact",
+];
+
+/// Print `ast` in the text syntax, see the [module documentation](self).
+pub fn to_string(ast: &Ast) -> String {
+    let mut out = String::new();
+    write_node(&mut out, ast);
+    out
+}
+
+/// Parse an [Ast] from the text syntax, see the [module documentation](self).
+pub fn from_str(s: &str) -> Result<Ast, Error> {
+    let mut parser = Parser { src: s, pos: 0 };
+    let ast = parser.value()?;
+    parser.skip_ws();
+    match parser.peek() {
+        None => Ok(ast),
+        Some(found) => Err(parser.error(ErrorKind::Unexpected {
+            expected: "end of input",
+            found,
+        })),
+    }
+}
+
+impl FromStr for Ast {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_str(s)
+    }
+}
+
+/// Error parsing the text syntax.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at {line}:{column}")]
+pub struct Error {
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error in characters, starting at 1.
+    pub column: usize,
+    /// Reason parsing failed.
+    pub kind: ErrorKind,
+}
+
+/// Reason parsing the text syntax failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ErrorKind {
+    #[error("unexpected end of input, expected {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("unexpected `{found}`, expected {expected}")]
+    Unexpected { expected: &'static str, found: char },
+    #[error("invalid {ty} literal `{literal}`")]
+    InvalidNumber { ty: &'static str, literal: String },
+    #[error("invalid escape `{0}`")]
+    InvalidEscape(String),
+    #[error("map entry without key and value")]
+    EmptyEntry,
+}
+
+fn write_node(out: &mut String, ast: &Ast) {
+    match ast {
+        XAst::Bool(v) => write!(out, "{v}"),
+        XAst::I8(v) => write!(out, "i8 {v}"),
+        XAst::I16(v) => write!(out, "i16 {v}"),
+        XAst::I32(v) => write!(out, "i32 {v}"),
+        XAst::I64(v) => write!(out, "i64 {v}"),
+        XAst::I128(v) => write!(out, "i128 {v}"),
+        XAst::U8(v) => write!(out, "u8 {v}"),
+        XAst::U16(v) => write!(out, "u16 {v}"),
+        XAst::U32(v) => write!(out, "u32 {v}"),
+        XAst::U64(v) => write!(out, "u64 {v}"),
+        XAst::U128(v) => write!(out, "u128 {v}"),
+        XAst::F32(v) if v.is_nan() => write!(out, "f32 {:#x}", v.to_bits()),
+        XAst::F32(v) => write!(out, "f32 {v:?}"),
+        XAst::F64(v) if v.is_nan() => write!(out, "f64 {:#x}", v.to_bits()),
+        XAst::F64(v) => write!(out, "f64 {v:?}"),
+        XAst::Char(v) => write!(out, "{v:?}"),
+        XAst::Str(v) => write!(out, "{v:?}"),
+        XAst::Bytes(v) => write!(out, "b\"{}\"", v.escape_ascii()),
+        XAst::None => write!(out, "None"),
+        XAst::Some(v) => {
+            out.push_str("Some(");
+            write_node(out, v);
+            write!(out, ")")
+        }
+        XAst::Unit => write!(out, "()"),
+        XAst::UnitStruct(name) => {
+            write_name(out, name);
+            Ok(())
+        }
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => {
+            write_variant(out, name, *variant_index, variant);
+            Ok(())
+        }
+        XAst::NewtypeStruct { name, value } => {
+            write_name(out, name);
+            out.push('(');
+            write_node(out, value);
+            write!(out, ")")
+        }
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => {
+            write_variant(out, name, *variant_index, variant);
+            out.push('(');
+            write_node(out, value);
+            write!(out, ")")
+        }
+        XAst::Seq { len, ops } => {
+            out.push('[');
+            write_list(
+                out,
+                ops.iter().map(|op| match op {
+                    Seq::Element { value } => value.as_ref(),
+                }),
+                write_node,
+            );
+            write_len(out, *len, Some(ops.len()));
+            write!(out, "]")
+        }
+        XAst::Tuple { len, ops } => {
+            write_tuple(
+                out,
+                ops.iter().map(|op| match op {
+                    Tuple::Element { value } => value.as_ref(),
+                }),
+                *len,
+                true,
+            );
+            Ok(())
+        }
+        XAst::TupleStruct { name, len, ops } => {
+            write_name(out, name);
+            write_tuple(
+                out,
+                ops.iter().map(|op| match op {
+                    TupleStruct::Field { value } => value.as_ref(),
+                }),
+                *len,
+                false,
+            );
+            Ok(())
+        }
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            write_variant(out, name, *variant_index, variant);
+            write_tuple(
+                out,
+                ops.iter().map(|op| match op {
+                    TupleVariant::Field { value } => value.as_ref(),
+                }),
+                *len,
+                false,
+            );
+            Ok(())
+        }
+        XAst::Map { len, ops } => {
+            let mut entries = Vec::new();
+            let mut iter = ops.iter().peekable();
+            while let Some(op) = iter.next() {
+                entries.push(match op {
+                    Map::Key { key } => match iter.next_if(|op| matches!(op, Map::Value { .. })) {
+                        Some(Map::Value { value }) => (Some(key.as_ref()), Some(value.as_ref())),
+                        _ => (Some(key.as_ref()), None),
+                    },
+                    Map::Value { value } => (None, Some(value.as_ref())),
+                });
+            }
+            let keys = ops
+                .iter()
+                .filter(|op| matches!(op, Map::Key { .. }))
+                .count();
+            write_braced(out, entries.into_iter(), *len, Some(keys), |out, entry| {
+                let write_hole = |out: &mut String, node: Option<&Ast>| match node {
+                    Some(node) => write_node(out, node),
+                    None => out.push('_'),
+                };
+                write_hole(out, entry.0);
+                out.push_str(" => ");
+                write_hole(out, entry.1);
+            });
+            Ok(())
+        }
+        XAst::Struct { name, len, ops } => {
+            write_name(out, name);
+            out.push(' ');
+            write_fields(
+                out,
+                ops.iter().map(|op| match op {
+                    Struct::Field { key, value } => (key.as_ref(), Some(value.as_ref())),
+                    Struct::Skip { key } => (key.as_ref(), None),
+                }),
+                *len,
+            );
+            Ok(())
+        }
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => {
+            write_variant(out, name, *variant_index, variant);
+            out.push(' ');
+            write_fields(
+                out,
+                ops.iter().map(|op| match op {
+                    StructVariant::Field { key, value } => (key.as_ref(), Some(value.as_ref())),
+                    StructVariant::Skip { key } => (key.as_ref(), None),
+                }),
+                *len,
+            );
+            Ok(())
+        }
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => {
+            out.push_str("readable(");
+            write_node(out, human_readable);
+            out.push_str(") compact(");
+            write_node(out, compact);
+            out.push(')');
+            Ok(())
+        }
+        XAst::X(x) => match *x {},
+    }
+    .expect("write to String");
+}
+
+fn write_name(out: &mut String, name: &str) {
+    let mut chars = name.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED.contains(&name);
+    if identifier {
+        out.push_str(name);
+    } else {
+        out.push('`');
+        for c in name.chars() {
+            if c == '`' || c == '\\' {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('`');
+    }
+}
+
+fn write_variant(out: &mut String, name: &str, variant_index: u32, variant: &str) {
+    write_name(out, name);
+    out.push_str("::");
+    write_name(out, variant);
+    write!(out, "@{variant_index}").expect("write to String");
+}
+
+fn write_len(out: &mut String, len: Option<usize>, default: Option<usize>) {
+    if len != default {
+        match len {
+            Some(len) => write!(out, "; len {len}").expect("write to String"),
+            None => out.push_str("; len none"),
+        }
+    }
+}
+
+fn write_list<T>(
+    out: &mut String,
+    items: impl Iterator<Item = T>,
+    mut write_item: impl FnMut(&mut String, T),
+) {
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_item(out, item);
+    }
+}
+
+fn write_tuple<'a>(
+    out: &mut String,
+    values: impl ExactSizeIterator<Item = &'a Ast>,
+    len: usize,
+    bare: bool,
+) {
+    let count = values.len();
+    out.push('(');
+    write_list(out, values, write_node);
+    // a trailing comma tells single element tuples apart from newtypes, and the empty tuple from unit
+    if count == 1 || (bare && count == 0) {
+        out.push(',');
+    }
+    write_len(out, Some(len), Some(count));
+    out.push(')');
+}
+
+fn write_braced<T>(
+    out: &mut String,
+    items: impl ExactSizeIterator<Item = T>,
+    len: Option<usize>,
+    default: Option<usize>,
+    write_item: impl FnMut(&mut String, T),
+) {
+    let empty = items.len() == 0 && len == default;
+    out.push('{');
+    if !empty {
+        out.push(' ');
+    }
+    write_list(out, items, write_item);
+    write_len(out, len, default);
+    if !empty {
+        out.push(' ');
+    }
+    out.push('}');
+}
+
+fn write_fields<'a>(
+    out: &mut String,
+    fields: impl ExactSizeIterator<Item = (&'a str, Option<&'a Ast>)>,
+    len: usize,
+) {
+    let fields: Vec<_> = fields.collect();
+    let count = fields.iter().filter(|(_, value)| value.is_some()).count();
+    write_braced(
+        out,
+        fields.into_iter(),
+        Some(len),
+        Some(count),
+        |out, (key, value)| match value {
+            Some(value) => {
+                write_name(out, key);
+                out.push_str(": ");
+                write_node(out, value);
+            }
+            None => {
+                out.push_str("skip ");
+                write_name(out, key);
+            }
+        },
+    );
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+impl<'s> Parser<'s> {
+    fn error(&self, kind: ErrorKind) -> Error {
+        let before = &self.src[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count()
+            + 1;
+        Error { line, column, kind }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> Error {
+        match self.peek() {
+            Some(found) => self.error(ErrorKind::Unexpected { expected, found }),
+            None => self.error(ErrorKind::UnexpectedEnd(expected)),
+        }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip whitespace and consume `token` if it follows.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        match self.rest().starts_with(token) {
+            true => {
+                self.pos += token.len();
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str, expected: &'static str) -> Result<(), Error> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// Skip whitespace and consume an identifier if it follows.
+    fn ident(&mut self) -> Option<&'s str> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| !(c.is_ascii_alphabetic() || c == '_' || (i > 0 && c.is_ascii_digit())))
+            .map_or(rest.len(), |(i, _)| i);
+        match len {
+            0 => None,
+            len => {
+                self.pos += len;
+                Some(&rest[..len])
+            }
+        }
+    }
+
+    /// Consume the identifier `word` if it follows.
+    fn keyword(&mut self, word: &str) -> bool {
+        let pos = self.pos;
+        match self.ident() {
+            Some(ident) if ident == word => true,
+            _ => {
+                self.pos = pos;
+                false
+            }
+        }
+    }
+
+    /// Parse a name, returning if it was quoted.
+    fn name(&mut self) -> Result<(String, bool), Error> {
+        if let Some(ident) = self.ident() {
+            return Ok((ident.to_owned(), false));
+        }
+        self.expect("`", "a name")?;
+        let mut name = String::new();
+        loop {
+            match self.bump() {
+                Some('`') => return Ok((name, true)),
+                Some('\\') => match self.bump() {
+                    Some(c @ ('`' | '\\')) => name.push(c),
+                    Some(c) => return Err(self.error(ErrorKind::InvalidEscape(format!("\\{c}")))),
+                    None => return Err(self.error(ErrorKind::UnexpectedEnd("a closing backtick"))),
+                },
+                Some(c) => name.push(c),
+                None => return Err(self.error(ErrorKind::UnexpectedEnd("a closing backtick"))),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Ast, Error> {
+        self.skip_ws();
+        let Some(c) = self.peek() else {
+            return Err(self.unexpected("a value"));
+        };
+        match c {
+            '"' => Ok(XAst::Str(self.quoted('"')?)),
+            '\'' => {
+                let s = self.quoted('\'')?;
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(XAst::Char(c)),
+                    _ => Err(self.error(ErrorKind::InvalidEscape(format!("'{s}'")))),
+                }
+            }
+            'b' if self.rest().starts_with("b\"") => {
+                self.pos += 1;
+                Ok(XAst::Bytes(self.bytes()?))
+            }
+            '[' => self.seq(),
+            '{' => self.map(),
+            '(' => {
+                self.pos += 1;
+                let (values, comma, len) = self.tuple()?;
+                if let (1, false, None) = (values.len(), comma, len) {
+                    return Err(self.unexpected("`,` in a single element tuple"));
+                }
+                self.bump();
+                match (values.len(), comma, len) {
+                    (0, false, None) => Ok(XAst::Unit),
+                    (count, _, len) => Ok(XAst::Tuple {
+                        len: len.unwrap_or(count),
+                        ops: values
+                            .into_iter()
+                            .map(|value| Tuple::Element {
+                                value: Box::new(value),
+                            })
+                            .collect(),
+                    }),
+                }
+            }
+            _ => {
+                let start = self.pos;
+                let (name, quoted) = self.name()?;
+                if quoted {
+                    return self.named(name);
+                }
+                match name.as_str() {
+                    "true" => Ok(XAst::Bool(true)),
+                    "false" => Ok(XAst::Bool(false)),
+                    "None" => Ok(XAst::None),
+                    "Some" => {
+                        self.expect("(", "`(`")?;
+                        let value = self.value()?;
+                        self.expect(")", "`)`")?;
+                        Ok(XAst::Some(Box::new(value)))
+                    }
+                    "readable" => {
+                        self.expect("(", "`(`")?;
+                        let human_readable = self.value()?;
+                        self.expect(")", "`)`")?;
+                        if !self.keyword("compact") {
+                            return Err(self.unexpected("`compact`"));
+                        }
+                        self.expect("(", "`(`")?;
+                        let compact = self.value()?;
+                        self.expect(")", "`)`")?;
+                        Ok(XAst::Readability {
+                            human_readable: Box::new(human_readable),
+                            compact: Box::new(compact),
+                        })
+                    }
+                    "i8" => self.number("i8", XAst::I8),
+                    "i16" => self.number("i16", XAst::I16),
+                    "i32" => self.number("i32", XAst::I32),
+                    "i64" => self.number("i64", XAst::I64),
+                    "i128" => self.number("i128", XAst::I128),
+                    "u8" => self.number("u8", XAst::U8),
+                    "u16" => self.number("u16", XAst::U16),
+                    "u32" => self.number("u32", XAst::U32),
+                    "u64" => self.number("u64", XAst::U64),
+                    "u128" => self.number("u128", XAst::U128),
+                    "f32" => self.float(
+                        "f32",
+                        |hex| u32::from_str_radix(hex, 16).ok().map(f32::from_bits),
+                        XAst::F32,
+                    ),
+                    "f64" => self.float(
+                        "f64",
+                        |hex| u64::from_str_radix(hex, 16).ok().map(f64::from_bits),
+                        XAst::F64,
+                    ),
+                    word if RESERVED.contains(&word) => {
+                        self.pos = start;
+                        Err(self.unexpected("a value"))
+                    }
+                    _ => self.named(name),
+                }
+            }
+        }
+    }
+
+    /// Parse the rest of a node starting with a name.
+    fn named(&mut self, name: String) -> Result<Ast, Error> {
+        let name = Name::Owned(name);
+        if self.eat("::") {
+            let variant = Name::Owned(self.name()?.0);
+            let variant_index = match self.eat("@") {
+                true => self.integer("variant index")?,
+                false => 0,
+            };
+            if self.eat("(") {
+                let (values, comma, len) = self.tuple()?;
+                self.bump();
+                return match (values.len(), comma, len) {
+                    (1, false, None) => Ok(XAst::NewtypeVariant {
+                        name,
+                        variant_index,
+                        variant,
+                        value: Box::new(values.into_iter().next().expect("one value")),
+                    }),
+                    (count, _, len) => Ok(XAst::TupleVariant {
+                        name,
+                        variant_index,
+                        variant,
+                        len: len.unwrap_or(count),
+                        ops: values
+                            .into_iter()
+                            .map(|value| TupleVariant::Field {
+                                value: Box::new(value),
+                            })
+                            .collect(),
+                    }),
+                };
+            }
+            if self.eat("{") {
+                let (len, fields) = self.fields()?;
+                return Ok(XAst::StructVariant {
+                    name,
+                    variant_index,
+                    variant,
+                    len,
+                    ops: fields
+                        .into_iter()
+                        .map(|(key, value)| match value {
+                            Some(value) => StructVariant::Field {
+                                key,
+                                value: Box::new(value),
+                            },
+                            None => StructVariant::Skip { key },
+                        })
+                        .collect(),
+                });
+            }
+            return Ok(XAst::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            });
+        }
+        if self.eat("(") {
+            let (values, comma, len) = self.tuple()?;
+            self.bump();
+            return match (values.len(), comma, len) {
+                (1, false, None) => Ok(XAst::NewtypeStruct {
+                    name,
+                    value: Box::new(values.into_iter().next().expect("one value")),
+                }),
+                (count, _, len) => Ok(XAst::TupleStruct {
+                    name,
+                    len: len.unwrap_or(count),
+                    ops: values
+                        .into_iter()
+                        .map(|value| TupleStruct::Field {
+                            value: Box::new(value),
+                        })
+                        .collect(),
+                }),
+            };
+        }
+        if self.eat("{") {
+            let (len, fields) = self.fields()?;
+            return Ok(XAst::Struct {
+                name,
+                len,
+                ops: fields
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        Some(value) => Struct::Field {
+                            key,
+                            value: Box::new(value),
+                        },
+                        None => Struct::Skip { key },
+                    })
+                    .collect(),
+            });
+        }
+        Ok(XAst::UnitStruct(name))
+    }
+
+    /// Parse a `; len` suffix, [None] if absent.
+    fn len(&mut self) -> Result<Option<Option<usize>>, Error> {
+        if !self.eat(";") {
+            return Ok(None);
+        }
+        if !self.keyword("len") {
+            return Err(self.unexpected("`len`"));
+        }
+        match self.keyword("none") {
+            true => Ok(Some(None)),
+            false => Ok(Some(Some(self.integer("len")?))),
+        }
+    }
+
+    /// Parse a `; len` suffix which can't be `none`.
+    fn fixed_len(&mut self) -> Result<Option<usize>, Error> {
+        let pos = self.pos;
+        match self.len()? {
+            Some(None) => {
+                self.pos = pos;
+                Err(self.unexpected("a len"))
+            }
+            len => Ok(len.flatten()),
+        }
+    }
+
+    /// Parse the rest of a tuple after `(` up to `)`, returning the values, if there was a trailing comma, and the len.
+    fn tuple(&mut self) -> Result<(Vec<Ast>, bool, Option<usize>), Error> {
+        let mut values = Vec::new();
+        let mut comma = false;
+        if self.eat(",") {
+            comma = true;
+        } else {
+            loop {
+                self.skip_ws();
+                if matches!(self.peek(), Some(')' | ';')) {
+                    break;
+                }
+                values.push(self.value()?);
+                comma = self.eat(",");
+                if !comma {
+                    break;
+                }
+            }
+        }
+        let len = self.fixed_len()?;
+        self.skip_ws();
+        if self.peek() != Some(')') {
+            return Err(self.unexpected("`)`"));
+        }
+        Ok((values, comma, len))
+    }
+
+    /// Parse a comma separated list of items until `;` or `close`.
+    fn list<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.peek() == Some(';') || self.peek() == Some(close) {
+                return Ok(items);
+            }
+            items.push(item(self)?);
+            if !self.eat(",") {
+                return Ok(items);
+            }
+        }
+    }
+
+    fn seq(&mut self) -> Result<Ast, Error> {
+        self.expect("[", "`[`")?;
+        let values = self.list(']', Self::value)?;
+        let len = self.len()?.unwrap_or(Some(values.len()));
+        self.expect("]", "`]`")?;
+        Ok(XAst::Seq {
+            len,
+            ops: values
+                .into_iter()
+                .map(|value| Seq::Element {
+                    value: Box::new(value),
+                })
+                .collect(),
+        })
+    }
+
+    /// Parse a value, or `_` for a missing one.
+    fn hole(&mut self) -> Result<Option<Ast>, Error> {
+        match self.keyword("_") {
+            true => Ok(None),
+            false => self.value().map(Some),
+        }
+    }
+
+    fn map(&mut self) -> Result<Ast, Error> {
+        self.expect("{", "`{`")?;
+        let entries = self.list('}', |parser| {
+            let key = parser.hole()?;
+            parser.expect("=>", "`=>`")?;
+            let value = parser.hole()?;
+            match (key, value) {
+                (None, None) => Err(parser.error(ErrorKind::EmptyEntry)),
+                entry => Ok(entry),
+            }
+        })?;
+        let mut ops = Vec::new();
+        for (key, value) in entries {
+            if let Some(key) = key {
+                ops.push(Map::Key { key: Box::new(key) });
+            }
+            if let Some(value) = value {
+                ops.push(Map::Value {
+                    value: Box::new(value),
+                });
+            }
+        }
+        let keys = ops
+            .iter()
+            .filter(|op| matches!(op, Map::Key { .. }))
+            .count();
+        let len = self.len()?.unwrap_or(Some(keys));
+        self.expect("}", "`}`")?;
+        Ok(XAst::Map { len, ops })
+    }
+
+    /// Parse the rest of struct fields after `{`, [None] values are skipped fields.
+    #[allow(clippy::type_complexity)]
+    fn fields(&mut self) -> Result<(usize, Vec<(Name, Option<Ast>)>), Error> {
+        let fields = self.list('}', |parser| {
+            let (key, quoted) = parser.name()?;
+            if !quoted && key == "skip" {
+                parser.skip_ws();
+                if parser.peek() != Some(':') {
+                    return Ok((Name::Owned(parser.name()?.0), None));
+                }
+            }
+            parser.expect(":", "`:`")?;
+            Ok((Name::Owned(key), Some(parser.value()?)))
+        })?;
+        let count = fields.iter().filter(|(_, value)| value.is_some()).count();
+        let len = match self.fixed_len()? {
+            Some(len) => len,
+            None => count,
+        };
+        self.expect("}", "`}`")?;
+        Ok((len, fields))
+    }
+
+    /// Parse a number token.
+    fn token(&mut self) -> &'s str {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '_')))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn integer<T: FromStr>(&mut self, ty: &'static str) -> Result<T, Error> {
+        let start = self.pos;
+        let token = self.token();
+        token.parse().map_err(|_| {
+            self.pos = start;
+            self.skip_ws();
+            self.error(ErrorKind::InvalidNumber {
+                ty,
+                literal: token.to_owned(),
+            })
+        })
+    }
+
+    fn number<T: FromStr>(&mut self, ty: &'static str, node: fn(T) -> Ast) -> Result<Ast, Error> {
+        self.integer(ty).map(node)
+    }
+
+    fn float<T: FromStr>(
+        &mut self,
+        ty: &'static str,
+        from_bits: fn(&str) -> Option<T>,
+        node: fn(T) -> Ast,
+    ) -> Result<Ast, Error> {
+        let start = self.pos;
+        let token = self.token();
+        let parsed = match token.strip_prefix("0x") {
+            Some(hex) => from_bits(hex),
+            None => token.parse().ok(),
+        };
+        match parsed {
+            Some(v) => Ok(node(v)),
+            None => {
+                self.pos = start;
+                self.skip_ws();
+                Err(self.error(ErrorKind::InvalidNumber {
+                    ty,
+                    literal: token.to_owned(),
+                }))
+            }
+        }
+    }
+
+    /// Parse the contents of a string or char literal, starting at the opening `quote`.
+    fn quoted(&mut self, quote: char) -> Result<String, Error> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            let escape = self.pos;
+            match self.bump() {
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') => {
+                    let c = match self.bump() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('0') => '\0',
+                        Some(c @ ('\\' | '\'' | '"')) => c,
+                        Some('u') => self.unicode_escape(escape)?,
+                        _ => {
+                            let invalid = self.src[escape..self.pos].to_owned();
+                            self.pos = escape;
+                            return Err(self.error(ErrorKind::InvalidEscape(invalid)));
+                        }
+                    };
+                    s.push(c);
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.unexpected("a closing quote")),
+            }
+        }
+    }
+
+    /// Parse the `{..}` part of a `\u{..}` escape.
+    fn unicode_escape(&mut self, escape: usize) -> Result<char, Error> {
+        let rest = self.rest();
+        let parsed = rest
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .and_then(|(hex, _)| {
+                let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)?;
+                Some((c, hex.len() + 2))
+            });
+        match parsed {
+            Some((c, len)) => {
+                self.pos += len;
+                Ok(c)
+            }
+            None => {
+                let invalid = self.src[escape..self.pos].to_owned();
+                self.pos = escape;
+                Err(self.error(ErrorKind::InvalidEscape(invalid)))
+            }
+        }
+    }
+
+    /// Parse the contents of a byte string, starting at the opening quote.
+    fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        self.bump();
+        let mut bytes = Vec::new();
+        loop {
+            let escape = self.pos;
+            match self.bump() {
+                Some('"') => return Ok(bytes),
+                Some('\\') => {
+                    let byte = match self.bump() {
+                        Some('n') => Some(b'\n'),
+                        Some('r') => Some(b'\r'),
+                        Some('t') => Some(b'\t'),
+                        Some('0') => Some(b'\0'),
+                        Some(c @ ('\\' | '\'' | '"')) => Some(c as u8),
+                        Some('x') => {
+                            let hex = self.rest().get(..2).unwrap_or_default();
+                            let byte = u8::from_str_radix(hex, 16).ok();
+                            if byte.is_some() {
+                                self.pos += 2;
+                            }
+                            byte
+                        }
+                        _ => None,
+                    };
+                    match byte {
+                        Some(byte) => bytes.push(byte),
+                        None => {
+                            let invalid = self.src[escape..self.pos].to_owned();
+                            self.pos = escape;
+                            return Err(self.error(ErrorKind::InvalidEscape(invalid)));
+                        }
+                    }
+                }
+                Some(c) if c.is_ascii() => bytes.push(c as u8),
+                Some(found) => {
+                    self.pos = escape;
+                    return Err(self.error(ErrorKind::Unexpected {
+                        expected: "an ASCII character or escape",
+                        found,
+                    }));
+                }
+                None => return Err(self.unexpected("a closing quote")),
+            }
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Total ordering and hashing of [XAst].
+//!
+//! [XAst] is only [PartialEq] because of [XAst::F32] and [XAst::F64]. [XAst::total_cmp] orders
+//! floats with [f64::total_cmp] instead, so `NaN` equals itself and `-0.0` is less than `0.0`,
+//! and [XAst::total_hash] hashes them by their bits. [Total] wraps an [XAst] to implement [Eq],
+//! [Ord] and [Hash] with them, e.g. to use an [XAst] as a key or deduplicate captures.
+//!
+//! Nodes of different variants are ordered by the declaration order of the variants, nodes of
+//! the same variant by their fields in declaration order, e.g. a name before its value.
+//!
+//! ```
+//! # use std::collections::HashSet;
+//! # use serde_ast::{to_ast, total::Total};
+//! let mut payloads = HashSet::new();
+//! assert!(payloads.insert(Total(to_ast(&(1, f64::NAN)).unwrap())));
+//! assert!(!payloads.insert(Total(to_ast(&(1, f64::NAN)).unwrap())));
+//! assert!(Total(to_ast(&-0.0).unwrap()) < Total(to_ast(&0.0).unwrap()));
+//! ```
+
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use crate::ast::{
+    Final, Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst,
+};
+
+/// [XAst] with total [Eq], [Ord] and [Hash], see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Total<X = Final>(pub XAst<X>);
+impl<X> From<XAst<X>> for Total<X> {
+    fn from(ast: XAst<X>) -> Self {
+        Self(ast)
+    }
+}
+impl<X> PartialEq for Total<X>
+where
+    X: Ord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<X> Eq for Total<X> where X: Ord {}
+impl<X> PartialOrd for Total<X>
+where
+    X: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<X> Ord for Total<X>
+where
+    X: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+impl<X> Hash for Total<X>
+where
+    X: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.total_hash(state)
+    }
+}
+
+/// Operation of a container projected to its tag, key and value.
+type Op<'a, X> = (u8, Option<&'a Name>, Option<&'a XAst<X>>);
+
+fn seq_op<X>(Seq::Element { value }: &Seq<X>) -> Op<'_, X> {
+    (0, None, Some(value))
+}
+fn tuple_op<X>(Tuple::Element { value }: &Tuple<X>) -> Op<'_, X> {
+    (0, None, Some(value))
+}
+fn tuple_struct_op<X>(TupleStruct::Field { value }: &TupleStruct<X>) -> Op<'_, X> {
+    (0, None, Some(value))
+}
+fn tuple_variant_op<X>(TupleVariant::Field { value }: &TupleVariant<X>) -> Op<'_, X> {
+    (0, None, Some(value))
+}
+fn map_op<X>(op: &Map<X>) -> Op<'_, X> {
+    match op {
+        Map::Key { key } => (0, None, Some(key)),
+        Map::Value { value } => (1, None, Some(value)),
+    }
+}
+fn struct_op<X>(op: &Struct<X>) -> Op<'_, X> {
+    match op {
+        Struct::Field { key, value } => (0, Some(key), Some(value)),
+        Struct::Skip { key } => (1, Some(key), None),
+    }
+}
+fn struct_variant_op<X>(op: &StructVariant<X>) -> Op<'_, X> {
+    match op {
+        StructVariant::Field { key, value } => (0, Some(key), Some(value)),
+        StructVariant::Skip { key } => (1, Some(key), None),
+    }
+}
+
+fn cmp_ops<'a, X, T: 'a>(a: &'a [T], b: &'a [T], op: fn(&'a T) -> Op<'a, X>) -> Ordering
+where
+    X: Ord + 'a,
+{
+    let pairs = a.iter().map(op).zip(b.iter().map(op));
+    for ((a_tag, a_key, a_value), (b_tag, b_key, b_value)) in pairs {
+        let ordering = (a_tag, a_key)
+            .cmp(&(b_tag, b_key))
+            .then_with(|| match (a_value, b_value) {
+                (Some(a), Some(b)) => a.total_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            });
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn hash_ops<'a, X, T: 'a, H>(ops: &'a [T], op: fn(&'a T) -> Op<'a, X>, state: &mut H)
+where
+    X: Hash + 'a,
+    H: Hasher,
+{
+    ops.len().hash(state);
+    for (tag, key, value) in ops.iter().map(op) {
+        (tag, key).hash(state);
+        if let Some(value) = value {
+            value.total_hash(state);
+        }
+    }
+}
+
+impl<X> XAst<X> {
+    /// Position of the variant in the declaration of [XAst].
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Bool(_) => 0,
+            Self::I8(_) => 1,
+            Self::I16(_) => 2,
+            Self::I32(_) => 3,
+            Self::I64(_) => 4,
+            Self::I128(_) => 5,
+            Self::U8(_) => 6,
+            Self::U16(_) => 7,
+            Self::U32(_) => 8,
+            Self::U64(_) => 9,
+            Self::U128(_) => 10,
+            Self::F32(_) => 11,
+            Self::F64(_) => 12,
+            Self::Char(_) => 13,
+            Self::Str(_) => 14,
+            Self::Bytes(_) => 15,
+            Self::None => 16,
+            Self::Some(_) => 17,
+            Self::Unit => 18,
+            Self::UnitStruct(_) => 19,
+            Self::UnitVariant { .. } => 20,
+            Self::NewtypeStruct { .. } => 21,
+            Self::NewtypeVariant { .. } => 22,
+            Self::Seq { .. } => 23,
+            Self::Tuple { .. } => 24,
+            Self::TupleStruct { .. } => 25,
+            Self::TupleVariant { .. } => 26,
+            Self::Map { .. } => 27,
+            Self::Struct { .. } => 28,
+            Self::StructVariant { .. } => 29,
+            Self::Readability { .. } => 30,
+            Self::X(_) => 31,
+        }
+    }
+
+    /// Compare with `other` in a total order, see the [module documentation](self).
+    pub fn total_cmp(&self, other: &Self) -> Ordering
+    where
+        X: Ord,
+    {
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| match (self, other) {
+                (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+                (Self::I8(a), Self::I8(b)) => a.cmp(b),
+                (Self::I16(a), Self::I16(b)) => a.cmp(b),
+                (Self::I32(a), Self::I32(b)) => a.cmp(b),
+                (Self::I64(a), Self::I64(b)) => a.cmp(b),
+                (Self::I128(a), Self::I128(b)) => a.cmp(b),
+                (Self::U8(a), Self::U8(b)) => a.cmp(b),
+                (Self::U16(a), Self::U16(b)) => a.cmp(b),
+                (Self::U32(a), Self::U32(b)) => a.cmp(b),
+                (Self::U64(a), Self::U64(b)) => a.cmp(b),
+                (Self::U128(a), Self::U128(b)) => a.cmp(b),
+                (Self::F32(a), Self::F32(b)) => a.total_cmp(b),
+                (Self::F64(a), Self::F64(b)) => a.total_cmp(b),
+                (Self::Char(a), Self::Char(b)) => a.cmp(b),
+                (Self::Str(a), Self::Str(b)) => a.cmp(b),
+                (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+                (Self::None, Self::None) | (Self::Unit, Self::Unit) => Ordering::Equal,
+                (Self::Some(a), Self::Some(b)) => a.total_cmp(b),
+                (Self::UnitStruct(a), Self::UnitStruct(b)) => a.cmp(b),
+                (
+                    Self::UnitVariant {
+                        name,
+                        variant_index,
+                        variant,
+                    },
+                    Self::UnitVariant {
+                        name: b_name,
+                        variant_index: b_index,
+                        variant: b_variant,
+                    },
+                ) => (name, variant_index, variant).cmp(&(b_name, b_index, b_variant)),
+                (
+                    Self::NewtypeStruct { name, value },
+                    Self::NewtypeStruct {
+                        name: b_name,
+                        value: b,
+                    },
+                ) => name.cmp(b_name).then_with(|| value.total_cmp(b)),
+                (
+                    Self::NewtypeVariant {
+                        name,
+                        variant_index,
+                        variant,
+                        value,
+                    },
+                    Self::NewtypeVariant {
+                        name: b_name,
+                        variant_index: b_index,
+                        variant: b_variant,
+                        value: b,
+                    },
+                ) => (name, variant_index, variant)
+                    .cmp(&(b_name, b_index, b_variant))
+                    .then_with(|| value.total_cmp(b)),
+                (Self::Seq { len, ops }, Self::Seq { len: b_len, ops: b }) => {
+                    len.cmp(b_len).then_with(|| cmp_ops(ops, b, seq_op))
+                }
+                (Self::Tuple { len, ops }, Self::Tuple { len: b_len, ops: b }) => {
+                    len.cmp(b_len).then_with(|| cmp_ops(ops, b, tuple_op))
+                }
+                (
+                    Self::TupleStruct { name, len, ops },
+                    Self::TupleStruct {
+                        name: b_name,
+                        len: b_len,
+                        ops: b,
+                    },
+                ) => (name, len)
+                    .cmp(&(b_name, b_len))
+                    .then_with(|| cmp_ops(ops, b, tuple_struct_op)),
+                (
+                    Self::TupleVariant {
+                        name,
+                        variant_index,
+                        variant,
+                        len,
+                        ops,
+                    },
+                    Self::TupleVariant {
+                        name: b_name,
+                        variant_index: b_index,
+                        variant: b_variant,
+                        len: b_len,
+                        ops: b,
+                    },
+                ) => (name, variant_index, variant, len)
+                    .cmp(&(b_name, b_index, b_variant, b_len))
+                    .then_with(|| cmp_ops(ops, b, tuple_variant_op)),
+                (Self::Map { len, ops }, Self::Map { len: b_len, ops: b }) => {
+                    len.cmp(b_len).then_with(|| cmp_ops(ops, b, map_op))
+                }
+                (
+                    Self::Struct { name, len, ops },
+                    Self::Struct {
+                        name: b_name,
+                        len: b_len,
+                        ops: b,
+                    },
+                ) => (name, len)
+                    .cmp(&(b_name, b_len))
+                    .then_with(|| cmp_ops(ops, b, struct_op)),
+                (
+                    Self::StructVariant {
+                        name,
+                        variant_index,
+                        variant,
+                        len,
+                        ops,
+                    },
+                    Self::StructVariant {
+                        name: b_name,
+                        variant_index: b_index,
+                        variant: b_variant,
+                        len: b_len,
+                        ops: b,
+                    },
+                ) => (name, variant_index, variant, len)
+                    .cmp(&(b_name, b_index, b_variant, b_len))
+                    .then_with(|| cmp_ops(ops, b, struct_variant_op)),
+                (
+                    Self::Readability {
+                        human_readable,
+                        compact,
+                    },
+                    Self::Readability {
+                        human_readable: b_human_readable,
+                        compact: b_compact,
+                    },
+                ) => human_readable
+                    .total_cmp(b_human_readable)
+                    .then_with(|| compact.total_cmp(b_compact)),
+                (Self::X(a), Self::X(b)) => a.cmp(b),
+                _ => unreachable!("nodes of the same rank have the same variant"),
+            })
+    }
+
+    /// Feed this [XAst] into `state`, consistently with [XAst::total_cmp].
+    pub fn total_hash<H>(&self, state: &mut H)
+    where
+        X: Hash,
+        H: Hasher,
+    {
+        self.rank().hash(state);
+        match self {
+            Self::Bool(v) => v.hash(state),
+            Self::I8(v) => v.hash(state),
+            Self::I16(v) => v.hash(state),
+            Self::I32(v) => v.hash(state),
+            Self::I64(v) => v.hash(state),
+            Self::I128(v) => v.hash(state),
+            Self::U8(v) => v.hash(state),
+            Self::U16(v) => v.hash(state),
+            Self::U32(v) => v.hash(state),
+            Self::U64(v) => v.hash(state),
+            Self::U128(v) => v.hash(state),
+            Self::F32(v) => v.to_bits().hash(state),
+            Self::F64(v) => v.to_bits().hash(state),
+            Self::Char(v) => v.hash(state),
+            Self::Str(v) => v.hash(state),
+            Self::Bytes(v) => v.hash(state),
+            Self::None | Self::Unit => {}
+            Self::Some(value) => value.total_hash(state),
+            Self::UnitStruct(name) => name.hash(state),
+            Self::UnitVariant {
+                name,
+                variant_index,
+                variant,
+            } => (name, variant_index, variant).hash(state),
+            Self::NewtypeStruct { name, value } => {
+                name.hash(state);
+                value.total_hash(state);
+            }
+            Self::NewtypeVariant {
+                name,
+                variant_index,
+                variant,
+                value,
+            } => {
+                (name, variant_index, variant).hash(state);
+                value.total_hash(state);
+            }
+            Self::Seq { len, ops } => {
+                len.hash(state);
+                hash_ops(ops, seq_op, state);
+            }
+            Self::Tuple { len, ops } => {
+                len.hash(state);
+                hash_ops(ops, tuple_op, state);
+            }
+            Self::TupleStruct { name, len, ops } => {
+                (name, len).hash(state);
+                hash_ops(ops, tuple_struct_op, state);
+            }
+            Self::TupleVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => {
+                (name, variant_index, variant, len).hash(state);
+                hash_ops(ops, tuple_variant_op, state);
+            }
+            Self::Map { len, ops } => {
+                len.hash(state);
+                hash_ops(ops, map_op, state);
+            }
+            Self::Struct { name, len, ops } => {
+                (name, len).hash(state);
+                hash_ops(ops, struct_op, state);
+            }
+            Self::StructVariant {
+                name,
+                variant_index,
+                variant,
+                len,
+                ops,
+            } => {
+                (name, variant_index, variant, len).hash(state);
+                hash_ops(ops, struct_variant_op, state);
+            }
+            Self::Readability {
+                human_readable,
+                compact,
+            } => {
+                human_readable.total_hash(state);
+                compact.total_hash(state);
+            }
+            Self::X(x) => x.hash(state),
+        }
+    }
+}
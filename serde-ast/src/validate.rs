@@ -0,0 +1,288 @@
+//! Validate that an [XAst] follows the protocol of [serde::Serializer].
+//!
+//! Any [XAst] can be constructed, but only some of them could have been produced by a
+//! [serde::Serialize] implementation. Formats like `bincode` rely on the protocol:
+//! - [XAst::Map] operations alternate [Map::Key] and [Map::Value]
+//! - the `len` of containers matches their elements, entries or fields, skipped fields excluded
+//! - [XAst::Struct] and [XAst::StructVariant] keys are unique, skipped fields included
+//!
+//! ```
+//! # use serde_ast::{ast::Seq, validate::ViolationKind, Ast};
+//! let ast = Ast::Seq {
+//!     len: None,
+//!     ops: vec![Seq::Element {
+//!         value: Box::new(Ast::Tuple { len: 2, ops: vec![] }),
+//!     }],
+//! };
+//! let violations = ast.validate();
+//! assert_eq!(violations.len(), 1);
+//! assert_eq!(violations[0].path.to_string(), "/0");
+//! assert_eq!(violations[0].kind, ViolationKind::Len { len: 2, actual: 0 });
+//! ```
+//!
+//! [Serializer::strict](crate::Serializer::strict) checks the nodes while capturing, and
+//! [Checker] applies the same rules to the calls made to any [serde::Serializer], see
+//! [CheckedSerializer](crate::ser::CheckedSerializer).
+
+use std::collections::HashSet;
+
+use crate::{
+    ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    path::{key_segment, AstPath},
+};
+
+/// A node which doesn't follow the protocol, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at `{path}`")]
+pub struct Violation {
+    /// Path of the invalid node.
+    pub path: AstPath,
+    /// Rule the node breaks.
+    pub kind: ViolationKind,
+}
+
+/// Rule broken by a [Violation].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(missing_docs)] // errors are self-documenting
+pub enum ViolationKind {
+    #[error("map key without a value")]
+    UnpairedKey,
+    #[error("map value without a key")]
+    UnpairedValue,
+    #[error("declared len {len} but serialized {actual}")]
+    Len { len: usize, actual: usize },
+    #[error("duplicate field `{0}`")]
+    DuplicateField(Name),
+}
+
+/// Incremental check of the operations of a single container, in serialization order.
+///
+/// Every operation is checked against the ones before it, [Checker::end] checks the `len`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checker {
+    len: Option<usize>,
+    actual: usize,
+    pending_key: bool,
+    keys: HashSet<Name>,
+}
+impl Checker {
+    /// Check a container declaring `len`, [None] accepts any length.
+    pub fn new(len: Option<usize>) -> Self {
+        Self {
+            len,
+            ..Self::default()
+        }
+    }
+
+    /// Check an element of a sequence or tuple, or a field of a tuple struct or variant.
+    pub fn element(&mut self) -> Result<(), ViolationKind> {
+        self.actual += 1;
+        Ok(())
+    }
+
+    /// Check a map key.
+    pub fn key(&mut self) -> Result<(), ViolationKind> {
+        if self.pending_key {
+            return Err(ViolationKind::UnpairedKey);
+        }
+        self.pending_key = true;
+        Ok(())
+    }
+
+    /// Check a map value.
+    pub fn value(&mut self) -> Result<(), ViolationKind> {
+        if !self.pending_key {
+            return Err(ViolationKind::UnpairedValue);
+        }
+        self.pending_key = false;
+        self.actual += 1;
+        Ok(())
+    }
+
+    /// Check a field `key` of a struct or struct variant.
+    pub fn field(&mut self, key: &Name) -> Result<(), ViolationKind> {
+        self.actual += 1;
+        self.unique(key)
+    }
+
+    /// Check a skipped field `key` of a struct or struct variant.
+    pub fn skip(&mut self, key: &Name) -> Result<(), ViolationKind> {
+        self.unique(key)
+    }
+
+    /// Check the end of the container.
+    pub fn end(&self) -> Result<(), ViolationKind> {
+        if self.pending_key {
+            return Err(ViolationKind::UnpairedKey);
+        }
+        match self.len {
+            Some(len) if len != self.actual => Err(ViolationKind::Len {
+                len,
+                actual: self.actual,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn unique(&mut self, key: &Name) -> Result<(), ViolationKind> {
+        if !self.keys.insert(key.clone()) {
+            return Err(ViolationKind::DuplicateField(key.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Check the operations of `node` itself, without its children.
+pub fn check<X>(node: &XAst<X>) -> Vec<ViolationKind> {
+    let mut violations = Vec::new();
+    let mut record = |result: Result<(), ViolationKind>| {
+        if let Err(violation) = result {
+            violations.push(violation);
+        }
+    };
+    let checker = match node {
+        XAst::Seq { len, ops } => {
+            let mut checker = Checker::new(*len);
+            for Seq::Element { .. } in ops {
+                record(checker.element());
+            }
+            checker
+        }
+        XAst::Tuple { len, ops } => {
+            let mut checker = Checker::new(Some(*len));
+            for Tuple::Element { .. } in ops {
+                record(checker.element());
+            }
+            checker
+        }
+        XAst::TupleStruct { len, ops, .. } => {
+            let mut checker = Checker::new(Some(*len));
+            for TupleStruct::Field { .. } in ops {
+                record(checker.element());
+            }
+            checker
+        }
+        XAst::TupleVariant { len, ops, .. } => {
+            let mut checker = Checker::new(Some(*len));
+            for TupleVariant::Field { .. } in ops {
+                record(checker.element());
+            }
+            checker
+        }
+        XAst::Map { len, ops } => {
+            let mut checker = Checker::new(*len);
+            for op in ops {
+                record(match op {
+                    Map::Key { .. } => checker.key(),
+                    Map::Value { .. } => checker.value(),
+                });
+            }
+            checker
+        }
+        XAst::Struct { len, ops, .. } => {
+            let mut checker = Checker::new(Some(*len));
+            for op in ops {
+                record(match op {
+                    Struct::Field { key, .. } => checker.field(key),
+                    Struct::Skip { key } => checker.skip(key),
+                });
+            }
+            checker
+        }
+        XAst::StructVariant { len, ops, .. } => {
+            let mut checker = Checker::new(Some(*len));
+            for op in ops {
+                record(match op {
+                    StructVariant::Field { key, .. } => checker.field(key),
+                    StructVariant::Skip { key } => checker.skip(key),
+                });
+            }
+            checker
+        }
+        _ => return violations,
+    };
+    record(checker.end());
+    violations
+}
+
+impl<X> XAst<X> {
+    /// Find every [Violation] of the protocol in this [XAst], see the [module documentation](self).
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        validate(&mut AstPath::root(), self, &mut violations);
+        violations
+    }
+}
+
+fn validate<X>(path: &mut AstPath, node: &XAst<X>, violations: &mut Vec<Violation>) {
+    violations.extend(check(node).into_iter().map(|kind| Violation {
+        path: path.clone(),
+        kind,
+    }));
+    let mut child = |segment: String, value: &XAst<X>| {
+        path.push(segment);
+        validate(path, value, violations);
+        path.pop();
+    };
+    match node {
+        XAst::Some(value)
+        | XAst::NewtypeStruct { value, .. }
+        | XAst::NewtypeVariant { value, .. } => validate(path, value, violations),
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => {
+            validate(path, human_readable, violations);
+            validate(path, compact, violations);
+        }
+        XAst::Seq { ops, .. } => {
+            for (index, Seq::Element { value }) in ops.iter().enumerate() {
+                child(index.to_string(), value);
+            }
+        }
+        XAst::Tuple { ops, .. } => {
+            for (index, Tuple::Element { value }) in ops.iter().enumerate() {
+                child(index.to_string(), value);
+            }
+        }
+        XAst::TupleStruct { ops, .. } => {
+            for (index, TupleStruct::Field { value }) in ops.iter().enumerate() {
+                child(index.to_string(), value);
+            }
+        }
+        XAst::TupleVariant { ops, .. } => {
+            for (index, TupleVariant::Field { value }) in ops.iter().enumerate() {
+                child(index.to_string(), value);
+            }
+        }
+        XAst::Map { ops, .. } => {
+            let mut key = None;
+            for (index, op) in ops.iter().enumerate() {
+                let (value, segment) = match op {
+                    Map::Key { key: value } => {
+                        key = Some(value);
+                        (value, key_segment(value))
+                    }
+                    Map::Value { value } => (value, key.take().and_then(|key| key_segment(key))),
+                };
+                let segment = segment.map_or_else(|| (index / 2).to_string(), |s| s.into_owned());
+                child(segment, value);
+            }
+        }
+        XAst::Struct { ops, .. } => {
+            for op in ops {
+                if let Struct::Field { key, value } = op {
+                    child(key.to_string(), value);
+                }
+            }
+        }
+        XAst::StructVariant { ops, .. } => {
+            for op in ops {
+                if let StructVariant::Field { key, value } = op {
+                    child(key.to_string(), value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
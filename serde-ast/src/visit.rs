@@ -0,0 +1,504 @@
+//! Traverse an [XAst] by shared reference.
+//!
+//! Each method of [Visit] defaults to the free function of the same name, which walks into the
+//! children of the node. Override a method to inspect a node, and call the free function to keep
+//! walking into its children.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{ast::XAst, to_ast, visit::{self, Visit}};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     hello: String,
+//!     nested: Vec<Example>,
+//! }
+//! let example = Example {
+//!     hello: "World".to_string(),
+//!     nested: vec![Example { hello: "Nested".to_string(), nested: vec![] }],
+//! };
+//! let ast = to_ast(&example).expect("serialize to_ast");
+//!
+//! struct Keys<'ast>(Vec<&'ast str>);
+//! impl<'ast, X: 'ast> Visit<'ast, X> for Keys<'ast> {
+//!     fn visit_struct_field(&mut self, key: &'ast str, value: &'ast XAst<X>) {
+//!         self.0.push(key);
+//!         visit::visit_struct_field(self, key, value);
+//!     }
+//! }
+//! let mut keys = Keys(Vec::new());
+//! keys.visit_ast(&ast);
+//! assert_eq!(keys.0, ["hello", "nested", "hello", "nested"]);
+//! ```
+
+use crate::ast::{Map, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst};
+
+/// Traverse an [XAst] by shared reference.
+///
+/// See the [module documentation](self).
+#[allow(unused_variables)] // default implementations ignore leaves
+pub trait Visit<'ast, X: 'ast> {
+    /// Visit any node, dispatching to the method for its variant.
+    fn visit_ast(&mut self, node: &'ast XAst<X>) {
+        visit_ast(self, node)
+    }
+
+    /// Visit [XAst::Bool].
+    fn visit_bool(&mut self, v: bool) {}
+    /// Visit [XAst::I8].
+    fn visit_i8(&mut self, v: i8) {}
+    /// Visit [XAst::I16].
+    fn visit_i16(&mut self, v: i16) {}
+    /// Visit [XAst::I32].
+    fn visit_i32(&mut self, v: i32) {}
+    /// Visit [XAst::I64].
+    fn visit_i64(&mut self, v: i64) {}
+    /// Visit [XAst::I128].
+    fn visit_i128(&mut self, v: i128) {}
+    /// Visit [XAst::U8].
+    fn visit_u8(&mut self, v: u8) {}
+    /// Visit [XAst::U16].
+    fn visit_u16(&mut self, v: u16) {}
+    /// Visit [XAst::U32].
+    fn visit_u32(&mut self, v: u32) {}
+    /// Visit [XAst::U64].
+    fn visit_u64(&mut self, v: u64) {}
+    /// Visit [XAst::U128].
+    fn visit_u128(&mut self, v: u128) {}
+    /// Visit [XAst::F32].
+    fn visit_f32(&mut self, v: f32) {}
+    /// Visit [XAst::F64].
+    fn visit_f64(&mut self, v: f64) {}
+    /// Visit [XAst::Char].
+    fn visit_char(&mut self, v: char) {}
+    /// Visit [XAst::Str].
+    fn visit_str(&mut self, v: &'ast str) {}
+    /// Visit [XAst::Bytes].
+    fn visit_bytes(&mut self, v: &'ast [u8]) {}
+
+    /// Visit [XAst::None].
+    fn visit_none(&mut self) {}
+    /// Visit [XAst::Some].
+    fn visit_some(&mut self, value: &'ast XAst<X>) {
+        visit_some(self, value)
+    }
+
+    /// Visit [XAst::Unit].
+    fn visit_unit(&mut self) {}
+    /// Visit [XAst::UnitStruct].
+    fn visit_unit_struct(&mut self, name: &'ast str) {}
+    /// Visit [XAst::UnitVariant].
+    fn visit_unit_variant(&mut self, name: &'ast str, variant_index: u32, variant: &'ast str) {}
+
+    /// Visit [XAst::NewtypeStruct].
+    fn visit_newtype_struct(&mut self, name: &'ast str, value: &'ast XAst<X>) {
+        visit_newtype_struct(self, name, value)
+    }
+    /// Visit [XAst::NewtypeVariant].
+    fn visit_newtype_variant(
+        &mut self,
+        name: &'ast str,
+        variant_index: u32,
+        variant: &'ast str,
+        value: &'ast XAst<X>,
+    ) {
+        visit_newtype_variant(self, name, variant_index, variant, value)
+    }
+
+    /// Visit [XAst::Seq].
+    fn visit_seq(&mut self, len: Option<usize>, ops: &'ast [Seq<X>]) {
+        visit_seq(self, len, ops)
+    }
+    /// Visit [XAst::Tuple].
+    fn visit_tuple(&mut self, len: usize, ops: &'ast [Tuple<X>]) {
+        visit_tuple(self, len, ops)
+    }
+    /// Visit [XAst::TupleStruct].
+    fn visit_tuple_struct(&mut self, name: &'ast str, len: usize, ops: &'ast [TupleStruct<X>]) {
+        visit_tuple_struct(self, name, len, ops)
+    }
+    /// Visit [XAst::TupleVariant].
+    fn visit_tuple_variant(
+        &mut self,
+        name: &'ast str,
+        variant_index: u32,
+        variant: &'ast str,
+        len: usize,
+        ops: &'ast [TupleVariant<X>],
+    ) {
+        visit_tuple_variant(self, name, variant_index, variant, len, ops)
+    }
+    /// Visit [XAst::Map].
+    fn visit_map(&mut self, len: Option<usize>, ops: &'ast [Map<X>]) {
+        visit_map(self, len, ops)
+    }
+    /// Visit [XAst::Struct].
+    fn visit_struct(&mut self, name: &'ast str, len: usize, ops: &'ast [Struct<X>]) {
+        visit_struct(self, name, len, ops)
+    }
+    /// Visit [XAst::StructVariant].
+    fn visit_struct_variant(
+        &mut self,
+        name: &'ast str,
+        variant_index: u32,
+        variant: &'ast str,
+        len: usize,
+        ops: &'ast [StructVariant<X>],
+    ) {
+        visit_struct_variant(self, name, variant_index, variant, len, ops)
+    }
+
+    /// Visit [XAst::Readability].
+    fn visit_readability(&mut self, human_readable: &'ast XAst<X>, compact: &'ast XAst<X>) {
+        visit_readability(self, human_readable, compact)
+    }
+
+    /// Visit [XAst::X].
+    fn visit_x(&mut self, x: &'ast X) {}
+
+    /// Visit a [Seq] operation.
+    fn visit_seq_op(&mut self, op: &'ast Seq<X>) {
+        visit_seq_op(self, op)
+    }
+    /// Visit a [Tuple] operation.
+    fn visit_tuple_op(&mut self, op: &'ast Tuple<X>) {
+        visit_tuple_op(self, op)
+    }
+    /// Visit a [TupleStruct] operation.
+    fn visit_tuple_struct_op(&mut self, op: &'ast TupleStruct<X>) {
+        visit_tuple_struct_op(self, op)
+    }
+    /// Visit a [TupleVariant] operation.
+    fn visit_tuple_variant_op(&mut self, op: &'ast TupleVariant<X>) {
+        visit_tuple_variant_op(self, op)
+    }
+    /// Visit a [Map] operation.
+    fn visit_map_op(&mut self, op: &'ast Map<X>) {
+        visit_map_op(self, op)
+    }
+    /// Visit a [Struct] operation.
+    fn visit_struct_op(&mut self, op: &'ast Struct<X>) {
+        visit_struct_op(self, op)
+    }
+    /// Visit a [StructVariant] operation.
+    fn visit_struct_variant_op(&mut self, op: &'ast StructVariant<X>) {
+        visit_struct_variant_op(self, op)
+    }
+
+    /// Visit [Map::Key].
+    fn visit_map_key(&mut self, key: &'ast XAst<X>) {
+        visit_map_key(self, key)
+    }
+    /// Visit [Map::Value].
+    fn visit_map_value(&mut self, value: &'ast XAst<X>) {
+        visit_map_value(self, value)
+    }
+    /// Visit [Struct::Field].
+    fn visit_struct_field(&mut self, key: &'ast str, value: &'ast XAst<X>) {
+        visit_struct_field(self, key, value)
+    }
+    /// Visit [Struct::Skip].
+    fn visit_struct_skip(&mut self, key: &'ast str) {}
+    /// Visit [StructVariant::Field].
+    fn visit_struct_variant_field(&mut self, key: &'ast str, value: &'ast XAst<X>) {
+        visit_struct_variant_field(self, key, value)
+    }
+    /// Visit [StructVariant::Skip].
+    fn visit_struct_variant_skip(&mut self, key: &'ast str) {}
+}
+
+/// Dispatch `node` to the [Visit] method for its variant.
+pub fn visit_ast<'ast, V, X>(v: &mut V, node: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match node {
+        XAst::Bool(x) => v.visit_bool(*x),
+        XAst::I8(x) => v.visit_i8(*x),
+        XAst::I16(x) => v.visit_i16(*x),
+        XAst::I32(x) => v.visit_i32(*x),
+        XAst::I64(x) => v.visit_i64(*x),
+        XAst::I128(x) => v.visit_i128(*x),
+        XAst::U8(x) => v.visit_u8(*x),
+        XAst::U16(x) => v.visit_u16(*x),
+        XAst::U32(x) => v.visit_u32(*x),
+        XAst::U64(x) => v.visit_u64(*x),
+        XAst::U128(x) => v.visit_u128(*x),
+        XAst::F32(x) => v.visit_f32(*x),
+        XAst::F64(x) => v.visit_f64(*x),
+        XAst::Char(x) => v.visit_char(*x),
+        XAst::Str(x) => v.visit_str(x),
+        XAst::Bytes(x) => v.visit_bytes(x),
+        XAst::None => v.visit_none(),
+        XAst::Some(value) => v.visit_some(value),
+        XAst::Unit => v.visit_unit(),
+        XAst::UnitStruct(name) => v.visit_unit_struct(name),
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => v.visit_unit_variant(name, *variant_index, variant),
+        XAst::NewtypeStruct { name, value } => v.visit_newtype_struct(name, value),
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => v.visit_newtype_variant(name, *variant_index, variant, value),
+        XAst::Seq { len, ops } => v.visit_seq(*len, ops),
+        XAst::Tuple { len, ops } => v.visit_tuple(*len, ops),
+        XAst::TupleStruct { name, len, ops } => v.visit_tuple_struct(name, *len, ops),
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => v.visit_tuple_variant(name, *variant_index, variant, *len, ops),
+        XAst::Map { len, ops } => v.visit_map(*len, ops),
+        XAst::Struct { name, len, ops } => v.visit_struct(name, *len, ops),
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => v.visit_struct_variant(name, *variant_index, variant, *len, ops),
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => v.visit_readability(human_readable, compact),
+        XAst::X(x) => v.visit_x(x),
+    }
+}
+
+/// Walk the `value` of [XAst::Some].
+pub fn visit_some<'ast, V, X>(v: &mut V, value: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
+
+/// Walk the `value` of [XAst::NewtypeStruct].
+pub fn visit_newtype_struct<'ast, V, X>(v: &mut V, _name: &'ast str, value: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
+
+/// Walk the `value` of [XAst::NewtypeVariant].
+pub fn visit_newtype_variant<'ast, V, X>(
+    v: &mut V,
+    _name: &'ast str,
+    _variant_index: u32,
+    _variant: &'ast str,
+    value: &'ast XAst<X>,
+) where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
+
+/// Walk the `ops` of [XAst::Seq].
+pub fn visit_seq<'ast, V, X>(v: &mut V, _len: Option<usize>, ops: &'ast [Seq<X>])
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_seq_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Tuple].
+pub fn visit_tuple<'ast, V, X>(v: &mut V, _len: usize, ops: &'ast [Tuple<X>])
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::TupleStruct].
+pub fn visit_tuple_struct<'ast, V, X>(
+    v: &mut V,
+    _name: &'ast str,
+    _len: usize,
+    ops: &'ast [TupleStruct<X>],
+) where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_struct_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::TupleVariant].
+pub fn visit_tuple_variant<'ast, V, X>(
+    v: &mut V,
+    _name: &'ast str,
+    _variant_index: u32,
+    _variant: &'ast str,
+    _len: usize,
+    ops: &'ast [TupleVariant<X>],
+) where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_variant_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Map].
+pub fn visit_map<'ast, V, X>(v: &mut V, _len: Option<usize>, ops: &'ast [Map<X>])
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_map_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Struct].
+pub fn visit_struct<'ast, V, X>(v: &mut V, _name: &'ast str, _len: usize, ops: &'ast [Struct<X>])
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_struct_op(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::StructVariant].
+pub fn visit_struct_variant<'ast, V, X>(
+    v: &mut V,
+    _name: &'ast str,
+    _variant_index: u32,
+    _variant: &'ast str,
+    _len: usize,
+    ops: &'ast [StructVariant<X>],
+) where
+    V: Visit<'ast, X> + ?Sized,
+{
+    for op in ops {
+        v.visit_struct_variant_op(op)
+    }
+}
+
+/// Walk both values of [XAst::Readability].
+pub fn visit_readability<'ast, V, X>(
+    v: &mut V,
+    human_readable: &'ast XAst<X>,
+    compact: &'ast XAst<X>,
+) where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(human_readable);
+    v.visit_ast(compact);
+}
+
+/// Walk a [Seq] operation.
+pub fn visit_seq_op<'ast, V, X>(v: &mut V, op: &'ast Seq<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        Seq::Element { value } => v.visit_ast(value),
+    }
+}
+
+/// Walk a [Tuple] operation.
+pub fn visit_tuple_op<'ast, V, X>(v: &mut V, op: &'ast Tuple<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        Tuple::Element { value } => v.visit_ast(value),
+    }
+}
+
+/// Walk a [TupleStruct] operation.
+pub fn visit_tuple_struct_op<'ast, V, X>(v: &mut V, op: &'ast TupleStruct<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        TupleStruct::Field { value } => v.visit_ast(value),
+    }
+}
+
+/// Walk a [TupleVariant] operation.
+pub fn visit_tuple_variant_op<'ast, V, X>(v: &mut V, op: &'ast TupleVariant<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        TupleVariant::Field { value } => v.visit_ast(value),
+    }
+}
+
+/// Dispatch a [Map] operation.
+pub fn visit_map_op<'ast, V, X>(v: &mut V, op: &'ast Map<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        Map::Key { key } => v.visit_map_key(key),
+        Map::Value { value } => v.visit_map_value(value),
+    }
+}
+
+/// Dispatch a [Struct] operation.
+pub fn visit_struct_op<'ast, V, X>(v: &mut V, op: &'ast Struct<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        Struct::Field { key, value } => v.visit_struct_field(key, value),
+        Struct::Skip { key } => v.visit_struct_skip(key),
+    }
+}
+
+/// Dispatch a [StructVariant] operation.
+pub fn visit_struct_variant_op<'ast, V, X>(v: &mut V, op: &'ast StructVariant<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    match op {
+        StructVariant::Field { key, value } => v.visit_struct_variant_field(key, value),
+        StructVariant::Skip { key } => v.visit_struct_variant_skip(key),
+    }
+}
+
+/// Walk the `key` of [Map::Key].
+pub fn visit_map_key<'ast, V, X>(v: &mut V, key: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(key)
+}
+
+/// Walk the `value` of [Map::Value].
+pub fn visit_map_value<'ast, V, X>(v: &mut V, value: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
+
+/// Walk the `value` of [Struct::Field].
+pub fn visit_struct_field<'ast, V, X>(v: &mut V, _key: &'ast str, value: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
+
+/// Walk the `value` of [StructVariant::Field].
+pub fn visit_struct_variant_field<'ast, V, X>(v: &mut V, _key: &'ast str, value: &'ast XAst<X>)
+where
+    V: Visit<'ast, X> + ?Sized,
+{
+    v.visit_ast(value)
+}
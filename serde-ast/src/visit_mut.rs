@@ -0,0 +1,512 @@
+//! Traverse an [XAst] by mutable reference, editing it in place.
+//!
+//! Each method of [VisitMut] defaults to the free function of the same name, which walks into the
+//! children of the node. Override a method to edit a node, and call the free function to keep
+//! walking into its children.
+//!
+//! ```
+//! # use serde::Serialize;
+//! # use serde_ast::{to_ast, visit_mut::VisitMut};
+//! #[derive(Serialize)]
+//! struct Example {
+//!     hello: String,
+//! }
+//! let example = Example { hello: "World".to_string() };
+//! let mut ast = to_ast(&example).expect("serialize to_ast");
+//!
+//! struct Uppercase;
+//! impl<X> VisitMut<X> for Uppercase {
+//!     fn visit_str_mut(&mut self, v: &mut String) {
+//!         *v = v.to_uppercase();
+//!     }
+//! }
+//! Uppercase.visit_ast_mut(&mut ast);
+//! assert_eq!(serde_json::to_string(&ast).unwrap(), r#"{"hello":"WORLD"}"#);
+//! ```
+
+use crate::ast::{Map, Name, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst};
+
+/// Traverse an [XAst] by mutable reference.
+///
+/// See the [module documentation](self).
+#[allow(unused_variables)] // default implementations ignore leaves
+pub trait VisitMut<X> {
+    /// Visit any node, dispatching to the method for its variant.
+    ///
+    /// Override this to replace whole nodes.
+    fn visit_ast_mut(&mut self, node: &mut XAst<X>) {
+        visit_ast_mut(self, node)
+    }
+
+    /// Visit [XAst::Bool].
+    fn visit_bool_mut(&mut self, v: &mut bool) {}
+    /// Visit [XAst::I8].
+    fn visit_i8_mut(&mut self, v: &mut i8) {}
+    /// Visit [XAst::I16].
+    fn visit_i16_mut(&mut self, v: &mut i16) {}
+    /// Visit [XAst::I32].
+    fn visit_i32_mut(&mut self, v: &mut i32) {}
+    /// Visit [XAst::I64].
+    fn visit_i64_mut(&mut self, v: &mut i64) {}
+    /// Visit [XAst::I128].
+    fn visit_i128_mut(&mut self, v: &mut i128) {}
+    /// Visit [XAst::U8].
+    fn visit_u8_mut(&mut self, v: &mut u8) {}
+    /// Visit [XAst::U16].
+    fn visit_u16_mut(&mut self, v: &mut u16) {}
+    /// Visit [XAst::U32].
+    fn visit_u32_mut(&mut self, v: &mut u32) {}
+    /// Visit [XAst::U64].
+    fn visit_u64_mut(&mut self, v: &mut u64) {}
+    /// Visit [XAst::U128].
+    fn visit_u128_mut(&mut self, v: &mut u128) {}
+    /// Visit [XAst::F32].
+    fn visit_f32_mut(&mut self, v: &mut f32) {}
+    /// Visit [XAst::F64].
+    fn visit_f64_mut(&mut self, v: &mut f64) {}
+    /// Visit [XAst::Char].
+    fn visit_char_mut(&mut self, v: &mut char) {}
+    /// Visit [XAst::Str].
+    fn visit_str_mut(&mut self, v: &mut String) {}
+    /// Visit [XAst::Bytes].
+    fn visit_bytes_mut(&mut self, v: &mut Vec<u8>) {}
+
+    /// Visit [XAst::None].
+    fn visit_none_mut(&mut self) {}
+    /// Visit [XAst::Some].
+    fn visit_some_mut(&mut self, value: &mut XAst<X>) {
+        visit_some_mut(self, value)
+    }
+
+    /// Visit [XAst::Unit].
+    fn visit_unit_mut(&mut self) {}
+    /// Visit [XAst::UnitStruct].
+    fn visit_unit_struct_mut(&mut self, name: &mut Name) {}
+    /// Visit [XAst::UnitVariant].
+    fn visit_unit_variant_mut(
+        &mut self,
+        name: &mut Name,
+        variant_index: &mut u32,
+        variant: &mut Name,
+    ) {
+    }
+
+    /// Visit [XAst::NewtypeStruct].
+    fn visit_newtype_struct_mut(&mut self, name: &mut Name, value: &mut XAst<X>) {
+        visit_newtype_struct_mut(self, name, value)
+    }
+    /// Visit [XAst::NewtypeVariant].
+    fn visit_newtype_variant_mut(
+        &mut self,
+        name: &mut Name,
+        variant_index: &mut u32,
+        variant: &mut Name,
+        value: &mut XAst<X>,
+    ) {
+        visit_newtype_variant_mut(self, name, variant_index, variant, value)
+    }
+
+    /// Visit [XAst::Seq].
+    fn visit_seq_mut(&mut self, len: &mut Option<usize>, ops: &mut Vec<Seq<X>>) {
+        visit_seq_mut(self, len, ops)
+    }
+    /// Visit [XAst::Tuple].
+    fn visit_tuple_mut(&mut self, len: &mut usize, ops: &mut Vec<Tuple<X>>) {
+        visit_tuple_mut(self, len, ops)
+    }
+    /// Visit [XAst::TupleStruct].
+    fn visit_tuple_struct_mut(
+        &mut self,
+        name: &mut Name,
+        len: &mut usize,
+        ops: &mut Vec<TupleStruct<X>>,
+    ) {
+        visit_tuple_struct_mut(self, name, len, ops)
+    }
+    /// Visit [XAst::TupleVariant].
+    fn visit_tuple_variant_mut(
+        &mut self,
+        name: &mut Name,
+        variant_index: &mut u32,
+        variant: &mut Name,
+        len: &mut usize,
+        ops: &mut Vec<TupleVariant<X>>,
+    ) {
+        visit_tuple_variant_mut(self, name, variant_index, variant, len, ops)
+    }
+    /// Visit [XAst::Map].
+    fn visit_map_mut(&mut self, len: &mut Option<usize>, ops: &mut Vec<Map<X>>) {
+        visit_map_mut(self, len, ops)
+    }
+    /// Visit [XAst::Struct].
+    fn visit_struct_mut(&mut self, name: &mut Name, len: &mut usize, ops: &mut Vec<Struct<X>>) {
+        visit_struct_mut(self, name, len, ops)
+    }
+    /// Visit [XAst::StructVariant].
+    fn visit_struct_variant_mut(
+        &mut self,
+        name: &mut Name,
+        variant_index: &mut u32,
+        variant: &mut Name,
+        len: &mut usize,
+        ops: &mut Vec<StructVariant<X>>,
+    ) {
+        visit_struct_variant_mut(self, name, variant_index, variant, len, ops)
+    }
+
+    /// Visit [XAst::Readability].
+    fn visit_readability_mut(&mut self, human_readable: &mut XAst<X>, compact: &mut XAst<X>) {
+        visit_readability_mut(self, human_readable, compact)
+    }
+
+    /// Visit [XAst::X].
+    fn visit_x_mut(&mut self, x: &mut X) {}
+
+    /// Visit a [Seq] operation.
+    fn visit_seq_op_mut(&mut self, op: &mut Seq<X>) {
+        visit_seq_op_mut(self, op)
+    }
+    /// Visit a [Tuple] operation.
+    fn visit_tuple_op_mut(&mut self, op: &mut Tuple<X>) {
+        visit_tuple_op_mut(self, op)
+    }
+    /// Visit a [TupleStruct] operation.
+    fn visit_tuple_struct_op_mut(&mut self, op: &mut TupleStruct<X>) {
+        visit_tuple_struct_op_mut(self, op)
+    }
+    /// Visit a [TupleVariant] operation.
+    fn visit_tuple_variant_op_mut(&mut self, op: &mut TupleVariant<X>) {
+        visit_tuple_variant_op_mut(self, op)
+    }
+    /// Visit a [Map] operation.
+    fn visit_map_op_mut(&mut self, op: &mut Map<X>) {
+        visit_map_op_mut(self, op)
+    }
+    /// Visit a [Struct] operation.
+    fn visit_struct_op_mut(&mut self, op: &mut Struct<X>) {
+        visit_struct_op_mut(self, op)
+    }
+    /// Visit a [StructVariant] operation.
+    fn visit_struct_variant_op_mut(&mut self, op: &mut StructVariant<X>) {
+        visit_struct_variant_op_mut(self, op)
+    }
+
+    /// Visit [Map::Key].
+    fn visit_map_key_mut(&mut self, key: &mut XAst<X>) {
+        visit_map_key_mut(self, key)
+    }
+    /// Visit [Map::Value].
+    fn visit_map_value_mut(&mut self, value: &mut XAst<X>) {
+        visit_map_value_mut(self, value)
+    }
+    /// Visit [Struct::Field].
+    fn visit_struct_field_mut(&mut self, key: &mut Name, value: &mut XAst<X>) {
+        visit_struct_field_mut(self, key, value)
+    }
+    /// Visit [Struct::Skip].
+    fn visit_struct_skip_mut(&mut self, key: &mut Name) {}
+    /// Visit [StructVariant::Field].
+    fn visit_struct_variant_field_mut(&mut self, key: &mut Name, value: &mut XAst<X>) {
+        visit_struct_variant_field_mut(self, key, value)
+    }
+    /// Visit [StructVariant::Skip].
+    fn visit_struct_variant_skip_mut(&mut self, key: &mut Name) {}
+}
+
+/// Dispatch `node` to the [VisitMut] method for its variant.
+pub fn visit_ast_mut<V, X>(v: &mut V, node: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match node {
+        XAst::Bool(x) => v.visit_bool_mut(x),
+        XAst::I8(x) => v.visit_i8_mut(x),
+        XAst::I16(x) => v.visit_i16_mut(x),
+        XAst::I32(x) => v.visit_i32_mut(x),
+        XAst::I64(x) => v.visit_i64_mut(x),
+        XAst::I128(x) => v.visit_i128_mut(x),
+        XAst::U8(x) => v.visit_u8_mut(x),
+        XAst::U16(x) => v.visit_u16_mut(x),
+        XAst::U32(x) => v.visit_u32_mut(x),
+        XAst::U64(x) => v.visit_u64_mut(x),
+        XAst::U128(x) => v.visit_u128_mut(x),
+        XAst::F32(x) => v.visit_f32_mut(x),
+        XAst::F64(x) => v.visit_f64_mut(x),
+        XAst::Char(x) => v.visit_char_mut(x),
+        XAst::Str(x) => v.visit_str_mut(x),
+        XAst::Bytes(x) => v.visit_bytes_mut(x),
+        XAst::None => v.visit_none_mut(),
+        XAst::Some(value) => v.visit_some_mut(value),
+        XAst::Unit => v.visit_unit_mut(),
+        XAst::UnitStruct(name) => v.visit_unit_struct_mut(name),
+        XAst::UnitVariant {
+            name,
+            variant_index,
+            variant,
+        } => v.visit_unit_variant_mut(name, variant_index, variant),
+        XAst::NewtypeStruct { name, value } => v.visit_newtype_struct_mut(name, value),
+        XAst::NewtypeVariant {
+            name,
+            variant_index,
+            variant,
+            value,
+        } => v.visit_newtype_variant_mut(name, variant_index, variant, value),
+        XAst::Seq { len, ops } => v.visit_seq_mut(len, ops),
+        XAst::Tuple { len, ops } => v.visit_tuple_mut(len, ops),
+        XAst::TupleStruct { name, len, ops } => v.visit_tuple_struct_mut(name, len, ops),
+        XAst::TupleVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => v.visit_tuple_variant_mut(name, variant_index, variant, len, ops),
+        XAst::Map { len, ops } => v.visit_map_mut(len, ops),
+        XAst::Struct { name, len, ops } => v.visit_struct_mut(name, len, ops),
+        XAst::StructVariant {
+            name,
+            variant_index,
+            variant,
+            len,
+            ops,
+        } => v.visit_struct_variant_mut(name, variant_index, variant, len, ops),
+        XAst::Readability {
+            human_readable,
+            compact,
+        } => v.visit_readability_mut(human_readable, compact),
+        XAst::X(x) => v.visit_x_mut(x),
+    }
+}
+
+/// Walk the `value` of [XAst::Some].
+pub fn visit_some_mut<V, X>(v: &mut V, value: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
+
+/// Walk the `value` of [XAst::NewtypeStruct].
+pub fn visit_newtype_struct_mut<V, X>(v: &mut V, _name: &mut Name, value: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
+
+/// Walk the `value` of [XAst::NewtypeVariant].
+pub fn visit_newtype_variant_mut<V, X>(
+    v: &mut V,
+    _name: &mut Name,
+    _variant_index: &mut u32,
+    _variant: &mut Name,
+    value: &mut XAst<X>,
+) where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
+
+/// Walk the `ops` of [XAst::Seq].
+pub fn visit_seq_mut<V, X>(v: &mut V, _len: &mut Option<usize>, ops: &mut Vec<Seq<X>>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_seq_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Tuple].
+pub fn visit_tuple_mut<V, X>(v: &mut V, _len: &mut usize, ops: &mut Vec<Tuple<X>>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::TupleStruct].
+pub fn visit_tuple_struct_mut<V, X>(
+    v: &mut V,
+    _name: &mut Name,
+    _len: &mut usize,
+    ops: &mut Vec<TupleStruct<X>>,
+) where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_struct_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::TupleVariant].
+pub fn visit_tuple_variant_mut<V, X>(
+    v: &mut V,
+    _name: &mut Name,
+    _variant_index: &mut u32,
+    _variant: &mut Name,
+    _len: &mut usize,
+    ops: &mut Vec<TupleVariant<X>>,
+) where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_tuple_variant_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Map].
+pub fn visit_map_mut<V, X>(v: &mut V, _len: &mut Option<usize>, ops: &mut Vec<Map<X>>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_map_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::Struct].
+pub fn visit_struct_mut<V, X>(
+    v: &mut V,
+    _name: &mut Name,
+    _len: &mut usize,
+    ops: &mut Vec<Struct<X>>,
+) where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_struct_op_mut(op)
+    }
+}
+
+/// Walk the `ops` of [XAst::StructVariant].
+pub fn visit_struct_variant_mut<V, X>(
+    v: &mut V,
+    _name: &mut Name,
+    _variant_index: &mut u32,
+    _variant: &mut Name,
+    _len: &mut usize,
+    ops: &mut Vec<StructVariant<X>>,
+) where
+    V: VisitMut<X> + ?Sized,
+{
+    for op in ops {
+        v.visit_struct_variant_op_mut(op)
+    }
+}
+
+/// Walk both values of [XAst::Readability].
+pub fn visit_readability_mut<V, X>(v: &mut V, human_readable: &mut XAst<X>, compact: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(human_readable);
+    v.visit_ast_mut(compact);
+}
+
+/// Walk a [Seq] operation.
+pub fn visit_seq_op_mut<V, X>(v: &mut V, op: &mut Seq<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        Seq::Element { value } => v.visit_ast_mut(value),
+    }
+}
+
+/// Walk a [Tuple] operation.
+pub fn visit_tuple_op_mut<V, X>(v: &mut V, op: &mut Tuple<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        Tuple::Element { value } => v.visit_ast_mut(value),
+    }
+}
+
+/// Walk a [TupleStruct] operation.
+pub fn visit_tuple_struct_op_mut<V, X>(v: &mut V, op: &mut TupleStruct<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        TupleStruct::Field { value } => v.visit_ast_mut(value),
+    }
+}
+
+/// Walk a [TupleVariant] operation.
+pub fn visit_tuple_variant_op_mut<V, X>(v: &mut V, op: &mut TupleVariant<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        TupleVariant::Field { value } => v.visit_ast_mut(value),
+    }
+}
+
+/// Dispatch a [Map] operation.
+pub fn visit_map_op_mut<V, X>(v: &mut V, op: &mut Map<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        Map::Key { key } => v.visit_map_key_mut(key),
+        Map::Value { value } => v.visit_map_value_mut(value),
+    }
+}
+
+/// Dispatch a [Struct] operation.
+pub fn visit_struct_op_mut<V, X>(v: &mut V, op: &mut Struct<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        Struct::Field { key, value } => v.visit_struct_field_mut(key, value),
+        Struct::Skip { key } => v.visit_struct_skip_mut(key),
+    }
+}
+
+/// Dispatch a [StructVariant] operation.
+pub fn visit_struct_variant_op_mut<V, X>(v: &mut V, op: &mut StructVariant<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    match op {
+        StructVariant::Field { key, value } => v.visit_struct_variant_field_mut(key, value),
+        StructVariant::Skip { key } => v.visit_struct_variant_skip_mut(key),
+    }
+}
+
+/// Walk the `key` of [Map::Key].
+pub fn visit_map_key_mut<V, X>(v: &mut V, key: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(key)
+}
+
+/// Walk the `value` of [Map::Value].
+pub fn visit_map_value_mut<V, X>(v: &mut V, value: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
+
+/// Walk the `value` of [Struct::Field].
+pub fn visit_struct_field_mut<V, X>(v: &mut V, _key: &mut Name, value: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
+
+/// Walk the `value` of [StructVariant::Field].
+pub fn visit_struct_variant_field_mut<V, X>(v: &mut V, _key: &mut Name, value: &mut XAst<X>)
+where
+    V: VisitMut<X> + ?Sized,
+{
+    v.visit_ast_mut(value)
+}
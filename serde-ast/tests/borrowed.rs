@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use serde_ast::{
+    ast::{AstRef, Borrowed},
+    visit::Visit,
+    Ast,
+};
+
+#[derive(Serialize)]
+struct Example {
+    name: String,
+    escaped: String,
+    mode: Mode,
+}
+
+#[derive(Serialize)]
+enum Mode {
+    Range { min: u8 },
+}
+
+fn example() -> String {
+    serde_json::to_string(&Example {
+        name: "borrowed".to_string(),
+        escaped: "tab\t".to_string(),
+        mode: Mode::Range { min: 1 },
+    })
+    .expect("serde_json::to_string")
+}
+
+#[derive(Default)]
+struct Strings<'a> {
+    borrowed: Vec<&'a str>,
+    owned: Vec<String>,
+}
+impl<'ast, 'a: 'ast> Visit<'ast, Borrowed<'a>> for Strings<'a> {
+    fn visit_str(&mut self, v: &'ast str) {
+        self.owned.push(v.to_string());
+    }
+    fn visit_x(&mut self, x: &'ast Borrowed<'a>) {
+        match x {
+            Borrowed::Str(v) => self.borrowed.push(v),
+            Borrowed::Bytes(v) => panic!("unexpected bytes {v:?}"),
+        }
+    }
+}
+
+#[test]
+fn borrowed_json() {
+    let json = example();
+    let ast = AstRef::deserialize(&mut serde_json::Deserializer::from_str(&json))
+        .expect("deserialize AstRef");
+
+    let mut strings = Strings::default();
+    strings.visit_ast(&ast);
+    assert_eq!(
+        strings.borrowed,
+        ["name", "borrowed", "escaped", "mode", "Range", "min"]
+    );
+    assert_eq!(strings.owned, ["tab\t"]);
+
+    assert_eq!(serde_json::to_string(&ast).expect("serialize AstRef"), json);
+}
+
+#[test]
+fn borrowed_into_owned() {
+    let json = example();
+    let borrowed = AstRef::deserialize(&mut serde_json::Deserializer::from_str(&json))
+        .expect("deserialize AstRef");
+    let owned: Ast = serde_json::from_str(&json).expect("deserialize Ast");
+    assert_eq!(borrowed.into_owned(), owned);
+}
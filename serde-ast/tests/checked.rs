@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use quickcheck::quickcheck;
+use serde::{
+    ser::{SerializeMap, SerializeSeq, SerializeStruct},
+    Serialize,
+};
+
+use serde_ast::{ser::CheckedSerializer, text, to_ast, Readability, Serializer};
+
+/// Serialize `value` into JSON through a [CheckedSerializer].
+fn checked_json<T>(value: &T) -> Result<String, String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut json = Vec::new();
+    value
+        .serialize(CheckedSerializer::new(&mut serde_json::Serializer::new(
+            &mut json,
+        )))
+        .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8(json).unwrap())
+}
+
+enum Broken {
+    KeyKey,
+    Value,
+    Dangling,
+    SeqLen,
+    StructLen,
+    Duplicate,
+}
+impl Serialize for Broken {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::KeyKey => {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_key("a")?;
+                map.serialize_key("b")?;
+                map.end()
+            }
+            Self::Value => {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_value("a")?;
+                map.end()
+            }
+            Self::Dangling => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_key("b")?;
+                map.end()
+            }
+            Self::SeqLen => {
+                let mut seq = serializer.serialize_seq(Some(2))?;
+                seq.serialize_element(&1)?;
+                seq.end()
+            }
+            Self::StructLen => {
+                let mut s = serializer.serialize_struct("Broken", 2)?;
+                s.serialize_field("a", &1)?;
+                s.skip_field("b")?;
+                s.end()
+            }
+            Self::Duplicate => {
+                let mut s = serializer.serialize_struct("Broken", 2)?;
+                s.serialize_field("a", &1)?;
+                s.serialize_field("a", &2)?;
+                s.end()
+            }
+        }
+    }
+}
+
+#[test]
+fn checked_forwards() {
+    #[derive(Serialize)]
+    struct Example {
+        name: &'static str,
+        tags: Vec<Option<char>>,
+        map: BTreeMap<u8, (bool, f32)>,
+    }
+    let example = Example {
+        name: "example",
+        tags: vec![Some('a'), None],
+        map: BTreeMap::from([(1, (true, 1.5))]),
+    };
+    assert_eq!(
+        checked_json(&example).unwrap(),
+        serde_json::to_string(&example).unwrap()
+    );
+}
+
+#[test]
+fn checked_violations() {
+    assert_eq!(
+        checked_json(&Broken::KeyKey).unwrap_err(),
+        "invalid protocol: map key without a value"
+    );
+    assert_eq!(
+        checked_json(&Broken::Value).unwrap_err(),
+        "invalid protocol: map value without a key"
+    );
+    assert_eq!(
+        checked_json(&Broken::Dangling).unwrap_err(),
+        "invalid protocol: map key without a value"
+    );
+    assert_eq!(
+        checked_json(&Broken::SeqLen).unwrap_err(),
+        "invalid protocol: declared len 2 but serialized 1"
+    );
+    assert_eq!(
+        checked_json(&Broken::StructLen).unwrap_err(),
+        "invalid protocol: declared len 2 but serialized 1"
+    );
+    assert_eq!(
+        checked_json(&Broken::Duplicate).unwrap_err(),
+        "invalid protocol: duplicate field `a`"
+    );
+}
+
+#[test]
+fn checked_nested() {
+    assert_eq!(
+        checked_json(&vec![Some(Broken::SeqLen)]).unwrap_err(),
+        "invalid protocol: declared len 2 but serialized 1"
+    );
+
+    let serializer = CheckedSerializer::new(Serializer::new(Readability::HumanReadable));
+    let error = (1, Broken::Value).serialize(serializer).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid protocol: map value without a key at [1]"
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid protocol: map key without a value")]
+fn checked_panicking() {
+    let serializer = CheckedSerializer::new(Serializer::new(Readability::HumanReadable));
+    let _ = [Broken::Dangling].serialize(serializer.panicking(true));
+}
+
+type Example = (
+    String,
+    Option<char>,
+    (i64, u8, f32),
+    Vec<u8>,
+    BTreeMap<i16, bool>,
+);
+
+quickcheck! {
+    fn checked_roundtrip(example: Vec<Example>) -> bool {
+        let serializer = CheckedSerializer::new(Serializer::new(Readability::HumanReadable));
+        let ast = example.serialize(serializer).expect("checked serialize");
+        text::to_string(&ast) == text::to_string(&to_ast(&example).expect("serialize to_ast"))
+    }
+}
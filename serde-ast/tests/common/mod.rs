@@ -0,0 +1,47 @@
+//! Fixture shared by the integration tests, covering every kind of node.
+#![allow(dead_code)] // each test crate uses a part of it
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Config {
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub mode: Mode,
+    pub pair: (u8, char),
+    pub ports: BTreeMap<u16, Mode>,
+    pub scale: f64,
+    pub id: Id,
+}
+
+#[derive(Serialize)]
+pub enum Mode {
+    Off,
+    Level(i32),
+    Pair(i8, i16),
+    Range { min: f32 },
+}
+
+#[derive(Serialize)]
+pub struct Id(pub u64);
+
+pub fn config() -> Config {
+    Config {
+        name: Some("example".to_string()),
+        user: None,
+        mode: Mode::Range { min: 1.5 },
+        pair: (1, 'c'),
+        ports: [
+            (22, Mode::Off),
+            (80, Mode::Level(-1)),
+            (443, Mode::Pair(-2, 3)),
+        ]
+        .into_iter()
+        .collect(),
+        scale: 0.5,
+        id: Id(7),
+    }
+}
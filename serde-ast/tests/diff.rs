@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::XAst,
+    diff,
+    diff::{diff_with, Change, Options},
+    to_ast, Ast,
+};
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u8>,
+    tags: Vec<String>,
+    ports: BTreeMap<u16, String>,
+}
+
+fn config() -> Config {
+    Config {
+        name: "example".to_string(),
+        version: None,
+        tags: vec!["a".to_string(), "b".to_string()],
+        ports: [(22, "ssh".to_string())].into_iter().collect(),
+    }
+}
+
+fn summary(changes: &[Change<'_, serde_ast::ast::Final>]) -> Vec<(char, String)> {
+    changes
+        .iter()
+        .map(|change| match change {
+            Change::Added { path, .. } => ('+', path.to_string()),
+            Change::Removed { path, .. } => ('-', path.to_string()),
+            Change::Changed { path, .. } => ('~', path.to_string()),
+        })
+        .collect()
+}
+
+#[test]
+fn diff_equal() {
+    let a = to_ast(&config()).unwrap();
+    let b = to_ast(&config()).unwrap();
+    let diff = diff(&a, &b);
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn diff_struct_fields() {
+    let a = to_ast(&config()).unwrap();
+    let b = to_ast(&Config {
+        name: "changed".to_string(),
+        version: Some(2),
+        ..config()
+    })
+    .unwrap();
+
+    let diff = diff(&a, &b);
+    assert_eq!(
+        summary(diff.changes()),
+        vec![('~', "/name".to_string()), ('+', "/version".to_string())],
+    );
+    assert_eq!(
+        diff.to_string(),
+        "@@ /name @@\n- \"example\"\n+ \"changed\"\n@@ /version @@\n+ Some(2u8)\n",
+    );
+
+    let diff = serde_ast::diff(&b, &a);
+    assert_eq!(
+        summary(diff.changes()),
+        vec![('~', "/name".to_string()), ('-', "/version".to_string())],
+    );
+}
+
+#[test]
+fn diff_map_entries() {
+    let a = to_ast(&config()).unwrap();
+    let b = to_ast(&Config {
+        ports: [(22, "sftp".to_string()), (80, "http".to_string())]
+            .into_iter()
+            .collect(),
+        ..config()
+    })
+    .unwrap();
+
+    let diff = diff(&a, &b);
+    assert_eq!(
+        summary(diff.changes()),
+        vec![
+            ('~', "/ports/22".to_string()),
+            ('+', "/ports/80".to_string())
+        ],
+    );
+}
+
+#[test]
+fn diff_seq_index() {
+    let a = to_ast(&["a", "b", "c"]).unwrap();
+    let b = to_ast(&["x", "a", "b", "c"]).unwrap();
+
+    let diff = diff(&a, &b);
+    assert_eq!(
+        summary(diff.changes()),
+        vec![
+            ('~', "/0".to_string()),
+            ('~', "/1".to_string()),
+            ('~', "/2".to_string()),
+            ('+', "/3".to_string()),
+        ],
+    );
+}
+
+#[test]
+fn diff_seq_lcs() {
+    let options = Options { lcs: true };
+
+    let a = to_ast(&vec!["a", "b", "c"]).unwrap();
+    let b = to_ast(&vec!["x", "a", "c", "d"]).unwrap();
+    let diff = diff_with(&a, &b, &options);
+    assert_eq!(
+        summary(diff.changes()),
+        vec![
+            ('+', "/0".to_string()),
+            ('-', "/1".to_string()),
+            ('+', "/3".to_string()),
+        ],
+    );
+
+    let a = to_ast(&vec!["a", "b", "c"]).unwrap();
+    let b = to_ast(&vec!["a", "x", "c"]).unwrap();
+    let diff = diff_with(&a, &b, &options);
+    assert!(matches!(
+        diff.changes(),
+        [Change::Changed { path, from: XAst::Str(from), to: XAst::Str(to) }]
+            if path.to_string() == "/1" && from == "b" && to == "x",
+    ));
+}
+
+#[test]
+fn diff_root_changed() {
+    let a: Ast = to_ast(&1u8).unwrap();
+    let b: Ast = to_ast(&"1").unwrap();
+    let diff = diff(&a, &b);
+    assert_eq!(diff.to_string(), "@@ (root) @@\n- 1u8\n+ \"1\"\n");
+}
+
+#[test]
+fn diff_seq_lcs_large() {
+    let a = to_ast(&(0..100_000u32).collect::<Vec<_>>()).unwrap();
+    let b = to_ast(&(1..100_000u32).collect::<Vec<_>>()).unwrap();
+    let diff = diff_with(&a, &b, &Options { lcs: true });
+    assert_eq!(diff.changes().len(), 100_000);
+    assert!(matches!(
+        diff.changes().last(),
+        Some(Change::Removed { path, .. }) if path.to_string() == "/99999",
+    ));
+}
+
+#[test]
+fn diff_map_composite_keys() {
+    let a = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((2, 'b'), 2)])).unwrap();
+    let b = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((2, 'b'), 3)])).unwrap();
+    assert_eq!(
+        diff(&a, &b).to_string(),
+        "@@ /(2u8, 'b') @@\n- 2u8\n+ 3u8\n"
+    );
+}
+
+#[test]
+fn diff_total_equality() {
+    let a = to_ast(&vec![f64::NAN, 1.0]).unwrap();
+    let b = to_ast(&vec![f64::NAN, -0.0, 1.0]).unwrap();
+    assert!(diff(&a, &a.clone()).is_empty());
+    let diff = diff_with(&a, &b, &Options { lcs: true });
+    assert_eq!(summary(diff.changes()), [('+', "/1".to_string())]);
+}
+
+#[test]
+fn diff_container_len() {
+    let a: Ast = XAst::Seq {
+        len: None,
+        ops: vec![],
+    };
+    let b: Ast = XAst::Seq {
+        len: Some(0),
+        ops: vec![],
+    };
+    assert_eq!(summary(diff(&a, &b).changes()), [('~', "".to_string())]);
+
+    let a = to_ast(&vec![1u8]).unwrap();
+    let b = to_ast(&vec![1u8, 2]).unwrap();
+    assert_eq!(summary(diff(&a, &b).changes()), [('+', "/1".to_string())]);
+}
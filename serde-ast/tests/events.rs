@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use quickcheck::quickcheck;
+use serde::Serialize;
+
+use serde_ast::{
+    ast::{Seq, XAst},
+    events::{self, Event, EventSerializer, Leaf},
+    text, to_ast, Readability, Serializer,
+};
+
+mod common;
+
+use common::{config, Mode};
+
+#[test]
+fn events_sequence() {
+    let events = events::to_events(&(Some(Mode::Level(1)), [0u8; 0])).unwrap();
+    assert_eq!(
+        events,
+        [
+            Event::TupleBegin { len: 2 },
+            Event::Some,
+            Event::NewtypeVariant {
+                name: "Mode".into(),
+                variant_index: 1,
+                variant: "Level".into(),
+            },
+            Event::Leaf(Leaf::I32(1)),
+            Event::TupleBegin { len: 0 },
+            Event::End,
+            Event::End,
+        ]
+    );
+}
+
+#[test]
+fn events_sink_callback() {
+    let mut count = 0;
+    config()
+        .serialize(&mut EventSerializer::new(|_: Event| count += 1))
+        .unwrap();
+    assert_eq!(count, events::to_events(&config()).unwrap().len());
+}
+
+#[test]
+fn events_ast_lossless() {
+    let ast = to_ast(&config()).unwrap();
+    let events = events::from_ast(&ast);
+    assert_eq!(events, events::to_events(&config()).unwrap());
+    assert!(events.contains(&Event::Skip("user".into())));
+    assert_eq!(events::to_ast(events).unwrap(), ast);
+}
+
+#[test]
+fn events_replay_json() {
+    let events = events::to_events(&config()).unwrap();
+    let mut json = Vec::new();
+    events::replay(events, &mut serde_json::Serializer::new(&mut json)).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        serde_json::to_string(&config()).unwrap()
+    );
+}
+
+#[test]
+fn events_replay_errors() {
+    let error = |events: Vec<Event>| events::to_ast(events).unwrap_err().to_string();
+    assert_eq!(error(vec![]), "unexpected end of events, expected a value");
+    assert_eq!(
+        error(vec![Event::SeqBegin { len: None }]),
+        "unexpected end of events, expected a value at [0]"
+    );
+    assert_eq!(
+        error(vec![
+            Event::MapBegin { len: None },
+            Event::Field("a".into())
+        ]),
+        "unexpected event Field(\"a\"), expected a map key, value or end"
+    );
+    assert_eq!(
+        error(vec![Event::Leaf(Leaf::Unit), Event::End]),
+        "unexpected event End after the value"
+    );
+}
+
+type Example = (
+    String,
+    Option<char>,
+    (i64, u8, f32),
+    Vec<u8>,
+    BTreeMap<i16, bool>,
+);
+
+quickcheck! {
+    fn events_roundtrip(example: Vec<Example>) -> bool {
+        let ast = to_ast(&example).expect("serialize to_ast");
+        let output = events::to_ast(events::from_ast(&ast)).expect("events to_ast");
+        text::to_string(&output) == text::to_string(&ast)
+    }
+}
+
+#[test]
+fn events_readability() {
+    let ast = XAst::Readability {
+        human_readable: Box::new(XAst::Str("a".to_string())),
+        compact: Box::new(XAst::U8(1)),
+    };
+    let events = events::from_ast(&ast);
+    assert_eq!(
+        events,
+        [
+            Event::Readability,
+            Event::Leaf(Leaf::Str("a".to_string())),
+            Event::Leaf(Leaf::U8(1)),
+        ]
+    );
+    assert_eq!(events::to_ast(events.clone()).unwrap(), ast);
+
+    let human_readable =
+        events::replay(events.clone(), Serializer::new(Readability::HumanReadable));
+    assert_eq!(text::to_string(&human_readable.unwrap()), r#""a""#);
+    let compact = events::replay(events, Serializer::new(Readability::Compact));
+    assert_eq!(text::to_string(&compact.unwrap()), "u8 1");
+
+    let ip = std::net::Ipv4Addr::new(127, 0, 0, 1);
+    let both = Serializer::new(Readability::HumanReadable)
+        .serialize_both(&vec![ip])
+        .unwrap();
+    assert_eq!(events::to_ast(events::from_ast(&both)).unwrap(), both);
+}
+
+#[test]
+fn events_readability_skip() {
+    let ast = XAst::Seq {
+        len: Some(2),
+        ops: vec![
+            Seq::Element {
+                value: Box::new(XAst::Readability {
+                    human_readable: Box::new(to_ast(&BTreeMap::from([("a", vec![1u8])])).unwrap()),
+                    compact: Box::new(to_ast(&(Some(1u8), [2u8])).unwrap()),
+                }),
+            },
+            Seq::Element {
+                value: Box::new(XAst::U8(3)),
+            },
+        ],
+    };
+    let events = events::from_ast(&ast);
+    let replay = |readability| {
+        let ast = events::replay(events.clone(), Serializer::new(readability)).unwrap();
+        text::to_string(&ast)
+    };
+    assert_eq!(
+        replay(Readability::HumanReadable),
+        r#"[{ "a" => [u8 1] }, u8 3]"#
+    );
+    assert_eq!(
+        replay(Readability::Compact),
+        "[(Some(u8 1), (u8 2,)), u8 3]"
+    );
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+
+use serde_ast::{
+    ast::{self, SerializeExtension, XAst},
+    ser::{self, Hook},
+    to_ast, Serializer,
+};
+
+/// Extension node replacing a value with a fixed placeholder.
+#[derive(Debug, Clone, PartialEq)]
+struct Redacted;
+impl SerializeExtension for Redacted {
+    fn serialize_extension<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str("***")
+    }
+}
+
+#[derive(Serialize)]
+struct Login {
+    user: String,
+    password: String,
+    tokens: Vec<String>,
+}
+
+/// Replace every `password` field and every sequence element with [Redacted].
+fn redact(ast: serde_ast::Ast) -> XAst<Redacted> {
+    let XAst::Struct { name, len, ops } = ast else {
+        panic!("expected a struct");
+    };
+    let ops = ops
+        .into_iter()
+        .map(|op| match op {
+            ast::Struct::Field { key, .. } if key == "password" => ast::Struct::Field {
+                key,
+                value: Box::new(XAst::X(Redacted)),
+            },
+            ast::Struct::Field { key, value } => ast::Struct::Field {
+                key,
+                value: Box::new(match *value {
+                    XAst::Str(v) => XAst::Str(v),
+                    XAst::Seq { len, ops } => XAst::Seq {
+                        len,
+                        ops: ops
+                            .into_iter()
+                            .map(|_| ast::Seq::Element {
+                                value: Box::new(XAst::X(Redacted)),
+                            })
+                            .collect(),
+                    },
+                    _ => panic!("unexpected value"),
+                }),
+            },
+            ast::Struct::Skip { key } => ast::Struct::Skip { key },
+        })
+        .collect();
+    XAst::Struct { name, len, ops }
+}
+
+#[test]
+fn json_extension_nested() {
+    let example = Login {
+        user: "admin".to_string(),
+        password: "hunter2".to_string(),
+        tokens: vec!["a".to_string(), "b".to_string()],
+    };
+
+    let ast = redact(to_ast(&example).expect("serialize to_ast"));
+    let output = serde_json::to_string(&ast).expect("serde_json::to_string");
+    assert_eq!(
+        output,
+        r#"{"user":"admin","password":"***","tokens":["***","***"]}"#
+    );
+}
+
+/// Extension nodes captured by [Capture].
+#[derive(Debug, Clone, PartialEq)]
+enum Captured {
+    Raw(String),
+    Datetime(String),
+    Secret,
+}
+impl SerializeExtension for Captured {
+    fn serialize_extension<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Raw(v) | Self::Datetime(v) => serializer.serialize_str(v),
+            Self::Secret => serializer.serialize_str("***"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Capture;
+impl Hook for Capture {
+    type X = Captured;
+
+    fn newtype_struct<T>(
+        &self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Option<XAst<Self::X>>, ser::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name != "$raw" {
+            return Ok(None);
+        }
+        match to_ast(value)? {
+            XAst::Str(raw) => Ok(Some(XAst::X(Captured::Raw(raw)))),
+            _ => Ok(None),
+        }
+    }
+
+    fn field(&self, key: &'static str, value: XAst<Self::X>) -> Result<XAst<Self::X>, ser::Error> {
+        match key {
+            "password" => Ok(XAst::X(Captured::Secret)),
+            _ => Ok(value),
+        }
+    }
+
+    fn node(&self, node: XAst<Self::X>) -> Result<XAst<Self::X>, ser::Error> {
+        match node {
+            XAst::Struct { name, ops, .. } if name == "Datetime" => match ops.as_slice() {
+                [ast::Struct::Field { value, .. }] => match value.as_ref() {
+                    XAst::Str(v) => Ok(XAst::X(Captured::Datetime(v.clone()))),
+                    _ => Err(serde::ser::Error::custom("invalid Datetime")),
+                },
+                _ => Err(serde::ser::Error::custom("invalid Datetime")),
+            },
+            node => Ok(node),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Raw(String);
+impl Raw {
+    fn serialize_raw<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct("$raw", &self.0)
+    }
+}
+
+#[derive(Serialize)]
+struct Datetime {
+    value: String,
+}
+
+#[derive(Serialize)]
+struct Config {
+    #[serde(serialize_with = "Raw::serialize_raw")]
+    raw: Raw,
+    created: Datetime,
+    password: String,
+}
+
+#[test]
+fn hook_extension_nodes() {
+    let example = Config {
+        raw: Raw("[1,2]".to_string()),
+        created: Datetime {
+            value: "1979-05-27T07:32:00Z".to_string(),
+        },
+        password: "hunter2".to_string(),
+    };
+
+    let ast = example
+        .serialize(Serializer::with_hook(Capture))
+        .expect("serialize with hook");
+    let XAst::Struct { ops, .. } = &ast else {
+        panic!("expected a struct");
+    };
+    let values: Vec<_> = ops
+        .iter()
+        .map(|op| match op {
+            ast::Struct::Field { value, .. } => value.as_ref().clone(),
+            ast::Struct::Skip { .. } => panic!("unexpected skip"),
+        })
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            XAst::X(Captured::Raw("[1,2]".to_string())),
+            XAst::X(Captured::Datetime("1979-05-27T07:32:00Z".to_string())),
+            XAst::X(Captured::Secret),
+        ]
+    );
+
+    let output = serde_json::to_string(&ast).expect("serde_json::to_string");
+    assert_eq!(
+        output,
+        r#"{"raw":"[1,2]","created":"1979-05-27T07:32:00Z","password":"***"}"#
+    );
+}
@@ -1,7 +1,7 @@
 use quickcheck::quickcheck;
 use serde::Serialize;
 
-use serde_ast::{to_ast, Ast};
+use serde_ast::{from_ast, persist, to_ast, Ast};
 
 fn ast_to_json(ast: &Ast) -> String {
     let mut buf = Vec::new();
@@ -31,6 +31,14 @@ quickcheck! {
         let ast = to_ast(&example).expect("serialize to_ast");
         ast_to_json(&ast) == expected
     }
+    fn integers128(example: (u128, i128)) -> bool {
+        let expected = serde_json::to_string(&example).expect("serde_json::to_string");
+        let ast = to_ast(&example).expect("serialize to_ast");
+        let bytes = persist::from_bytes(&persist::to_bytes(&ast)).expect("decode bytes");
+        ast_to_json(&ast) == expected
+            && from_ast::<(u128, i128)>(&ast).expect("deserialize from_ast") == example
+            && bytes == ast
+    }
     fn floats(example: (f32, f64)) -> bool {
         let expected = serde_json::to_string(&example).expect("serde_json::to_string");
         let ast = to_ast(&example).expect("serialize to_ast");
@@ -41,4 +49,9 @@ quickcheck! {
         let ast = to_ast(&example).expect("serialize to_ast");
         ast_to_json(&ast) == expected
     }
+    fn json_to_ast(example: std::collections::BTreeMap<String, (Vec<i64>, Option<bool>)>) -> bool {
+        let expected = serde_json::to_string(&example).expect("serde_json::to_string");
+        let ast: Ast = serde_json::from_str(&expected).expect("serde_json::from_str");
+        ast_to_json(&ast) == expected
+    }
 }
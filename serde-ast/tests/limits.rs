@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Serializer as _};
+
+use serde_ast::{
+    ast::{self, Final, XAst},
+    ser::{self, ErrorKind, Hook, Readability, Serializer},
+    to_ast, Ast,
+};
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    tags: Vec<String>,
+    nested: Option<Box<Config>>,
+}
+
+fn config(depth: usize) -> Config {
+    Config {
+        name: "config".to_string(),
+        tags: vec!["a".to_string(), "bc".to_string()],
+        nested: (depth > 0).then(|| Box::new(config(depth - 1))),
+    }
+}
+
+fn serializer() -> Serializer {
+    Serializer::new(Readability::HumanReadable)
+}
+
+fn limit(limit: &'static str, max: usize) -> ErrorKind {
+    ErrorKind::LimitExceeded { limit, max }
+}
+
+#[test]
+fn limits_unlimited() {
+    let value = config(3);
+    assert_eq!(
+        value.serialize(serializer()).unwrap(),
+        to_ast(&value).unwrap()
+    );
+    let limited = serializer()
+        .max_depth(Some(8))
+        .max_nodes(Some(24))
+        .max_bytes(Some(36))
+        .max_len(Some(3));
+    assert_eq!(value.serialize(limited).unwrap(), to_ast(&value).unwrap());
+}
+
+#[test]
+fn limits_depth() {
+    let error = config(3)
+        .serialize(serializer().max_depth(Some(5)))
+        .unwrap_err();
+    assert_eq!(error.kind, limit("depth", 5));
+    assert_eq!(
+        error.to_string(),
+        "depth limit of 5 exceeded at .nested.nested.tags[0]"
+    );
+}
+
+#[test]
+fn limits_nodes() {
+    let error = config(3)
+        .serialize(serializer().max_nodes(Some(23)))
+        .unwrap_err();
+    assert_eq!(error.kind, limit("nodes", 23));
+}
+
+#[test]
+fn limits_bytes() {
+    let error = config(3)
+        .serialize(serializer().max_bytes(Some(35)))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "bytes limit of 35 exceeded at .nested.nested.nested.tags[1]"
+    );
+}
+
+#[test]
+fn limits_len() {
+    let error = config(0)
+        .serialize(serializer().max_len(Some(2)))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "len limit of 2 exceeded");
+
+    let map = BTreeMap::from([(1, vec![1, 2]), (2, vec![1, 2, 3])]);
+    let error = map.serialize(serializer().max_len(Some(2))).unwrap_err();
+    assert_eq!(error.to_string(), "len limit of 2 exceeded at [\"2\"]");
+
+    let items = (0..5).filter(|_| true);
+    let error = serializer()
+        .max_len(Some(4))
+        .collect_seq(items)
+        .unwrap_err();
+    assert_eq!(error.kind, limit("len", 4));
+}
+
+#[test]
+fn limits_clone_budget() {
+    let serializer = serializer().max_nodes(Some(24));
+    for _ in 0..2 {
+        config(3).serialize(serializer.clone()).unwrap();
+    }
+}
+
+/// [Hook] building newtype structs named `Inline` itself, outside of [Serializer].
+#[derive(Clone)]
+struct Inline;
+impl Hook for Inline {
+    type X = Final;
+
+    fn newtype_struct<T>(&self, name: &'static str, value: &T) -> Result<Option<Ast>, ser::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        match name {
+            "Inline" => to_ast(value).map(Some),
+            "Lying" => Ok(Some(lying())),
+            "Deep" => Ok(Some((0..50).fold(XAst::Unit, |value, _| XAst::Seq {
+                len: Some(3),
+                ops: vec![ast::Seq::Element {
+                    value: Box::new(value),
+                }],
+            }))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Seq declaring more elements than it has.
+fn lying() -> Ast {
+    XAst::Seq {
+        len: Some(2),
+        ops: vec![ast::Seq::Element {
+            value: Box::new(XAst::Unit),
+        }],
+    }
+}
+
+/// [Hook] replacing units and fields named `name` by bigger nodes, and booleans by [lying].
+#[derive(Clone)]
+struct Bloat;
+impl Hook for Bloat {
+    type X = Final;
+
+    fn field(&self, key: &'static str, value: Ast) -> Result<Ast, ser::Error> {
+        match key {
+            "name" => Ok(XAst::Str("x".repeat(100))),
+            _ => Ok(value),
+        }
+    }
+
+    fn node(&self, node: Ast) -> Result<Ast, ser::Error> {
+        match node {
+            XAst::Unit => Ok(XAst::Seq {
+                len: Some(50),
+                ops: (0..50)
+                    .map(|_| ast::Seq::Element {
+                        value: Box::new(XAst::None),
+                    })
+                    .collect(),
+            }),
+            XAst::Bool(_) => Ok(lying()),
+            node => Ok(node),
+        }
+    }
+}
+
+#[test]
+fn limits_hook() {
+    let value = config(0);
+    let serializer = || Serializer::with_hook(Inline);
+    let serialize =
+        |serializer: Serializer<Inline>| serializer.serialize_newtype_struct("Inline", &value);
+    assert_eq!(
+        serialize(serializer().max_nodes(Some(6)).max_bytes(Some(9))).unwrap(),
+        to_ast(&value).unwrap()
+    );
+    assert_eq!(
+        serialize(serializer().max_nodes(Some(5))).unwrap_err().kind,
+        limit("nodes", 5)
+    );
+    assert_eq!(
+        serialize(serializer().max_bytes(Some(8))).unwrap_err().kind,
+        limit("bytes", 8)
+    );
+
+    let error = serializer()
+        .strict(true)
+        .serialize_newtype_struct("Lying", &())
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::InvalidProtocol(_)));
+}
+
+#[test]
+fn limits_hook_depth_len() {
+    let serialize =
+        |serializer: Serializer<Inline>| serializer.serialize_newtype_struct("Deep", &());
+    let serializer = || Serializer::with_hook(Inline);
+    assert_eq!(
+        serialize(serializer().max_depth(Some(2))).unwrap_err().kind,
+        limit("depth", 2)
+    );
+    assert_eq!(
+        serialize(serializer().max_len(Some(1))).unwrap_err().kind,
+        limit("len", 1)
+    );
+    serialize(serializer().max_depth(Some(50)).max_len(Some(3))).unwrap();
+}
+
+#[test]
+fn limits_hook_node() {
+    let serializer = || Serializer::with_hook(Bloat);
+    assert_eq!(
+        serializer()
+            .max_nodes(Some(5))
+            .serialize_unit()
+            .unwrap_err()
+            .kind,
+        limit("nodes", 5)
+    );
+    assert_eq!(
+        serializer()
+            .max_len(Some(49))
+            .serialize_unit()
+            .unwrap_err()
+            .kind,
+        limit("len", 49)
+    );
+    // the seq of one unit is counted once, each unit became 51 nodes
+    let units = [(), ()];
+    units.serialize(serializer().max_nodes(Some(103))).unwrap();
+    assert_eq!(
+        units
+            .serialize(serializer().max_nodes(Some(102)))
+            .unwrap_err()
+            .kind,
+        limit("nodes", 102)
+    );
+}
+
+#[test]
+fn limits_hook_field() {
+    let value = config(0);
+    let serializer = || Serializer::with_hook(Bloat);
+    // the 6 bytes of `config` are replaced by 100
+    value.serialize(serializer().max_bytes(Some(103))).unwrap();
+    let error = value
+        .serialize(serializer().max_bytes(Some(102)))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "bytes limit of 102 exceeded at .tags[1]");
+    let error = value
+        .serialize(serializer().max_bytes(Some(99)))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "bytes limit of 99 exceeded at .name");
+}
+
+#[test]
+fn limits_hook_strict() {
+    let serializer = || Serializer::with_hook(Bloat).strict(true);
+    let error = serializer().serialize_bool(true).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::InvalidProtocol(_)));
+    serializer().serialize_unit().unwrap();
+    config(1).serialize(serializer()).unwrap();
+
+    #[derive(Serialize)]
+    struct Flag {
+        flag: bool,
+    }
+    let error = Flag { flag: true }.serialize(serializer()).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::InvalidProtocol(_)));
+    assert!(error.to_string().ends_with(" at .flag"), "{error}");
+}
+
+#[test]
+fn limits_send() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Serializer>();
+}
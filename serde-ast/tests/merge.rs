@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::{Struct, XAst},
+    merge::{ConflictStrategy, MergeStrategy, SeqStrategy},
+    to_ast, Ast,
+};
+
+#[derive(Serialize)]
+struct Config {
+    host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    tags: Vec<String>,
+    ports: BTreeMap<u16, String>,
+}
+
+fn defaults() -> Config {
+    Config {
+        host: "localhost".to_string(),
+        user: None,
+        tags: vec!["a".to_string(), "b".to_string()],
+        ports: [(22, "ssh".to_string())].into_iter().collect(),
+    }
+}
+
+fn merge(base: Ast, overlay: Ast, strategy: &MergeStrategy) -> String {
+    let merged = XAst::merge(base, overlay, strategy).unwrap();
+    serde_json::to_string(&merged).unwrap()
+}
+
+#[test]
+fn merge_struct_same_type() {
+    let base = to_ast(&defaults()).unwrap();
+    let overlay = to_ast(&Config {
+        user: Some("root".to_string()),
+        tags: vec!["c".to_string()],
+        ports: [(80, "http".to_string())].into_iter().collect(),
+        ..defaults()
+    })
+    .unwrap();
+
+    let merged = XAst::merge(base, overlay, &MergeStrategy::default()).unwrap();
+    // the struct shape is kept, the skipped field is filled in
+    let XAst::Struct { name, len, ops } = &merged else {
+        panic!("expected struct, got {merged:?}");
+    };
+    assert_eq!(*name, "Config");
+    assert_eq!(*len, 4);
+    let keys: Vec<_> = ops
+        .iter()
+        .map(|op| match op {
+            Struct::Field { key, .. } => key.as_ref(),
+            Struct::Skip { key } => panic!("unexpected skip {key}"),
+        })
+        .collect();
+    assert_eq!(keys, ["host", "user", "tags", "ports"]);
+    assert_eq!(
+        serde_json::to_string(&merged).unwrap(),
+        r#"{"host":"localhost","user":"root","tags":["c"],"ports":{"22":"ssh","80":"http"}}"#,
+    );
+}
+
+#[test]
+fn merge_struct_with_map() {
+    let base = to_ast(&defaults()).unwrap();
+    let overlay: Ast =
+        serde_json::from_str(r#"{"host":"example.com","ports":{"22":"sftp"},"extra":true}"#)
+            .unwrap();
+    assert_eq!(
+        merge(base, overlay, &MergeStrategy::default()),
+        r#"{"host":"example.com","tags":["a","b"],"ports":{"22":"sftp"},"extra":true}"#,
+    );
+}
+
+#[test]
+fn merge_seq_strategies() {
+    let base = || to_ast(&vec![vec![1, 2], vec![3]]).unwrap();
+    let overlay = || to_ast(&vec![vec![4]]).unwrap();
+
+    let strategy = |seq| MergeStrategy {
+        seq,
+        ..Default::default()
+    };
+    assert_eq!(
+        merge(base(), overlay(), &strategy(SeqStrategy::Replace)),
+        "[[4]]"
+    );
+    assert_eq!(
+        merge(base(), overlay(), &strategy(SeqStrategy::Append)),
+        "[[1,2],[3],[4]]"
+    );
+    assert_eq!(
+        merge(base(), overlay(), &strategy(SeqStrategy::ByIndex)),
+        "[[4,2],[3]]"
+    );
+}
+
+#[test]
+fn merge_conflict() {
+    let base = to_ast(&defaults()).unwrap();
+    let overlay: Ast = serde_json::from_str(r#"{"tags":{"a":1}}"#).unwrap();
+
+    let error = XAst::merge(base.clone(), overlay.clone(), &MergeStrategy::default()).unwrap_err();
+    assert_eq!(error.path.to_string(), "/tags");
+    assert_eq!(error.to_string(), "cannot merge Map into Seq at `/tags`");
+
+    let strategy = MergeStrategy {
+        conflict: ConflictStrategy::OverlayWins,
+        ..Default::default()
+    };
+    assert_eq!(
+        merge(base, overlay, &strategy),
+        r#"{"host":"localhost","tags":{"a":1},"ports":{"22":"ssh"}}"#,
+    );
+}
+
+#[test]
+fn merge_leaf_conflict() {
+    let base = || to_ast(&"str").unwrap();
+    let overlay = || to_ast(&5u32).unwrap();
+
+    let error = XAst::merge(base(), overlay(), &MergeStrategy::default()).unwrap_err();
+    assert_eq!(error.to_string(), "cannot merge U32 into Str at ``");
+
+    let strategy = MergeStrategy {
+        conflict: ConflictStrategy::OverlayWins,
+        ..Default::default()
+    };
+    assert_eq!(
+        XAst::merge(base(), overlay(), &strategy).unwrap(),
+        XAst::U32(5)
+    );
+}
+
+#[test]
+fn merge_leaf_numbers() {
+    let merged = |base: Ast, overlay: &str| {
+        XAst::merge(
+            base,
+            serde_json::from_str(overlay).unwrap(),
+            &MergeStrategy::default(),
+        )
+    };
+    assert_eq!(merged(XAst::U16(80), "443"), Ok(XAst::U16(443)));
+    assert_eq!(merged(XAst::I8(1), "-2"), Ok(XAst::I8(-2)));
+    assert_eq!(merged(XAst::F32(1.0), "2"), Ok(XAst::F32(2.0)));
+    assert_eq!(merged(XAst::F32(1.0), "0.5"), Ok(XAst::F32(0.5)));
+
+    let error = merged(XAst::U8(1), "256").unwrap_err();
+    assert_eq!(error.to_string(), "cannot merge U64 into U8 at ``");
+    assert!(merged(XAst::U8(1), "-1").is_err());
+    assert!(merged(XAst::U8(1), "1.0").is_err());
+}
+
+#[test]
+fn merge_json_transparent() {
+    #[derive(Serialize)]
+    struct Port(u16);
+
+    #[derive(Serialize)]
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    #[derive(Serialize)]
+    struct Server {
+        port: Option<u16>,
+        name: Port,
+        mode: Mode,
+    }
+
+    let base = || {
+        to_ast(&Server {
+            port: Some(80),
+            name: Port(1),
+            mode: Mode::Fast,
+        })
+        .unwrap()
+    };
+    let merged = |overlay: &str| {
+        XAst::merge(
+            base(),
+            serde_json::from_str(overlay).unwrap(),
+            &MergeStrategy::default(),
+        )
+        .unwrap()
+    };
+    let expected = to_ast(&Server {
+        port: Some(443),
+        name: Port(2),
+        mode: Mode::Fast,
+    })
+    .unwrap();
+    assert_eq!(merged(r#"{"port":443,"name":2,"mode":"Fast"}"#), expected);
+
+    // the variant index is unknown, the JSON output matches
+    let merged = merged(r#"{"port":null,"mode":"Slow"}"#);
+    let expected = Server {
+        port: None,
+        name: Port(1),
+        mode: Mode::Slow,
+    };
+    assert_eq!(
+        serde_json::to_string(&merged).unwrap(),
+        serde_json::to_string(&expected).unwrap()
+    );
+}
+
+#[test]
+fn merge_map_entries() {
+    let base = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((2, 'b'), 2)])).unwrap();
+    let overlay = to_ast(&BTreeMap::from([((2u8, 'b'), 3u8), ((3, 'c'), 4)])).unwrap();
+    assert_eq!(
+        XAst::merge(base, overlay, &MergeStrategy::default()).unwrap(),
+        to_ast(&BTreeMap::from([
+            ((1u8, 'a'), 1u8),
+            ((2, 'b'), 3),
+            ((3, 'c'), 4)
+        ]))
+        .unwrap()
+    );
+
+    let base = to_ast(&(0..10_000u32).map(|i| (i, i)).collect::<BTreeMap<_, _>>()).unwrap();
+    let overlay: Ast = serde_json::from_str(r#"{"9999":0}"#).unwrap();
+    let merged: BTreeMap<u32, u32> =
+        serde_ast::from_ast(&XAst::merge(base, overlay, &MergeStrategy::default()).unwrap())
+            .unwrap();
+    assert_eq!(merged.len(), 10_000);
+    assert_eq!(merged[&9999], 0);
+}
@@ -0,0 +1,93 @@
+use std::collections::{BTreeMap, HashMap};
+
+use quickcheck::quickcheck;
+use serde::Serialize;
+
+use serde_ast::{
+    normalize::{self, CollapseNewtypes, DropSkips, FillLengths, Pass, SortMapEntries},
+    text, to_ast, Ast,
+};
+
+fn apply(text: &str, mut pass: impl Pass<serde_ast::ast::Final>) -> String {
+    let mut ast: Ast = text::from_str(text).expect("parse text");
+    pass.apply(&mut ast);
+    text::to_string(&ast)
+}
+
+#[test]
+fn normalize_collapse_newtypes() {
+    assert_eq!(
+        apply(
+            "[Meters(u8 1), Wrapper(Meters(u8 2)), Unit::Variant(Meters(u8 3))]",
+            CollapseNewtypes
+        ),
+        "[u8 1, u8 2, Unit::Variant@0(u8 3)]"
+    );
+}
+
+#[test]
+fn normalize_drop_skips() {
+    assert_eq!(
+        apply("A { skip a, b: u8 1, skip c }", DropSkips),
+        "A { b: u8 1 }"
+    );
+    assert_eq!(
+        apply("E::V { skip a, b: u8 1 }", DropSkips),
+        "E::V@0 { b: u8 1 }"
+    );
+}
+
+#[test]
+fn normalize_fill_lengths() {
+    assert_eq!(
+        apply(
+            r#"[{ "a" => [; len none]; len none }; len none]"#,
+            FillLengths
+        ),
+        r#"[{ "a" => [] }]"#
+    );
+}
+
+#[test]
+fn normalize_sort_map_entries() {
+    assert_eq!(
+        apply(
+            r#"{ "b" => { "z" => (), "y" => () }, "a" => () }"#,
+            SortMapEntries
+        ),
+        r#"{ "a" => (), "b" => { "y" => (), "z" => () } }"#
+    );
+    assert_eq!(
+        apply(r#"{ "b" => (), "a" => _ }"#, SortMapEntries),
+        r#"{ "b" => (), "a" => _ }"#
+    );
+}
+
+#[test]
+fn normalize_canonical() {
+    #[derive(Serialize)]
+    struct Id(u32);
+
+    let hash_map: HashMap<_, _> = (0..32u32).map(|i| (i.to_string(), Id(i))).collect();
+    let btree_map: BTreeMap<_, _> = (0..32u32).map(|i| (i.to_string(), i)).collect();
+
+    let mut a = to_ast(&hash_map).unwrap();
+    let mut b = to_ast(&btree_map).unwrap();
+    assert_ne!(a, b);
+    normalize::canonical().apply(&mut a);
+    normalize::canonical().apply(&mut b);
+    assert_eq!(a, b);
+}
+
+type Example = (Option<String>, HashMap<i8, Vec<u8>>);
+
+quickcheck! {
+    fn normalize_idempotent(value: Vec<Example>) -> bool {
+        let mut once = to_ast(&value).expect("serialize to_ast");
+        let mut pass = (CollapseNewtypes, DropSkips, FillLengths, SortMapEntries);
+        pass.apply(&mut once);
+        let mut twice = once.clone();
+        pass.apply(&mut twice);
+        once == twice
+    }
+}
@@ -0,0 +1,110 @@
+use std::{cell::Cell, collections::BTreeMap};
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::XAst,
+    pretty::Printer,
+    ser::{Error, ErrorKind, Failure},
+    to_ast, to_ast_partial,
+};
+
+struct Fails;
+impl Serialize for Fails {
+    fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Err(serde::ser::Error::custom("fails"))
+    }
+}
+
+#[derive(Serialize)]
+struct Config {
+    name: &'static str,
+    ports: Vec<Port>,
+    debug: bool,
+}
+
+#[derive(Serialize)]
+enum Port {
+    Fixed(u16),
+    Broken(Fails),
+}
+
+fn print(ast: &XAst<Failure>) -> String {
+    Printer::new()
+        .indent(None)
+        .extension(|x: &Failure, f: &mut std::fmt::Formatter<'_>| write!(f, "<{}>", x.0))
+        .display(ast)
+        .to_string()
+}
+
+#[test]
+fn partial_complete() {
+    let value = (1, "two");
+    assert_eq!(to_ast_partial(&value).unwrap(), to_ast(&value).unwrap());
+}
+
+#[test]
+fn partial_marker() {
+    let config = Config {
+        name: "config",
+        ports: vec![Port::Fixed(80), Port::Broken(Fails), Port::Broken(Fails)],
+        debug: true,
+    };
+    let error = to_ast_partial(&config).unwrap_err();
+    assert_eq!(error.error, to_ast(&config).unwrap_err());
+    assert_eq!(error.to_string(), "fails at .ports[1]::Broken");
+    assert_eq!(
+        print(&error.partial),
+        r#"Config { name: "config", ports: [Port::Fixed(80u16), Port::Broken(<fails>), Port::Broken(<fails>)], debug: true }"#
+    );
+    assert_eq!(
+        serde_json::to_string(&error.partial)
+            .unwrap_err()
+            .to_string(),
+        "fails"
+    );
+}
+
+#[test]
+fn partial_root() {
+    let error = to_ast_partial(&Fails).unwrap_err();
+    assert_eq!(
+        *error.partial,
+        XAst::X(Failure(Error::new(ErrorKind::Custom("fails".to_string()))))
+    );
+}
+
+struct Counted<'a>(&'a Cell<usize>);
+impl Serialize for Counted<'_> {
+    fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.set(self.0.get() + 1);
+        Err(serde::ser::Error::custom("fails"))
+    }
+}
+
+#[test]
+fn partial_single_pass() {
+    let calls = Cell::new(0);
+    let error = to_ast_partial(&[(1, Counted(&calls))]).unwrap_err();
+    assert_eq!(calls.get(), 1);
+    assert_eq!(error.to_string(), "fails at [0][1]");
+}
+
+#[test]
+fn partial_path() {
+    let value: BTreeMap<_, _> = [
+        ("a", vec![Port::Fixed(80)]),
+        ("b", vec![Port::Fixed(81), Port::Broken(Fails)]),
+    ]
+    .into_iter()
+    .collect();
+    let error = to_ast_partial(&value).unwrap_err();
+    assert_eq!(error.error, to_ast(&value).unwrap_err());
+    assert_eq!(error.to_string(), r#"fails at ["b"][1]::Broken"#);
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::XAst,
+    patch::{ErrorKind, Operation, Patch},
+    path::{self, AstPath},
+    text, to_ast, Ast, Readability, Serializer,
+};
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    tags: Vec<String>,
+    env: BTreeMap<String, String>,
+}
+
+fn config() -> Ast {
+    to_ast(&Config {
+        name: "example".to_string(),
+        tags: vec!["a".to_string(), "b".to_string()],
+        env: [("HOME".to_string(), "/root".to_string())]
+            .into_iter()
+            .collect(),
+    })
+    .unwrap()
+}
+
+fn path(s: &str) -> AstPath {
+    s.parse().unwrap()
+}
+
+#[test]
+fn patch_operations() {
+    let mut ast = config();
+    let patch = Patch::new(vec![
+        Operation::Add {
+            path: path("/tags/0"),
+            value: XAst::Str("first".to_string()),
+        },
+        Operation::Remove {
+            path: path("/tags/2"),
+        },
+        Operation::Replace {
+            path: path("/name"),
+            value: XAst::Str("patched".to_string()),
+        },
+        Operation::Copy {
+            from: path("/env/HOME"),
+            path: path("/env/USER_HOME"),
+        },
+        Operation::Move {
+            from: path("/env/HOME"),
+            path: path("/tags/-"),
+        },
+        Operation::Test {
+            path: path("/tags/2"),
+            value: XAst::Str("/root".to_string()),
+        },
+    ]);
+    patch.apply(&mut ast).unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&ast).unwrap(),
+        r#"{"name":"patched","tags":["first","a","/root"],"env":{"USER_HOME":"/root"}}"#,
+    );
+}
+
+#[test]
+fn patch_json() {
+    let json = r#"[{"op":"add","path":"/env/PATH","value":"/bin"},{"op":"move","from":"/tags/0","path":"/tags/1"}]"#;
+    let patch: Patch = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        patch.operations(),
+        &[
+            Operation::Add {
+                path: path("/env/PATH"),
+                value: XAst::Str("/bin".to_string()),
+            },
+            Operation::Move {
+                from: path("/tags/0"),
+                path: path("/tags/1"),
+            },
+        ],
+    );
+    assert_eq!(serde_json::to_string(&patch).unwrap(), json);
+
+    let mut ast = config();
+    patch.apply(&mut ast).unwrap();
+    assert_eq!(
+        serde_json::to_string(&ast).unwrap(),
+        r#"{"name":"example","tags":["b","a"],"env":{"HOME":"/root","PATH":"/bin"}}"#,
+    );
+}
+
+#[test]
+fn patch_error_index() {
+    let mut ast = config();
+    let patch = Patch::new(vec![
+        Operation::Remove {
+            path: path("/name"),
+        },
+        Operation::Test {
+            path: path("/tags/0"),
+            value: XAst::Str("b".to_string()),
+        },
+    ]);
+    let error = patch.apply(&mut ast).unwrap_err();
+    assert_eq!(error.index, 1);
+    assert_eq!(
+        error.kind,
+        ErrorKind::TestFailed {
+            path: path("/tags/0")
+        },
+    );
+    assert_eq!(
+        error.to_string(),
+        "operation 1 failed: test failed at `/tags/0`"
+    );
+    // atomic, the first operation is not applied
+    assert_eq!(ast, config());
+
+    let patch = Patch::new(vec![Operation::Replace {
+        path: path("/missing"),
+        value: XAst::Unit,
+    }]);
+    let error = patch.apply(&mut ast).unwrap_err();
+    assert_eq!(error.index, 0);
+    assert!(matches!(
+        error.kind,
+        ErrorKind::Path(path::Error {
+            kind: path::ErrorKind::MissingKey,
+            ..
+        })
+    ));
+
+    let patch = Patch::new(vec![Operation::Move {
+        from: path("/env"),
+        path: path("/env/nested"),
+    }]);
+    let error = patch.apply(&mut ast).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::MoveIntoChild { .. }));
+}
+
+#[test]
+fn patch_test_json_values() {
+    #[derive(Serialize)]
+    struct Limits {
+        port: u8,
+        ratio: Option<f32>,
+        env: BTreeMap<String, String>,
+        nested: Option<Config>,
+    }
+    let mut ast = to_ast(&Limits {
+        port: 1,
+        ratio: Some(0.5),
+        env: [("HOME".to_string(), "/root".to_string())]
+            .into_iter()
+            .collect(),
+        nested: None,
+    })
+    .unwrap();
+
+    let patch: Patch = serde_json::from_str(
+        r#"[
+            {"op":"test","path":"/port","value":1},
+            {"op":"test","path":"/port","value":1.0},
+            {"op":"test","path":"/ratio","value":0.5},
+            {"op":"test","path":"/env","value":{"HOME":"/root"}},
+            {"op":"test","path":"/nested","value":null},
+            {"op":"test","path":"","value":{"nested":null,"ratio":0.5,"env":{"HOME":"/root"},"port":1}}
+        ]"#,
+    )
+    .unwrap();
+    patch.apply(&mut ast).unwrap();
+
+    for value in [r#""1""#, "2", "-1", r#"{"HOME":"/root","USER":"root"}"#] {
+        let json = format!(r#"[{{"op":"test","path":"/port","value":{value}}}]"#);
+        let patch: Patch = serde_json::from_str(&json).unwrap();
+        assert!(patch.apply(&mut ast).is_err(), "{value}");
+    }
+    let patch: Patch = serde_json::from_str(r#"[{"op":"test","path":"/env","value":{}}]"#).unwrap();
+    assert!(patch.apply(&mut ast).is_err());
+}
+
+#[test]
+fn patch_test_composite_keys() {
+    let mut ast = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((2, 'b'), 2)])).unwrap();
+    let same = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((2, 'b'), 2)])).unwrap();
+    let other = to_ast(&BTreeMap::from([((1u8, 'a'), 1u8), ((3, 'b'), 2)])).unwrap();
+
+    let test = |value| {
+        Patch::new(vec![Operation::Test {
+            path: AstPath::root(),
+            value,
+        }])
+    };
+    test(same).apply(&mut ast).unwrap();
+    let error = test(other).apply(&mut ast).unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::TestFailed { .. }));
+}
+
+#[test]
+fn patch_through_readability() {
+    let ip = std::net::Ipv4Addr::new(127, 0, 0, 1);
+    let mut ast = Serializer::new(Readability::HumanReadable)
+        .serialize_both(&vec![ip])
+        .unwrap();
+
+    let patch: Patch =
+        serde_json::from_str(r#"[{"op":"test","path":"/0","value":"127.0.0.1"}]"#).unwrap();
+    patch.apply(&mut ast).unwrap();
+
+    let patch: Patch =
+        serde_json::from_str(r#"[{"op":"replace","path":"/0/0","value":10}]"#).unwrap();
+    let error = patch.apply(&mut ast).unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ErrorKind::Path(path::Error {
+            kind: path::ErrorKind::Readability,
+            ..
+        })
+    ));
+
+    // replacing the node as a whole replaces both modes
+    let replaced = Serializer::new(Readability::HumanReadable)
+        .serialize_both(&std::net::Ipv4Addr::new(10, 0, 0, 1))
+        .unwrap();
+    Patch::new(vec![Operation::Replace {
+        path: path("/0"),
+        value: replaced,
+    }])
+    .apply(&mut ast)
+    .unwrap();
+    let compact = ast
+        .serialize(Serializer::new(Readability::Compact))
+        .unwrap();
+    assert_eq!(text::to_string(&compact), "[(u8 10, u8 0, u8 0, u8 1)]");
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::XAst,
+    path::{AstPath, ErrorKind, ParseError},
+    to_ast, Ast,
+};
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+    ports: BTreeMap<u16, String>,
+    env: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+fn config() -> Ast {
+    let config = Config {
+        name: "example".to_string(),
+        servers: vec![
+            Server {
+                host: "a".to_string(),
+                port: 80,
+            },
+            Server {
+                host: "b".to_string(),
+                port: 8080,
+            },
+        ],
+        ports: [(22, "ssh".to_string())].into_iter().collect(),
+        env: Some(
+            [("a/b~c".to_string(), "1".to_string())]
+                .into_iter()
+                .collect(),
+        ),
+    };
+    to_ast(&config).expect("serialize to_ast")
+}
+
+fn path(s: &str) -> AstPath {
+    s.parse().expect("valid path")
+}
+
+fn json(ast: &Ast) -> String {
+    serde_json::to_string(ast).expect("serde_json::to_string")
+}
+
+#[test]
+fn parse_display() {
+    for s in ["", "/", "/a", "/servers/0/port", "/a~1b~0c", "//x"] {
+        assert_eq!(path(s).to_string(), s);
+    }
+    assert_eq!(path("/a~1b~0c").segments(), ["a/b~c"]);
+    assert_eq!(AstPath::parse("a"), Err(ParseError::MissingSlash));
+    assert!(matches!(
+        AstPath::parse("/a~2"),
+        Err(ParseError::InvalidEscape(_))
+    ));
+}
+
+#[test]
+fn get() {
+    let ast = config();
+    assert_eq!(ast.get(&path("")).expect("root"), &ast);
+    assert_eq!(
+        ast.get(&path("/servers/1/port")).expect("get"),
+        &XAst::U16(8080)
+    );
+    assert_eq!(
+        ast.get(&path("/ports/22")).expect("get"),
+        &XAst::Str("ssh".to_string())
+    );
+    assert_eq!(
+        ast.get(&path("/env/a~1b~0c")).expect("get through Some"),
+        &XAst::Str("1".to_string())
+    );
+}
+
+#[test]
+fn get_errors() {
+    let ast = config();
+
+    let error = ast
+        .get(&path("/servers/2/port"))
+        .expect_err("out of bounds");
+    assert_eq!(error.path, path("/servers/2"));
+    assert_eq!(error.kind, ErrorKind::OutOfBounds);
+
+    let error = ast.get(&path("/servers/01")).expect_err("leading zero");
+    assert_eq!(error.kind, ErrorKind::InvalidIndex);
+
+    let error = ast.get(&path("/servers/0/user")).expect_err("missing key");
+    assert_eq!(error.path, path("/servers/0/user"));
+    assert_eq!(error.kind, ErrorKind::MissingKey);
+
+    let error = ast.get(&path("/name/first")).expect_err("leaf");
+    assert_eq!(error.kind, ErrorKind::NotAContainer);
+    assert_eq!(error.to_string(), "node has no children at `/name/first`");
+}
+
+#[test]
+fn get_mut() {
+    let mut ast = config();
+    *ast.get_mut(&path("/servers/0/host")).expect("get_mut") = XAst::Str("c".to_string());
+    assert_eq!(
+        ast.get(&path("/servers/0/host")).expect("get"),
+        &XAst::Str("c".to_string())
+    );
+}
+
+#[test]
+fn insert() {
+    let mut ast = config();
+
+    let old = ast
+        .insert(&path("/name"), XAst::Str("renamed".to_string()))
+        .expect("replace field");
+    assert_eq!(old, Some(XAst::Str("example".to_string())));
+
+    ast.insert(&path("/version"), XAst::U8(2))
+        .expect("append field");
+    ast.insert(&path("/ports/443"), XAst::Str("https".to_string()))
+        .expect("append entry");
+    ast.insert(&path("/servers/-"), XAst::Unit)
+        .expect("append element");
+    ast.insert(&path("/servers/0"), XAst::None)
+        .expect("insert element");
+
+    assert_eq!(
+        json(&ast),
+        r#"{"name":"renamed","servers":[null,{"host":"a","port":80},{"host":"b","port":8080},null],"ports":{"22":"ssh","443":"https"},"env":{"a/b~c":"1"},"version":2}"#
+    );
+    let XAst::Struct { len, .. } = &ast else {
+        panic!("expected a struct");
+    };
+    assert_eq!(*len, 5);
+    let XAst::Seq { len, .. } = ast.get(&path("/servers")).expect("get") else {
+        panic!("expected a seq");
+    };
+    assert_eq!(*len, Some(4));
+
+    let error = ast
+        .insert(&path("/servers/5"), XAst::Unit)
+        .expect_err("out of bounds");
+    assert_eq!(error.kind, ErrorKind::OutOfBounds);
+}
+
+#[test]
+fn remove() {
+    let mut ast = config();
+
+    let removed = ast.remove(&path("/servers/0")).expect("remove element");
+    assert_eq!(json(&removed), r#"{"host":"a","port":80}"#);
+    ast.remove(&path("/ports/22")).expect("remove entry");
+    ast.remove(&path("/env")).expect("remove field");
+
+    assert_eq!(
+        json(&ast),
+        r#"{"name":"example","servers":[{"host":"b","port":8080}],"ports":{}}"#
+    );
+
+    let error = ast.remove(&path("")).expect_err("remove root");
+    assert_eq!(error.kind, ErrorKind::RemoveRoot);
+}
+
+#[test]
+fn insert_map_key_type() {
+    let mut ast = config();
+    ast.insert(&path("/ports/5"), XAst::Str("rje".to_string()))
+        .expect("append entry");
+    let ports: BTreeMap<u16, String> =
+        serde_ast::from_ast(ast.get(&path("/ports")).expect("get")).expect("from_ast");
+    assert_eq!(ports.get(&5).map(String::as_str), Some("rje"));
+
+    let error = ast
+        .insert(&path("/ports/http"), XAst::Unit)
+        .expect_err("not a u16");
+    assert_eq!(error.kind, ErrorKind::InvalidKey);
+
+    ast.insert(&path("/env/5"), XAst::Str("2".to_string()))
+        .expect("append entry");
+    assert_eq!(
+        ast.get(&path("/env/5")).expect("get"),
+        &XAst::Str("2".to_string())
+    );
+}
+
+#[test]
+fn readability_human_readable() {
+    let mut ast = XAst::Seq {
+        len: Some(1),
+        ops: vec![serde_ast::ast::Seq::Element {
+            value: Box::new(XAst::Readability {
+                human_readable: Box::new(to_ast(&[("a", 1u8)]).unwrap()),
+                compact: Box::new(XAst::U8(1)),
+            }),
+        }],
+    };
+    assert_eq!(ast.get(&path("/0/0/1")).expect("get"), &XAst::U8(1));
+
+    // editing one mode would leave the other one stale
+    let error = ast.get_mut(&path("/0/0/1")).expect_err("get_mut");
+    assert_eq!(error.kind, ErrorKind::Readability);
+    let error = ast.insert(&path("/0/-"), XAst::Unit).expect_err("insert");
+    assert_eq!(
+        (error.path, error.kind),
+        (path("/0/-"), ErrorKind::Readability)
+    );
+    let error = ast.remove(&path("/0/0")).expect_err("remove");
+    assert_eq!(error.kind, ErrorKind::Readability);
+
+    *ast.get_mut(&path("/0")).expect("replace as a whole") = XAst::U8(2);
+    assert_eq!(json(&ast), "[2]");
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use quickcheck::quickcheck;
+
+use serde_ast::{
+    ast::{Map, Struct, XAst},
+    persist::{self, ErrorKind, Repr},
+    text, to_ast, Ast,
+};
+
+mod common;
+
+use common::{Config, Id};
+
+/// [common::config] with the edge cases of the binary encoding.
+fn config() -> Config {
+    Config {
+        scale: f64::NAN,
+        id: Id(u64::MAX),
+        ..common::config()
+    }
+}
+
+/// Compare through text, NaN is not equal to itself.
+fn assert_same(a: &Ast, b: &Ast) {
+    assert_eq!(text::to_string(a), text::to_string(b));
+}
+
+#[test]
+fn persist_json() {
+    let ast = to_ast(&config()).unwrap();
+    let json = serde_json::to_string(&Repr(&ast)).unwrap();
+    let Repr(decoded): Repr = serde_json::from_str(&json).unwrap();
+    assert_same(&decoded, &ast);
+
+    let json = serde_json::to_value(Repr(XAst::F32(f32::NEG_INFINITY))).unwrap();
+    assert_eq!(json, serde_json::json!({ "F32": "0xff800000" }));
+    let Repr(decoded): Repr = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded, XAst::F32(f32::NEG_INFINITY));
+
+    let error = serde_json::from_str::<Repr>(r#"{"F64":"nan"}"#).unwrap_err();
+    assert!(error.to_string().contains("invalid float bits `nan`"));
+}
+
+#[test]
+fn persist_bytes() {
+    let ast = to_ast(&config()).unwrap();
+    let bytes = persist::to_bytes(&ast);
+    assert_same(&persist::from_bytes(&bytes).unwrap(), &ast);
+
+    assert_eq!(
+        persist::to_bytes(&XAst::Map {
+            len: None,
+            ops: vec![Map::Key {
+                key: Box::new(XAst::U16(0x1234)),
+            }],
+        }),
+        b"SAST\x02\x19\x00\x01\x00\x06\x34\x12",
+    );
+    let ast = XAst::Struct {
+        name: "S".into(),
+        len: 300,
+        ops: vec![Struct::Skip { key: "k".into() }],
+    };
+    let bytes = persist::to_bytes(&ast);
+    assert_eq!(bytes, b"SAST\x02\x1a\x01S\xac\x02\x01\x01\x01k");
+    assert_eq!(persist::from_bytes(&bytes).unwrap(), ast);
+}
+
+#[test]
+fn persist_bytes_errors() {
+    let error = |bytes: &[u8]| persist::from_bytes(bytes).unwrap_err();
+
+    assert_eq!(error(b"JSON\x01\x10").kind, ErrorKind::InvalidMagic);
+    assert_eq!(
+        error(b"SAST\x03\x10").kind,
+        ErrorKind::UnsupportedVersion(3)
+    );
+    let e = error(b"SAST\x01\x0c\x05ab");
+    assert_eq!((e.offset, e.kind), (9, ErrorKind::UnexpectedEnd));
+    let e = error(b"SAST\x01\x0f\x1f");
+    assert_eq!((e.offset, e.kind), (6, ErrorKind::InvalidTag(31)));
+    assert_eq!(error(b"SAST\x01\x0c\x01\xff").kind, ErrorKind::InvalidUtf8);
+    assert_eq!(
+        error(b"SAST\x01\x0b\x00\xd8\x00\x00").kind,
+        ErrorKind::InvalidChar(0xd800)
+    );
+    let e = error(b"SAST\x01\x10\x10");
+    assert_eq!(e.to_string(), "trailing bytes at byte 6");
+    assert_eq!((e.offset, e.kind), (6, ErrorKind::TrailingBytes));
+    assert_eq!(
+        error(b"SAST\x01\x0d\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01").kind,
+        ErrorKind::LengthOverflow
+    );
+}
+
+#[test]
+fn persist_bytes_versions() {
+    // version 1 has no I128, U128 and Readability tags, but is otherwise the same
+    assert_eq!(
+        persist::from_bytes(b"SAST\x01\x05\x07").unwrap(),
+        XAst::U8(7)
+    );
+    assert_eq!(
+        persist::from_bytes(b"SAST\x02\x05\x07").unwrap(),
+        XAst::U8(7)
+    );
+    assert!(persist::to_bytes(&XAst::U128(1)).starts_with(b"SAST\x02\x1d"));
+}
+
+#[test]
+fn persist_bytes_depth() {
+    let nested = |depth: usize| {
+        let mut bytes = b"SAST\x02".to_vec();
+        bytes.resize(5 + depth, 0x0f);
+        bytes.push(0x0e);
+        bytes
+    };
+
+    let mut ast = persist::from_bytes(&nested(persist::MAX_DEPTH - 1)).unwrap();
+    let mut depth = 1;
+    while let XAst::Some(value) = ast {
+        ast = *value;
+        depth += 1;
+    }
+    assert_eq!((depth, ast), (persist::MAX_DEPTH, XAst::None));
+
+    let e = persist::from_bytes(&nested(persist::MAX_DEPTH)).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "depth limit of 128 exceeded at byte {}",
+            5 + persist::MAX_DEPTH
+        )
+    );
+    assert_eq!(
+        (e.offset, e.kind),
+        (5 + persist::MAX_DEPTH, ErrorKind::DepthLimit)
+    );
+
+    let e = persist::from_bytes(&nested(1_000_000)).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::DepthLimit);
+
+    // nested containers count their elements
+    let mut bytes = b"SAST\x02".to_vec();
+    for _ in 0..persist::MAX_DEPTH {
+        bytes.extend_from_slice(b"\x15\x00\x01");
+    }
+    bytes.push(0x0e);
+    assert_eq!(
+        persist::from_bytes(&bytes).unwrap_err().kind,
+        ErrorKind::DepthLimit
+    );
+}
+
+type Example = (
+    String,
+    Option<char>,
+    (i64, u8, f32),
+    Vec<u8>,
+    BTreeMap<i16, bool>,
+);
+
+quickcheck! {
+    fn persist_roundtrip(example: Vec<Example>) -> bool {
+        let ast = to_ast(&example).expect("serialize to_ast");
+        let expected = text::to_string(&ast);
+        let bytes = persist::from_bytes(&persist::to_bytes(&ast)).expect("decode bytes");
+        let json = serde_json::to_string(&Repr(&ast)).expect("serialize json");
+        let Repr(json) = serde_json::from_str(&json).expect("deserialize json");
+        text::to_string(&bytes) == expected && text::to_string(&json) == expected
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use serde_ast::{ast::XAst, pretty::Printer, to_ast};
+
+mod common;
+
+use common::config;
+
+#[test]
+fn pretty_default() {
+    let ast = to_ast(&config()).unwrap();
+    assert_eq!(
+        ast.to_string(),
+        r#"Config {
+  name: Some("example"),
+  skip user,
+  mode: Mode::Range {
+    min: 1.5f32,
+  },
+  pair: (
+    1u8,
+    'c',
+  ),
+  ports: {
+    22u16 => Mode::Off,
+    80u16 => Mode::Level(-1i32),
+    443u16 => Mode::Pair(
+      -2i8,
+      3i16,
+    ),
+  },
+  scale: 0.5f64,
+  id: Id(7u64),
+}"#,
+    );
+}
+
+#[test]
+fn pretty_single_line() {
+    let ast = to_ast(&config()).unwrap();
+    let printer = Printer::new()
+        .indent(None)
+        .show_len(true)
+        .show_variant_index(true);
+    assert_eq!(
+        printer.display(&ast).to_string(),
+        "Config { name: Some(\"example\"), skip user, mode: Mode::Range@3 { min: 1.5f32; len 1 }, \
+         pair: (1u8, 'c'; len 2), ports: { 22u16 => Mode::Off@0, 80u16 => Mode::Level@1(-1i32), \
+         443u16 => Mode::Pair@2(-2i8, 3i16; len 2); len 3 }, scale: 0.5f64, id: Id(7u64); len 6 }",
+    );
+
+    let ast = to_ast(&(1u8,)).unwrap();
+    assert_eq!(
+        Printer::new().indent(None).display(&ast).to_string(),
+        "(1u8,)"
+    );
+}
+
+#[test]
+fn pretty_limits() {
+    let ast = to_ast(&config()).unwrap();
+    let printer = Printer::new()
+        .indent(None)
+        .max_depth(Some(1))
+        .max_str_len(Some(2));
+    assert_eq!(
+        printer.display(&ast).to_string(),
+        "Config { name: Some(\"ex…\"), skip user, mode: Mode::Range { … }, pair: (…), ports: { … }, \
+         scale: 0.5f64, id: Id(7u64) }",
+    );
+
+    let ast = to_ast(&Bytes(b"abc")).unwrap();
+    assert_eq!(printer.display(&ast).to_string(), "b\"ab…\"");
+}
+
+#[test]
+fn pretty_colors() {
+    let ast = to_ast(&vec![true]).unwrap();
+    let printer = Printer::new().indent(None).colors(true);
+    assert_eq!(printer.display(&ast).to_string(), "[\x1b[33mtrue\x1b[0m]");
+}
+
+#[test]
+fn pretty_extension() {
+    #[derive(Debug)]
+    struct Secret;
+    let ast: XAst<Secret> = XAst::Seq {
+        len: Some(1),
+        ops: vec![serde_ast::ast::Seq::Element {
+            value: Box::new(XAst::X(Secret)),
+        }],
+    };
+    assert_eq!(ast.to_string(), "[\n  Secret,\n]");
+
+    let printer = Printer::new()
+        .indent(None)
+        .extension(|_: &Secret, f: &mut std::fmt::Formatter<'_>| f.write_str("<redacted>"));
+    assert_eq!(printer.display(&ast).to_string(), "[<redacted>]");
+}
+
+struct Bytes(&'static [u8]);
+impl Serialize for Bytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
@@ -0,0 +1,98 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use serde::{Deserialize, Serialize};
+
+use serde_ast::{
+    ast::{Struct, XAst},
+    from_ast, persist, text, to_ast, Ast, Readability, Serializer,
+};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Host {
+    name: String,
+    ip: IpAddr,
+}
+
+fn host() -> Host {
+    Host {
+        name: "localhost".to_string(),
+        ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+    }
+}
+
+fn serialize<T>(value: &T, readability: Readability) -> Ast
+where
+    T: Serialize + ?Sized,
+{
+    value
+        .serialize(Serializer::new(readability))
+        .expect("serialize")
+}
+
+#[test]
+fn readability_modes() {
+    let ip = Ipv4Addr::new(10, 0, 0, 1);
+    assert_eq!(
+        serialize(&ip, Readability::HumanReadable),
+        XAst::Str("10.0.0.1".to_string())
+    );
+    assert_eq!(
+        text::to_string(&serialize(&ip, Readability::Compact)),
+        "(u8 10, u8 0, u8 0, u8 1)"
+    );
+    assert_eq!(
+        serialize(&ip, Readability::HumanReadable),
+        to_ast(&ip).unwrap()
+    );
+}
+
+#[test]
+fn readability_both() {
+    let ast = Serializer::new(Readability::Compact)
+        .serialize_both(&host())
+        .unwrap();
+    let XAst::Struct { ops, .. } = &ast else {
+        panic!("expected a struct, got {ast:?}")
+    };
+    assert!(
+        matches!(&ops[0], Struct::Field { value, .. } if **value == XAst::Str("localhost".to_string()))
+    );
+    assert!(
+        matches!(&ops[1], Struct::Field { value, .. } if matches!(**value, XAst::Readability { .. }))
+    );
+
+    for readability in [Readability::HumanReadable, Readability::Compact] {
+        assert_eq!(
+            serialize(&ast, readability),
+            serialize(&host(), readability)
+        );
+    }
+    assert_eq!(
+        serde_json::to_string(&ast).unwrap(),
+        serde_json::to_string(&host()).unwrap()
+    );
+    assert_eq!(from_ast::<Host>(&ast).unwrap(), host());
+}
+
+#[test]
+fn readability_both_same() {
+    let value = ("same", [1u8, 2]);
+    let ast = Serializer::new(Readability::HumanReadable)
+        .serialize_both(&value)
+        .unwrap();
+    assert_eq!(ast, to_ast(&value).unwrap());
+}
+
+#[test]
+fn readability_persist() {
+    let ast = Serializer::new(Readability::HumanReadable)
+        .serialize_both(&host())
+        .unwrap();
+    let text = text::to_string(&ast);
+    assert_eq!(
+        text,
+        r#"Host { name: "localhost", ip: readable("127.0.0.1") compact(IpAddr::V4@0((u8 127, u8 0, u8 0, u8 1))) }"#
+    );
+    assert_eq!(text::from_str(&text).unwrap(), ast);
+    assert_eq!(persist::from_bytes(&persist::to_bytes(&ast)).unwrap(), ast);
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    path::AstPath,
+    ser::{Error, ErrorKind, Segment},
+    to_ast,
+};
+
+struct Fails;
+impl Serialize for Fails {
+    fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Err(serde::ser::Error::custom("fails"))
+    }
+}
+
+#[derive(Serialize)]
+struct Config {
+    name: &'static str,
+    servers: Vec<Server>,
+}
+
+#[derive(Serialize)]
+enum Server {
+    Local(Fails),
+    Remote { host: &'static str, port: Fails },
+}
+
+#[test]
+fn ser_error_root() {
+    let error = to_ast(&Fails).unwrap_err();
+    assert_eq!(error, Error::new(ErrorKind::Custom("fails".to_string())));
+    assert_eq!(error.to_string(), "fails");
+    assert!(error.ast_path().is_root());
+}
+
+#[test]
+fn ser_error_path() {
+    let config = Config {
+        name: "config",
+        servers: vec![Server::Remote {
+            host: "localhost",
+            port: Fails,
+        }],
+    };
+    let error = to_ast(&config).unwrap_err();
+    assert_eq!(
+        error.path,
+        vec![
+            Segment::Field("servers"),
+            Segment::Index(0),
+            Segment::Variant("Remote"),
+            Segment::Field("port"),
+        ]
+    );
+    assert_eq!(error.to_string(), "fails at .servers[0]::Remote.port");
+    assert_eq!(
+        error.ast_path(),
+        "/servers/0/port".parse::<AstPath>().unwrap()
+    );
+
+    let error = to_ast(&(1, Server::Local(Fails))).unwrap_err();
+    assert_eq!(error.to_string(), "fails at [1]::Local");
+}
+
+#[test]
+fn ser_error_map() {
+    let map = BTreeMap::from([("a", None), ("b/c", Some(Fails))]);
+    let error = to_ast(&map).unwrap_err();
+    assert_eq!(error.to_string(), r#"fails at ["b/c"]"#);
+    assert_eq!(error.ast_path().to_string(), "/b~1c");
+
+    let map = BTreeMap::from([((), Fails)]);
+    let error = to_ast(&map).unwrap_err();
+    assert_eq!(error.path, vec![Segment::Index(0)]);
+}
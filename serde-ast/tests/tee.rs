@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{ser::TeeSerializer, text, to_ast, Ast};
+
+mod common;
+
+use common::config;
+
+fn tee_json<T>(value: &T) -> (Result<Ast, Option<Ast>>, String)
+where
+    T: Serialize,
+{
+    let mut json = Vec::new();
+    let result = value
+        .serialize(TeeSerializer::new(&mut serde_json::Serializer::new(
+            &mut json,
+        )))
+        .map(|((), ast)| ast)
+        .map_err(|e| e.partial);
+    (result, String::from_utf8(json).unwrap())
+}
+
+#[test]
+fn tee_json_forwards() {
+    let (ast, json) = tee_json(&config());
+    assert_eq!(ast.unwrap(), to_ast(&config()).unwrap());
+    assert_eq!(json, serde_json::to_string(&config()).unwrap());
+}
+
+#[derive(Serialize)]
+struct Invalid {
+    a: u8,
+    keys: BTreeMap<(u8, u8), u8>,
+    b: u8,
+}
+
+#[test]
+fn tee_json_partial() {
+    let invalid = Invalid {
+        a: 1,
+        keys: [((2, 3), 4)].into_iter().collect(),
+        b: 5,
+    };
+    let (partial, json) = tee_json(&invalid);
+    assert_eq!(json, r#"{"a":1,"keys":{"#);
+    assert_eq!(
+        text::to_string(&partial.unwrap_err().expect("partial ast")),
+        "Invalid { a: u8 1, keys: { (,; len 2) => _ }; len 3 }"
+    );
+}
+
+#[test]
+fn tee_custom_error() {
+    struct Fails;
+    impl Serialize for Fails {
+        fn serialize<S>(&self, _: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            Err(serde::ser::Error::custom("fails"))
+        }
+    }
+
+    let (partial, _) = tee_json(&vec![Fails]);
+    assert_eq!(
+        text::to_string(&partial.unwrap_err().expect("partial ast")),
+        "[; len 1]"
+    );
+    let (partial, _) = tee_json(&Fails);
+    assert_eq!(partial.unwrap_err(), None);
+}
+
+/// Compact serializer ignoring the value of `Some`, like a format writing only presence.
+struct SkipSome;
+macro_rules! skip_leaves {
+    ($($method:ident: $ty:ty),*) => {$(
+        fn $method(self, _: $ty) -> Result<(), serde::de::value::Error> {
+            Ok(())
+        }
+    )*};
+}
+impl serde::Serializer for SkipSome {
+    type Ok = ();
+    type Error = serde::de::value::Error;
+    type SerializeSeq = serde::ser::Impossible<(), Self::Error>;
+    type SerializeTuple = serde::ser::Impossible<(), Self::Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<(), Self::Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<(), Self::Error>;
+    type SerializeMap = serde::ser::Impossible<(), Self::Error>;
+    type SerializeStruct = serde::ser::Impossible<(), Self::Error>;
+    type SerializeStructVariant = serde::ser::Impossible<(), Self::Error>;
+
+    skip_leaves!(
+        serialize_bool: bool, serialize_i8: i8, serialize_i16: i16, serialize_i32: i32,
+        serialize_i64: i64, serialize_u8: u8, serialize_u16: u16, serialize_u32: u32,
+        serialize_u64: u64, serialize_f32: f32, serialize_f64: f64, serialize_char: char,
+        serialize_str: &str, serialize_bytes: &[u8], serialize_unit_struct: &'static str
+    );
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn serialize_unit_variant(self, _: &str, _: u32, _: &str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn tee_skipped_readability() {
+    let addr: std::net::Ipv4Addr = "127.0.0.1".parse().unwrap();
+    let ((), ast) = Some(addr)
+        .serialize(TeeSerializer::new(SkipSome))
+        .map_err(|e| e.error)
+        .unwrap();
+    assert_eq!(text::to_string(&ast), "Some((u8 127, u8 0, u8 0, u8 1))");
+}
@@ -0,0 +1,314 @@
+use quickcheck::quickcheck;
+
+use serde_ast::{
+    ast::{Map, Seq, Struct, StructVariant, Tuple, TupleStruct, TupleVariant, XAst},
+    text::{self, ErrorKind},
+    to_ast, Ast,
+};
+
+fn roundtrip(ast: &Ast) -> String {
+    let text = text::to_string(ast);
+    let parsed = text::from_str(&text).unwrap_or_else(|e| panic!("parse `{text}`: {e}"));
+    assert_eq!(&parsed, ast, "roundtrip `{text}`");
+    text
+}
+
+fn boxed(ast: Ast) -> Box<Ast> {
+    Box::new(ast)
+}
+
+#[test]
+fn text_leaves() {
+    let cases = [
+        (XAst::Bool(true), "true"),
+        (XAst::I8(-5), "i8 -5"),
+        (XAst::I16(-300), "i16 -300"),
+        (XAst::I32(7), "i32 7"),
+        (XAst::I64(i64::MIN), "i64 -9223372036854775808"),
+        (
+            XAst::I128(i128::MIN),
+            "i128 -170141183460469231731687303715884105728",
+        ),
+        (XAst::U8(3), "u8 3"),
+        (XAst::U16(80), "u16 80"),
+        (XAst::U32(1), "u32 1"),
+        (XAst::U64(u64::MAX), "u64 18446744073709551615"),
+        (
+            XAst::U128(u128::MAX),
+            "u128 340282366920938463463374607431768211455",
+        ),
+        (XAst::F32(1.5), "f32 1.5"),
+        (XAst::F64(-0.0), "f64 -0.0"),
+        (XAst::F64(f64::INFINITY), "f64 inf"),
+        (XAst::F64(1e-300), "f64 1e-300"),
+        (XAst::Char('\''), r"'\''"),
+        (XAst::Char('ł'), "'ł'"),
+        (
+            XAst::Str("a \"b\"\n\u{7}".to_string()),
+            r#""a \"b\"\n\u{7}""#,
+        ),
+        (XAst::Bytes(vec![0, b'a', 255, b'"']), r#"b"\x00a\xff\"""#),
+        (XAst::None, "None"),
+        (XAst::Some(boxed(XAst::Unit)), "Some(())"),
+        (XAst::Unit, "()"),
+        (XAst::UnitStruct("Unit".into()), "Unit"),
+        (
+            XAst::UnitVariant {
+                name: "Mode".into(),
+                variant_index: 2,
+                variant: "Off".into(),
+            },
+            "Mode::Off@2",
+        ),
+    ];
+    for (ast, expected) in cases {
+        assert_eq!(roundtrip(&ast), expected);
+    }
+}
+
+#[test]
+fn text_nan() {
+    let text = text::to_string(&XAst::F32(f32::NAN));
+    assert_eq!(text, format!("f32 {:#x}", f32::NAN.to_bits()));
+    let XAst::F32(parsed) = text::from_str(&text).unwrap() else {
+        panic!("expected f32");
+    };
+    assert_eq!(parsed.to_bits(), f32::NAN.to_bits());
+}
+
+#[test]
+fn text_containers() {
+    let cases = [
+        (
+            XAst::NewtypeStruct {
+                name: "Id".into(),
+                value: boxed(XAst::U8(1)),
+            },
+            "Id(u8 1)",
+        ),
+        (
+            XAst::NewtypeVariant {
+                name: "Option".into(),
+                variant_index: 1,
+                variant: "Some".into(),
+                value: boxed(XAst::U8(3)),
+            },
+            "Option::`Some`@1(u8 3)",
+        ),
+        (
+            XAst::Seq {
+                len: Some(1),
+                ops: vec![Seq::Element {
+                    value: boxed(XAst::U8(1)),
+                }],
+            },
+            "[u8 1]",
+        ),
+        (
+            XAst::Seq {
+                len: None,
+                ops: vec![],
+            },
+            "[; len none]",
+        ),
+        (
+            XAst::Tuple {
+                len: 0,
+                ops: vec![],
+            },
+            "(,)",
+        ),
+        (
+            XAst::Tuple {
+                len: 1,
+                ops: vec![Tuple::Element {
+                    value: boxed(XAst::Unit),
+                }],
+            },
+            "((),)",
+        ),
+        (
+            XAst::TupleStruct {
+                name: "Pair".into(),
+                len: 1,
+                ops: vec![TupleStruct::Field {
+                    value: boxed(XAst::Bool(false)),
+                }],
+            },
+            "Pair(false,)",
+        ),
+        (
+            XAst::TupleStruct {
+                name: "Empty".into(),
+                len: 3,
+                ops: vec![],
+            },
+            "Empty(; len 3)",
+        ),
+        (
+            XAst::TupleVariant {
+                name: "E".into(),
+                variant_index: 0,
+                variant: "T".into(),
+                len: 2,
+                ops: vec![
+                    TupleVariant::Field {
+                        value: boxed(XAst::U8(1)),
+                    },
+                    TupleVariant::Field {
+                        value: boxed(XAst::U8(2)),
+                    },
+                ],
+            },
+            "E::T@0(u8 1, u8 2)",
+        ),
+        (
+            XAst::Map {
+                len: Some(5),
+                ops: vec![
+                    Map::Key {
+                        key: boxed(XAst::Str("a".to_string())),
+                    },
+                    Map::Value {
+                        value: boxed(XAst::U8(1)),
+                    },
+                    Map::Key {
+                        key: boxed(XAst::Str("b".to_string())),
+                    },
+                    Map::Value {
+                        value: boxed(XAst::U8(2)),
+                    },
+                    Map::Key {
+                        key: boxed(XAst::Str("c".to_string())),
+                    },
+                ],
+            },
+            r#"{ "a" => u8 1, "b" => u8 2, "c" => _; len 5 }"#,
+        ),
+        (
+            XAst::Map {
+                len: Some(0),
+                ops: vec![],
+            },
+            "{}",
+        ),
+        (
+            XAst::Struct {
+                name: "my-struct".into(),
+                len: 1,
+                ops: vec![
+                    Struct::Field {
+                        key: "skip".into(),
+                        value: boxed(XAst::None),
+                    },
+                    Struct::Skip { key: "b".into() },
+                ],
+            },
+            "`my-struct` { `skip`: None, skip b }",
+        ),
+        (
+            XAst::StructVariant {
+                name: "".into(),
+                variant_index: 7,
+                variant: "V".into(),
+                len: 4,
+                ops: vec![StructVariant::Field {
+                    key: "a".into(),
+                    value: boxed(XAst::Char('a')),
+                }],
+            },
+            "``::V@7 { a: 'a'; len 4 }",
+        ),
+    ];
+    for (ast, expected) in cases {
+        assert_eq!(roundtrip(&ast), expected);
+    }
+}
+
+#[test]
+fn text_parse() {
+    let ast = text::from_str(
+        "Config {\n  name: \"example\",\n  mode: Mode::Off,\n  ports: [u16 22, u16 80,],\n}",
+    )
+    .unwrap();
+    let expected = XAst::Struct {
+        name: "Config".into(),
+        len: 3,
+        ops: vec![
+            Struct::Field {
+                key: "name".into(),
+                value: boxed(XAst::Str("example".to_string())),
+            },
+            Struct::Field {
+                key: "mode".into(),
+                value: boxed(XAst::UnitVariant {
+                    name: "Mode".into(),
+                    variant_index: 0,
+                    variant: "Off".into(),
+                }),
+            },
+            Struct::Field {
+                key: "ports".into(),
+                value: boxed(XAst::Seq {
+                    len: Some(2),
+                    ops: vec![
+                        Seq::Element {
+                            value: boxed(XAst::U16(22)),
+                        },
+                        Seq::Element {
+                            value: boxed(XAst::U16(80)),
+                        },
+                    ],
+                }),
+            },
+        ],
+    };
+    assert_eq!(ast, expected);
+    assert_eq!(
+        "Mode::Off".parse::<Ast>().unwrap(),
+        XAst::UnitVariant {
+            name: "Mode".into(),
+            variant_index: 0,
+            variant: "Off".into(),
+        }
+    );
+}
+
+#[test]
+fn text_errors() {
+    let error = text::from_str("[u8 1,\n  u8 300]").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 6));
+    assert_eq!(error.to_string(), "invalid u8 literal `300` at 2:6");
+
+    let error = text::from_str("(u8 1)").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ErrorKind::Unexpected { found: ')', .. }
+    ));
+
+    let error = text::from_str("\"\\q\"").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::InvalidEscape("\\q".to_string()));
+
+    let error = text::from_str("{ _ => _ }").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::EmptyEntry);
+
+    let error = text::from_str("[true").unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UnexpectedEnd("`]`"));
+
+    let error = text::from_str("true false").unwrap_err();
+    assert!(matches!(
+        error.kind,
+        ErrorKind::Unexpected { found: 'f', .. }
+    ));
+}
+
+type Example = (String, Option<char>, (i64, u8, f64), Vec<u8>);
+
+quickcheck! {
+    fn text_roundtrip(example: Vec<Example>) -> bool {
+        let ast = to_ast(&example).expect("serialize to_ast");
+        let text = text::to_string(&ast);
+        // compare printed, NaN is not equal to itself
+        text::to_string(&text::from_str(&text).expect("parse text")) == text
+    }
+}
@@ -1,6 +1,8 @@
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-use serde_ast::{to_ast, Ast};
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+
+use serde_ast::{from_ast, to_ast, Ast};
 
 #[derive(Serialize, Deserialize)]
 struct Example {
@@ -46,3 +48,134 @@ fn json_struct_nested() {
     let ast = to_ast(&example).expect("serialize to_ast");
     assert_eq!(ast_to_json(&ast), expected);
 }
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Skipping {
+    a: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    b: Option<String>,
+    c: Option<char>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Variants {
+    Unit,
+    Newtype(u16),
+    Tuple(i8, String),
+    Struct { x: f32, y: Option<bool> },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Wrapper(Vec<Variants>);
+
+#[test]
+fn from_ast_struct_nested() {
+    let example = Example {
+        hello: "World".to_string(),
+        nested: Nested { a: 100, b: 42 },
+        array: [1, 2, 3],
+        vec: vec![String::from("hello"), String::from("world")],
+        vec2: vec![-1, 0, 1],
+    };
+
+    let ast = to_ast(&example).expect("serialize to_ast");
+    let output: Example = from_ast(&ast).expect("deserialize from_ast");
+    assert_eq!(
+        ast_to_json(&to_ast(&output).expect("to_ast")),
+        ast_to_json(&ast)
+    );
+}
+
+#[test]
+fn from_ast_struct_skip() {
+    let example = Skipping {
+        a: 1,
+        b: None,
+        c: Some('c'),
+    };
+
+    let ast = to_ast(&example).expect("serialize to_ast");
+    let output: Skipping = from_ast(&ast).expect("deserialize from_ast");
+    assert_eq!(output, example);
+}
+
+#[test]
+fn from_ast_enum_variants() {
+    let example = Wrapper(vec![
+        Variants::Unit,
+        Variants::Newtype(7),
+        Variants::Tuple(-3, "three".to_string()),
+        Variants::Struct { x: 1.5, y: None },
+    ]);
+
+    let ast = to_ast(&example).expect("serialize to_ast");
+    let output: Wrapper = from_ast(&ast).expect("deserialize from_ast");
+    assert_eq!(output, example);
+}
+
+#[test]
+fn from_ast_map() {
+    let example: BTreeMap<u32, Option<String>> = [(1, Some("one".to_string())), (2, None)]
+        .into_iter()
+        .collect();
+
+    let ast = to_ast(&example).expect("serialize to_ast");
+    let output = BTreeMap::<u32, Option<String>>::deserialize(ast.into_deserializer())
+        .expect("deserialize IntoDeserializer");
+    assert_eq!(output, example);
+}
+
+#[test]
+fn json_to_ast_enum_variants() {
+    let example = Wrapper(vec![
+        Variants::Unit,
+        Variants::Newtype(7),
+        Variants::Tuple(-3, "three".to_string()),
+        Variants::Struct { x: 1.5, y: None },
+    ]);
+    let json = serde_json::to_string(&example).expect("serde_json::to_string");
+
+    let ast: Ast = serde_json::from_str(&json).expect("serde_json::from_str");
+    assert_eq!(ast_to_json(&ast), json);
+    let output: Wrapper = from_ast(&ast).expect("deserialize from_ast");
+    assert_eq!(output, example);
+}
+
+#[test]
+fn ast_from_ast_enum_variants() {
+    let example = Variants::Struct { x: 1.5, y: None };
+
+    let ast = to_ast(&example).expect("serialize to_ast");
+    let output: Ast = from_ast(&ast).expect("deserialize from_ast");
+    assert!(matches!(
+        &output,
+        Ast::StructVariant {
+            name,
+            variant_index: 0,
+            variant,
+            len: 2,
+            ..
+        } if name.is_empty() && variant == "Struct"
+    ));
+    assert_eq!(ast_to_json(&output), ast_to_json(&ast));
+}
+
+#[test]
+fn json_runtime_names() {
+    let keys = ["a", "b"].map(String::from);
+    let ast = Ast::Struct {
+        name: String::from("Runtime").into(),
+        len: keys.len(),
+        ops: keys
+            .iter()
+            .map(|key| serde_ast::ast::Struct::Field {
+                key: key.clone().into(),
+                value: Box::new(Ast::U32(1)),
+            })
+            .collect(),
+    };
+    assert_eq!(ast_to_json(&ast), r#"{"a":1,"b":1}"#);
+
+    let output: Nested = from_ast(&ast).expect("deserialize from_ast");
+    assert_eq!((output.a, output.b), (1, 1));
+}
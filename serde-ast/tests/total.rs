@@ -0,0 +1,102 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    hash::{BuildHasher, RandomState},
+};
+
+use quickcheck::quickcheck;
+
+use serde_ast::{
+    normalize::{Pass, SortMapEntries},
+    text, to_ast,
+    total::Total,
+    Ast,
+};
+
+fn total(text: &str) -> Total {
+    Total(text::from_str(text).expect("parse text"))
+}
+
+#[test]
+fn total_floats() {
+    assert_eq!(total("f64 NaN"), total("f64 NaN"));
+    assert_ne!(total("f64 0"), total("f64 -0"));
+    assert!(total("f64 -0") < total("f64 0"));
+    assert!(total("f32 inf") < total("f32 NaN"));
+    assert_ne!(total("f32 1"), total("f64 1"));
+}
+
+#[test]
+fn total_order() {
+    let sorted: Vec<_> = [
+        "{ u8 1 => () }",
+        "[u8 2, u8 1]",
+        "[u8 10]",
+        "A { a: u8 1, skip b }",
+        "A { a: u8 1 }",
+        "Some(u8 2)",
+        "None",
+        "u16 1",
+        "u8 9",
+        "true",
+    ]
+    .into_iter()
+    .map(total)
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .map(|Total(ast)| text::to_string(&ast))
+    .collect();
+    assert_eq!(
+        sorted,
+        vec![
+            "true",
+            "u8 9",
+            "u16 1",
+            "None",
+            "Some(u8 2)",
+            "[u8 10]",
+            "[u8 2, u8 1]",
+            "{ u8 1 => () }",
+            "A { a: u8 1 }",
+            "A { a: u8 1, skip b }",
+        ]
+    );
+}
+
+#[test]
+fn total_dedup() {
+    let payloads = [(1, f64::NAN), (2, 0.0), (1, f64::NAN), (2, -0.0)];
+    let unique: HashSet<_> = payloads
+        .iter()
+        .map(|payload| Total(to_ast(payload).unwrap()))
+        .collect();
+    assert_eq!(unique.len(), 3);
+}
+
+#[test]
+fn total_sort_map_entries() {
+    let map: HashMap<_, _> = [((2, "b"), 'b'), ((10, "a"), 'c'), ((2, "a"), 'a')]
+        .into_iter()
+        .collect();
+    let mut ast = to_ast(&map).unwrap();
+    SortMapEntries.apply(&mut ast);
+    assert_eq!(
+        text::to_string(&ast),
+        r#"{ (i32 2, "a") => 'a', (i32 2, "b") => 'b', (i32 10, "a") => 'c' }"#
+    );
+}
+
+fn hash(ast: &Ast, state: &RandomState) -> u64 {
+    state.hash_one(Total(ast.clone()))
+}
+
+quickcheck! {
+    fn total_consistent(a: Vec<(Option<f32>, String)>, b: Vec<(Option<f32>, String)>) -> bool {
+        let (a, b) = (to_ast(&a).unwrap(), to_ast(&b).unwrap());
+        let state = RandomState::new();
+        let same = a.total_cmp(&b).is_eq();
+        a.total_cmp(&a).is_eq()
+            && a.total_cmp(&b) == b.total_cmp(&a).reverse()
+            && same == (text::to_string(&a) == text::to_string(&b))
+            && (!same || hash(&a, &state) == hash(&b, &state))
+    }
+}
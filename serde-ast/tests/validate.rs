@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use quickcheck::quickcheck;
+use serde::{ser::SerializeStruct, Serialize};
+
+use serde_ast::{
+    ast::{Map, Struct, XAst},
+    ser::ErrorKind,
+    text, to_ast,
+    validate::{Checker, ViolationKind},
+    Readability, Serializer,
+};
+
+fn violations(text: &str) -> Vec<String> {
+    text::from_str(text)
+        .expect("parse text")
+        .validate()
+        .into_iter()
+        .map(|violation| violation.to_string())
+        .collect::<Vec<_>>()
+}
+
+#[test]
+fn validate_valid() {
+    #[derive(Serialize)]
+    struct Example {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skipped: Option<u8>,
+        map: BTreeMap<&'static str, (u8, char)>,
+    }
+    let ast = to_ast(&Example {
+        skipped: None,
+        map: BTreeMap::from([("a", (1, 'b'))]),
+    })
+    .unwrap();
+    assert_eq!(ast.validate(), vec![]);
+}
+
+#[test]
+fn validate_violations() {
+    let key = |key: &str| Map::<()>::Key {
+        key: Box::new(XAst::Str(key.to_string())),
+    };
+    let value = || Map::<()>::Value {
+        value: Box::new(XAst::Unit),
+    };
+    let map = XAst::Map {
+        len: Some(1),
+        ops: vec![key("a"), key("b"), value(), value()],
+    };
+    let kinds: Vec<_> = map.validate().into_iter().map(|v| v.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![ViolationKind::UnpairedKey, ViolationKind::UnpairedValue]
+    );
+
+    let skipped = XAst::<()>::Struct {
+        name: "Example".into(),
+        len: 2,
+        ops: vec![
+            Struct::Field {
+                key: "a".into(),
+                value: Box::new(XAst::Unit),
+            },
+            Struct::Skip { key: "a".into() },
+        ],
+    };
+    let kinds: Vec<_> = skipped.validate().into_iter().map(|v| v.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ViolationKind::DuplicateField("a".into()),
+            ViolationKind::Len { len: 2, actual: 1 },
+        ]
+    );
+}
+
+#[test]
+fn validate_paths() {
+    assert_eq!(
+        violations(r#"{ "a" => [(u8 1,); len 2], u8 2 => Some(Empty {; len 1}) }"#),
+        vec![
+            "declared len 2 but serialized 1 at `/a`",
+            "declared len 1 but serialized 0 at `/2`",
+        ]
+    );
+}
+
+#[test]
+fn validate_checker() {
+    let mut checker = Checker::new(Some(1));
+    assert_eq!(checker.key(), Ok(()));
+    assert_eq!(checker.end(), Err(ViolationKind::UnpairedKey));
+    assert_eq!(checker.key(), Err(ViolationKind::UnpairedKey));
+    assert_eq!(checker.value(), Ok(()));
+    assert_eq!(checker.end(), Ok(()));
+}
+
+struct Lying;
+impl Serialize for Lying {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut s = serializer.serialize_struct("Lying", 2)?;
+        s.serialize_field("a", &1)?;
+        s.skip_field("b")?;
+        s.end()
+    }
+}
+
+#[test]
+fn validate_strict() {
+    let serializer = Serializer::new(Readability::HumanReadable);
+    let ast = vec![Lying].serialize(serializer.clone()).unwrap();
+    assert_eq!(ast.validate().len(), 1);
+
+    let error = vec![Lying].serialize(serializer.strict(true)).unwrap_err();
+    assert_eq!(
+        error.kind,
+        ErrorKind::InvalidProtocol("declared len 2 but serialized 1".to_string())
+    );
+    assert_eq!(
+        error.to_string(),
+        "invalid protocol: declared len 2 but serialized 1 at [0]"
+    );
+}
+
+type Example = (
+    String,
+    Option<char>,
+    (i64, u8, f32),
+    Vec<u8>,
+    BTreeMap<i16, bool>,
+);
+
+quickcheck! {
+    fn validate_serialized(example: Vec<Example>) -> bool {
+        let serializer = Serializer::new(Readability::HumanReadable).strict(true);
+        example.serialize(serializer).expect("strict serialize").validate().is_empty()
+    }
+}
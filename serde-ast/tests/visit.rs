@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use serde_ast::{
+    ast::{Name, Struct, XAst},
+    fold::{self, Fold},
+    to_ast,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+};
+
+#[derive(Serialize)]
+struct Example {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    tags: BTreeMap<String, u32>,
+    nested: Option<Box<Example>>,
+}
+
+fn example() -> Example {
+    Example {
+        name: "outer".to_string(),
+        comment: None,
+        tags: [("a".to_string(), 1), ("b".to_string(), 2)]
+            .into_iter()
+            .collect(),
+        nested: Some(Box::new(Example {
+            name: "inner".to_string(),
+            comment: Some("hello".to_string()),
+            tags: BTreeMap::new(),
+            nested: None,
+        })),
+    }
+}
+
+#[test]
+fn visit_counts() {
+    #[derive(Default)]
+    struct Count {
+        strs: Vec<String>,
+        keys: usize,
+        skips: usize,
+    }
+    impl<'ast, X: 'ast> Visit<'ast, X> for Count {
+        fn visit_str(&mut self, v: &'ast str) {
+            self.strs.push(v.to_string());
+        }
+        fn visit_map_key(&mut self, key: &'ast XAst<X>) {
+            self.keys += 1;
+            visit::visit_map_key(self, key);
+        }
+        fn visit_struct_skip(&mut self, _key: &'ast str) {
+            self.skips += 1;
+        }
+    }
+
+    let ast = to_ast(&example()).expect("serialize to_ast");
+    let mut count = Count::default();
+    count.visit_ast(&ast);
+    assert_eq!(count.strs, ["outer", "a", "b", "inner", "hello"]);
+    assert_eq!(count.keys, 2);
+    assert_eq!(count.skips, 1);
+}
+
+#[test]
+fn visit_mut_increment() {
+    struct Increment;
+    impl<X> VisitMut<X> for Increment {
+        fn visit_map_value_mut(&mut self, value: &mut XAst<X>) {
+            if let XAst::U32(v) = value {
+                *v += 10;
+            }
+            visit_mut::visit_map_value_mut(self, value);
+        }
+    }
+
+    let mut ast = to_ast(&example()).expect("serialize to_ast");
+    Increment.visit_ast_mut(&mut ast);
+    let output = serde_json::to_string(&ast).expect("serde_json::to_string");
+    assert_eq!(
+        output,
+        r#"{"name":"outer","tags":{"a":11,"b":12},"nested":{"name":"inner","comment":"hello","tags":{},"nested":null}}"#
+    );
+}
+
+#[test]
+fn fold_struct_fields() {
+    struct DropComments;
+    impl<X> Fold<X> for DropComments {
+        fn fold_struct_field(&mut self, key: Name, value: XAst<X>) -> Struct<X> {
+            match key.as_ref() {
+                "comment" => Struct::Skip { key },
+                _ => fold::fold_struct_field(self, key, value),
+            }
+        }
+    }
+
+    let ast = DropComments.fold_ast(to_ast(&example()).expect("serialize to_ast"));
+    let output = serde_json::to_string(&ast).expect("serde_json::to_string");
+    assert_eq!(
+        output,
+        r#"{"name":"outer","tags":{"a":1,"b":2},"nested":{"name":"inner","tags":{},"nested":null}}"#
+    );
+}